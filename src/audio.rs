@@ -0,0 +1,145 @@
+//! A module for downloading the pronunciation recordings
+//! the Free Dictionary API links to, and playing them.
+//!
+
+use crate::{cache, http::Client};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Grabs the region of a recording from its file name,
+/// i.e.: `uk` for `.../hello-uk.mp3`.
+pub fn region(url: &str) -> Option<String> {
+    let file_name = url.rsplit('/').next()?;
+    let stem = file_name.split('.').next()?;
+    let (_, region) = stem.rsplit_once('-')?;
+    (!region.is_empty() && region.chars().all(|c| c.is_ascii_alphabetic()))
+        .then(|| region.to_lowercase())
+}
+
+/// Works out where a recording gets saved: the output path itself, a file
+/// named after the recording inside the output directory, or the cache.
+fn save_path(url: &str, output: Option<&str>) -> Result<PathBuf, Error> {
+    let file_name = url
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or("pronunciation.mp3");
+    match output {
+        Some(output) if Path::new(output).is_dir() => Ok(Path::new(output).join(file_name)),
+        Some(output) => Ok(PathBuf::from(output)),
+        None => {
+            let dir = cache::cache_dir()
+                .ok_or("No cache directory was found, please set XDG_CACHE_HOME")?
+                .join("audio");
+            fs::create_dir_all(&dir)?;
+            Ok(dir.join(file_name))
+        }
+    }
+}
+
+/// Downloads the recording and returns where it was saved.
+pub async fn download(client: &Client, url: &str, output: Option<&str>) -> Result<PathBuf, Error> {
+    let path = save_path(url, output)?;
+    let response = client.get(url).await?;
+    if !response.is_success() {
+        return Err(format!("The server responded with HTTP {}", response.status).into());
+    }
+    fs::write(&path, response.body)?;
+    Ok(path)
+}
+
+/// Plays the recording with the given player command, i.e.: `mpv --no-video`.
+///
+/// The command is split into words the way a shell would, so its words
+/// can be quoted or escaped, but it isn't run through a shell: pipes,
+/// redirections and variables aren't expanded.
+pub fn play(player: &str, path: &Path) -> Result<(), Error> {
+    let words = split_command(player)?;
+    let (program, args) = words.split_first().ok_or("The player command is empty")?;
+    let status = Command::new(program).args(args).arg(path).status()?;
+    if !status.success() {
+        return Err(format!("{program} exited with {status}").into());
+    }
+    Ok(())
+}
+
+/// Splits a command into words on whitespace, keeping the whitespace of
+/// words in single or double quotes, or escaped with a backslash.
+fn split_command(command: &str) -> Result<Vec<String>, Error> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = command.chars();
+    while let Some(chr) = chars.next() {
+        match (quote, chr) {
+            (Some(open), _) if chr == open => quote = None,
+            (None | Some('"'), '\\') => {
+                let escaped = chars.next().ok_or("The player command ends with a \\")?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            (None, '\'' | '"') => {
+                quote = Some(chr);
+                word.get_or_insert_with(String::new);
+            }
+            (None, _) if chr.is_whitespace() => words.extend(word.take()),
+            _ => word.get_or_insert_with(String::new).push(chr),
+        }
+    }
+    if quote.is_some() {
+        return Err("The player command has an unclosed quote".into());
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn regions_should_be_parsed() {
+        assert_eq!(
+            Some("uk".to_string()),
+            region("https://api.dictionaryapi.dev/media/pronunciations/en/hello-uk.mp3")
+        );
+        assert_eq!(
+            Some("au".to_string()),
+            region("https://example.com/hello-AU.ogg")
+        );
+        assert_eq!(None, region("https://example.com/hello.mp3"));
+        assert_eq!(None, region("https://example.com/hello-1.mp3"));
+    }
+
+    #[test]
+    fn player_commands_should_be_split() {
+        let split = |command| split_command(command).expect("Command should split");
+        assert_eq!(vec!["mpv", "--no-video"], split("  mpv --no-video "));
+        assert_eq!(
+            vec!["/opt/my player/play", "--title", "it's", ""],
+            split(r#""/opt/my player/play" --title it\'s ''"#)
+        );
+        assert_eq!(vec!["a b\\c"], split(r"a\ b'\c'"));
+        assert!(split_command("mpv 'unclosed").is_err());
+    }
+
+    #[test]
+    fn recordings_should_be_saved_in_the_output_dir() {
+        let dir = env::temp_dir();
+        let url = "https://example.com/hello-us.mp3";
+        let output = dir.to_string_lossy();
+        assert_eq!(
+            dir.join("hello-us.mp3"),
+            save_path(url, Some(&output)).expect("Path should be found")
+        );
+        assert_eq!(
+            PathBuf::from("hello.mp3"),
+            save_path(url, Some("hello.mp3")).expect("Path should be found")
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! A module containing the on-disk cache of the online providers'
+//! responses, kept under `$XDG_CACHE_HOME/rdict` (or `~/.cache/rdict`).
+//!
+
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+
+/// How long responses are used for before being requested again.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// How many bytes of responses are kept before evicting the oldest.
+pub const DEFAULT_MAX_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Finds the directory rdict caches files in, following the XDG base directory spec.
+pub fn cache_dir() -> Option<PathBuf> {
+    let cache_home = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| Path::new(dir).is_absolute())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+    Some(cache_home.join("rdict"))
+}
+
+/// What a response is cached under: the provider it came
+/// from, the language it is in and the word it defines.
+#[derive(Debug, PartialEq)]
+pub struct CacheKey {
+    provider: &'static str,
+    lang: String,
+    word: String,
+}
+
+impl CacheKey {
+    /// Builds a key, normalizing the word so that i.e.: `Hello ` and `hello` share it.
+    pub fn new(provider: &'static str, lang: &str, word: &str) -> Self {
+        Self {
+            provider,
+            lang: lang.to_lowercase(),
+            word: word
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ")
+                .to_lowercase(),
+        }
+    }
+
+    /// Grabs the path of the entry relative to the cache, escaping
+    /// anything that isn't safe to use in a file name.
+    fn path(&self) -> PathBuf {
+        let escape = |part: &str| -> String {
+            part.bytes()
+                .map(|byte| match byte {
+                    b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' => (byte as char).to_string(),
+                    _ => format!("%{byte:02X}"),
+                })
+                .collect()
+        };
+        Path::new(self.provider)
+            .join(escape(&self.lang))
+            .join(format!("{}.json", escape(&self.word)))
+    }
+}
+
+/// A cached response, along with how long ago it was stored.
+#[derive(Debug)]
+pub struct CachedResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+    pub age: Duration,
+}
+
+/// A struct for keeping responses on disk, evicting the
+/// oldest ones once they take up too much space.
+///
+/// Every entry starts with the status of its response on a line
+/// of its own, followed by the body of the response.
+pub struct Cache {
+    dir: PathBuf,
+    ttl: Duration,
+    max_size: u64,
+    /// How many bytes the entries take up, once counted.
+    size: Mutex<Option<u64>>,
+}
+
+/// Tells apart the temporary files entries are written to.
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+impl Cache {
+    pub fn new(dir: PathBuf, ttl: Duration, max_size: u64) -> Self {
+        Self {
+            dir,
+            ttl,
+            max_size,
+            size: Mutex::new(None),
+        }
+    }
+
+    /// Checks whether a cached response is still fresh.
+    pub fn is_fresh(&self, cached: &CachedResponse) -> bool {
+        cached.age < self.ttl
+    }
+
+    /// Grabs the cached response of the key, however old it is.
+    pub fn get(&self, key: &CacheKey) -> Option<CachedResponse> {
+        let path = self.dir.join(key.path());
+        let modified = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()?;
+        let entry = fs::read(&path).ok()?;
+        let (status, body) = parse_entry(entry)?;
+        Some(CachedResponse {
+            status,
+            body,
+            age: SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Stores the response of the key, then makes room if needed.
+    ///
+    /// The entry is written to a temporary file first and moved in place
+    /// once complete, so that concurrent readers never see half of it.
+    pub fn put(&self, key: &CacheKey, status: u16, body: &[u8]) -> io::Result<()> {
+        let path = self.dir.join(key.path());
+        let Some(parent) = path.parent() else {
+            return Ok(());
+        };
+        fs::create_dir_all(parent)?;
+        let temp = parent.join(format!(
+            ".{}-{}.tmp",
+            std::process::id(),
+            TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut entry = format!("{status}\n").into_bytes();
+        entry.extend_from_slice(body);
+        let written = File::options()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .and_then(|mut file| file.write_all(&entry));
+        let replaced = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+        if let Err(e) = written.and_then(|_| fs::rename(&temp, &path)) {
+            let _ = fs::remove_file(&temp);
+            return Err(e);
+        }
+
+        let mut size = self.size.lock().expect("Lock isn't poisoned");
+        let total = match *size {
+            Some(total) => total.saturating_sub(replaced) + entry.len() as u64,
+            None => self.count()?,
+        };
+        *size = Some(if total > self.max_size {
+            self.evict()?
+        } else {
+            total
+        });
+        Ok(())
+    }
+
+    /// Counts the bytes the entries take up.
+    fn count(&self) -> io::Result<u64> {
+        Ok(self.entries()?.iter().map(|(_, len, _)| len).sum())
+    }
+
+    /// Grabs the modification time, size and path of every entry.
+    fn entries(&self) -> io::Result<Vec<(SystemTime, u64, PathBuf)>> {
+        let mut entries = vec![];
+        let mut dirs = vec![self.dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                if metadata.is_dir() {
+                    dirs.push(entry.path());
+                } else if !entry.file_name().to_string_lossy().ends_with(".tmp") {
+                    entries.push((metadata.modified()?, metadata.len(), entry.path()));
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Removes the oldest entries until the cache fits in its maximum
+    /// size, coming back with the size left.
+    fn evict(&self) -> io::Result<u64> {
+        let mut entries = self.entries()?;
+        let mut size: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        entries.sort();
+        for (_, len, path) in entries.iter() {
+            if size <= self.max_size {
+                break;
+            }
+            // Another process may have evicted it already
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => size -= len,
+            }
+        }
+        Ok(size)
+    }
+}
+
+/// Splits an entry into the status and body of its response.
+fn parse_entry(entry: Vec<u8>) -> Option<(u16, Vec<u8>)> {
+    let end = entry.iter().position(|&byte| byte == b'\n')?;
+    let status = std::str::from_utf8(&entry[..end]).ok()?.parse().ok()?;
+    Some((status, entry[end + 1..].to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn test_cache(name: &str, ttl: Duration, max_size: u64) -> (TempDir, Cache) {
+        let dir = TempDir::new(&format!("cache-{name}"));
+        let cache = Cache::new(dir.to_path_buf(), ttl, max_size);
+        (dir, cache)
+    }
+
+    #[test]
+    fn keys_should_be_normalized() {
+        assert_eq!(
+            CacheKey::new("free-dictionary", "en", "ice cream"),
+            CacheKey::new("free-dictionary", "EN", "  Ice   Cream ")
+        );
+        assert_eq!(
+            Path::new("free-dictionary/pt-br/ice%20cream%2F.json"),
+            CacheKey::new("free-dictionary", "pt-BR", "ice cream/").path()
+        );
+    }
+
+    #[test]
+    fn responses_should_be_cached() {
+        let (dir, cache) = test_cache("ttl", DEFAULT_TTL, DEFAULT_MAX_SIZE);
+        let key = CacheKey::new("urban-dictionary", "en", "faux");
+        assert!(cache.get(&key).is_none());
+
+        cache
+            .put(&key, 200, b"[]")
+            .expect("Cache should be writable");
+        let cached = cache.get(&key).expect("Response should be cached");
+        assert_eq!(b"[]".to_vec(), cached.body);
+        assert!(cache.is_fresh(&cached));
+        assert!(!Cache::new(dir.to_path_buf(), Duration::ZERO, DEFAULT_MAX_SIZE).is_fresh(&cached));
+
+        let missing = CacheKey::new("urban-dictionary", "en", "fawx");
+        cache
+            .put(&missing, 404, b"{}")
+            .expect("Cache should be writable");
+        let cached = cache.get(&missing).expect("Miss should be cached");
+        assert_eq!((404, b"{}".to_vec()), (cached.status, cached.body));
+
+        fs::write(dir.join(missing.path()), b"[]").expect("Cache should be writable");
+        assert!(cache.get(&missing).is_none());
+    }
+
+    #[test]
+    fn oldest_responses_should_be_evicted() {
+        let (dir, cache) = test_cache("eviction", DEFAULT_TTL, 25);
+        let now = SystemTime::now();
+        for (i, word) in ["a", "b", "c"].iter().enumerate() {
+            let key = CacheKey::new("free-dictionary", "en", word);
+            cache
+                .put(&key, 200, &[b'x'; 6])
+                .expect("Cache should be writable");
+            fs::File::options()
+                .write(true)
+                .open(dir.join(key.path()))
+                .and_then(|file| file.set_modified(now - Duration::from_secs(60 - i as u64)))
+                .expect("Modification time should be settable");
+        }
+
+        let cached = |word| cache.get(&CacheKey::new("free-dictionary", "en", word));
+        assert!(cached("a").is_none());
+        assert!(cached("b").is_some() && cached("c").is_some());
+    }
+}
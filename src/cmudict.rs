@@ -0,0 +1,258 @@
+//! A module containing the reader for the CMU Pronouncing Dictionary,
+//! which maps English words to their ARPAbet pronunciations.
+//!
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufRead, BufReader},
+};
+
+/// A struct holding every pronunciation of the CMU Pronouncing Dictionary.
+///
+/// Both the original uppercase file, with its `;;;` comments, and the
+/// lowercase one maintained by CMU Sphinx, with `word(2)` variants
+/// and `#` comments, are understood.
+pub struct Cmudict {
+    entries: BTreeMap<String, Vec<Vec<String>>>,
+}
+
+/// A word rhyming with another, along with its syllable count.
+#[derive(Debug, PartialEq)]
+pub struct Rhyme {
+    pub word: String,
+    pub syllables: usize,
+}
+
+impl Cmudict {
+    /// Loads the dictionary found at the given path.
+    pub fn new(path: &str) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Loads the dictionary from a reader, i.e.: an in-memory copy.
+    pub fn from_reader(mut reader: impl BufRead) -> io::Result<Self> {
+        let mut entries: BTreeMap<String, Vec<Vec<String>>> = BTreeMap::new();
+        let mut line = vec![];
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            // Older releases aren't quite UTF-8
+            let line = String::from_utf8_lossy(&line);
+            let line = line.split(" #").next().unwrap_or_default();
+            if line.starts_with(";;;") {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let Some(word) = fields.next() else {
+                continue;
+            };
+            let word = match word.find('(') {
+                Some(variant) if word.ends_with(')') => &word[..variant],
+                _ => word,
+            };
+            let phones: Vec<String> = fields.map(String::from).collect();
+            if !phones.is_empty() {
+                entries.entry(word.to_lowercase()).or_default().push(phones);
+            }
+        }
+        Ok(Self { entries })
+    }
+
+    /// Grabs every pronunciation of the word, as space-separated ARPAbet.
+    pub fn pronunciations(&self, word: &str) -> Vec<String> {
+        self.phones(word)
+            .iter()
+            .map(|phones| phones.join(" "))
+            .collect()
+    }
+
+    /// Grabs the number of syllables of the word's first pronunciation.
+    pub fn syllables(&self, word: &str) -> Option<usize> {
+        self.phones(word).first().map(|phones| syllables(phones))
+    }
+
+    /// Grabs the stress pattern of the word's first pronunciation,
+    /// i.e.: `010` for "banana".
+    pub fn stress(&self, word: &str) -> Option<String> {
+        self.phones(word).first().map(|phones| stress(phones))
+    }
+
+    /// Checks whether any pronunciation of the word has the given stress pattern.
+    pub fn matches_stress(&self, word: &str, pattern: &str) -> bool {
+        self.phones(word)
+            .iter()
+            .any(|phones| stress(phones) == pattern)
+    }
+
+    /// Grabs every word with a pronunciation of the given stress pattern.
+    pub fn words_with_stress(&self, pattern: &str) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|(_, pronunciations)| {
+                pronunciations
+                    .iter()
+                    .any(|phones| stress(phones) == pattern)
+            })
+            .map(|(word, _)| word.clone())
+            .collect()
+    }
+
+    /// Grabs the perfect rhymes of the word, which share every sound from
+    /// its last stressed vowel on, and its near rhymes, which share the
+    /// vowels but not every consonant.
+    pub fn rhymes(&self, word: &str) -> (Vec<Rhyme>, Vec<Rhyme>) {
+        let word = word.to_lowercase();
+        let tails: Vec<&[String]> = self
+            .phones(&word)
+            .iter()
+            .map(|phones| rhyme_tail(phones))
+            .collect();
+        let (mut perfect, mut near) = (vec![], vec![]);
+        if tails.is_empty() {
+            return (perfect, near);
+        }
+
+        for (other, pronunciations) in self.entries.iter() {
+            if *other == word {
+                continue;
+            }
+            let rhyme = |phones: &Vec<String>| Rhyme {
+                word: other.clone(),
+                syllables: syllables(phones),
+            };
+            let perfect_match = pronunciations
+                .iter()
+                .find(|phones| tails.iter().any(|tail| rhyme_tail(phones) == *tail));
+            if let Some(phones) = perfect_match {
+                perfect.push(rhyme(phones));
+                continue;
+            }
+            let near_match = pronunciations.iter().find(|phones| {
+                tails
+                    .iter()
+                    .any(|tail| is_near_rhyme(rhyme_tail(phones), tail))
+            });
+            if let Some(phones) = near_match {
+                near.push(rhyme(phones));
+            }
+        }
+        (perfect, near)
+    }
+
+    fn phones(&self, word: &str) -> &[Vec<String>] {
+        self.entries
+            .get(&word.trim().to_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Only vowels carry a stress marker in ARPAbet.
+fn is_vowel(phone: &str) -> bool {
+    phone.ends_with(|c: char| c.is_ascii_digit())
+}
+
+fn syllables(phones: &[String]) -> usize {
+    phones.iter().filter(|phone| is_vowel(phone)).count()
+}
+
+fn stress(phones: &[String]) -> String {
+    phones
+        .iter()
+        .filter_map(|phone| phone.chars().last().filter(char::is_ascii_digit))
+        .collect()
+}
+
+/// Grabs the sounds from the last primarily stressed vowel on, falling back
+/// to the last stressed vowel and then to the last vowel altogether.
+fn rhyme_tail(phones: &[String]) -> &[String] {
+    let last_with = |markers: &[char]| phones.iter().rposition(|phone| phone.ends_with(markers));
+    let start = last_with(&['1'])
+        .or_else(|| last_with(&['2']))
+        .or_else(|| last_with(&['0']))
+        .unwrap_or(0);
+    &phones[start..]
+}
+
+/// Two tails rhyme nearly when they have as many sounds and the same
+/// vowels, regardless of stress, i.e.: `AY1 M` and `AY1 N`.
+fn is_near_rhyme(tail: &[String], other: &[String]) -> bool {
+    let unstressed = |phone: &String| {
+        phone
+            .trim_end_matches(|c: char| c.is_ascii_digit())
+            .to_string()
+    };
+    let vowels = |tail: &[String]| -> Vec<String> {
+        tail.iter()
+            .filter(|phone| is_vowel(phone))
+            .map(unstressed)
+            .collect()
+    };
+    tail.len() == other.len() && vowels(tail) == vowels(other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CMUDICT: &str = ";;; # CMUdict  --  Major Version: 0.07
+BANANA  B AH0 N AE1 N AH0
+CLIMB  K L AY1 M
+LINE  L AY1 N
+RHYME  R AY1 M
+SUBLIME  S AH0 B L AY1 M
+time T AY1 M
+times T AY1 M Z
+tomato T AH0 M EY1 T OW2
+tomato(2) T AH0 M AA1 T OW2 # british
+";
+
+    fn cmudict() -> Cmudict {
+        Cmudict::from_reader(CMUDICT.as_bytes()).expect("Dictionary should load")
+    }
+
+    #[test]
+    fn cmudict_pronunciations_test() {
+        let dict = cmudict();
+        assert_eq!(
+            vec![
+                "T AH0 M EY1 T OW2".to_string(),
+                "T AH0 M AA1 T OW2".to_string()
+            ],
+            dict.pronunciations("Tomato")
+        );
+        assert_eq!(Some(3), dict.syllables("banana"));
+        assert_eq!(Some("010".to_string()), dict.stress("banana"));
+        assert!(dict.matches_stress("tomato", "012"));
+        assert!(!dict.matches_stress("time", "0"));
+        assert!(dict.pronunciations("cat").is_empty());
+        assert_eq!(None, dict.syllables("cat"));
+        assert_eq!(vec!["banana"], dict.words_with_stress("010"));
+        assert_eq!(vec!["sublime"], dict.words_with_stress("01"));
+        // Any pronunciation of the word can match
+        assert_eq!(vec!["tomato"], dict.words_with_stress("012"));
+    }
+
+    #[test]
+    fn cmudict_rhymes_test() {
+        let dict = cmudict();
+        let (perfect, near) = dict.rhymes("time");
+        let words = |rhymes: &[Rhyme]| -> Vec<String> {
+            rhymes.iter().map(|rhyme| rhyme.word.clone()).collect()
+        };
+
+        assert_eq!(vec!["climb", "rhyme", "sublime"], words(&perfect));
+        assert_eq!(
+            Rhyme {
+                word: "sublime".to_string(),
+                syllables: 2,
+            },
+            perfect[2]
+        );
+        assert_eq!(vec!["line"], words(&near));
+        assert_eq!((vec![], vec![]), dict.rhymes("cat"));
+    }
+}
@@ -1,9 +1,38 @@
-use ansi_term::Colour;
+use ansi_term::{Colour, Style};
 
 // TODO: Make these read from environment variables
 const YELLOW: Colour = Colour::RGB(255, 255, 128);
 const VIOLET: Colour = Colour::RGB(102, 0, 255);
 
-pub fn print_colored(msg: &str) -> ansi_term::ANSIGenericString<'_, str> {
-    YELLOW.bold().on(VIOLET).paint(format!(" {msg} "))
+/// Paints the message with the given style, or leaves it
+/// as plain text if colors are disabled.
+pub fn paint(style: Style, msg: &str, color: bool) -> String {
+    if color {
+        style.paint(msg).to_string()
+    } else {
+        msg.to_string()
+    }
+}
+
+pub fn print_colored(msg: &str, color: bool) -> ansi_term::ANSIGenericString<'_, str> {
+    let style = if color {
+        YELLOW.bold().on(VIOLET)
+    } else {
+        Style::new()
+    };
+    style.paint(format!(" {msg} "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_colors_should_be_plain() {
+        assert_ne!("word", paint(YELLOW.bold(), "word", true));
+        assert_ne!(" word ", print_colored("word", true).to_string());
+
+        assert_eq!("word", paint(YELLOW.bold(), "word", false));
+        assert_eq!(" word ", print_colored("word", false).to_string());
+    }
 }
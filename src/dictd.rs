@@ -0,0 +1,209 @@
+//! A module containing the reader for dictd databases,
+//! made out of a `.index` file and a `.dict` or `.dict.dz` file.
+//!
+
+use crate::dictzip::Dictzip;
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+};
+
+/// The headword holding the short name of the database.
+const SHORT_NAME: &str = "00-database-short";
+/// The headword marking databases whose headwords are matched
+/// using every character rather than only alphanumeric ones.
+const ALL_CHARS: &str = "00-database-allchars";
+
+/// A struct for looking words up in a dictd database.
+///
+/// A .index is a sorted list of lines, all of
+/// which contains three tab-separated fields:
+///     - headword
+///     - offset ;; the entry's offset in .dict, in base64
+///     - length ;; the entry's length in .dict, in base64
+pub struct Dictd {
+    pub name: String,
+    pub index: BTreeMap<String, Vec<(u64, usize)>>,
+    all_chars: bool,
+    file: Option<File>,
+    dictzip: Option<Dictzip>,
+}
+
+impl Dictd {
+    /// Opens the dictd database found at the given path.
+    ///
+    /// The path can either be the common prefix of the database
+    /// files (i.e.: `/usr/share/dictd/wn`), or the path of its
+    /// .index or .dict(.dz) file.
+    pub fn new(path: &str) -> io::Result<Self> {
+        let prefix = dictd_prefix(path);
+
+        let index_file = fs::read_to_string(format!("{prefix}.index"))?;
+        let all_chars = index_file
+            .lines()
+            .any(|line| line.starts_with(&format!("{ALL_CHARS}\t")));
+
+        let mut index: BTreeMap<String, Vec<(u64, usize)>> = BTreeMap::new();
+        for line in index_file.lines() {
+            let mut fields = line.split('\t');
+            let (Some(word), Some(offset), Some(len)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let (Some(offset), Some(len)) = (decode_b64(offset), decode_b64(len)) else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid .index line: {line}"),
+                ));
+            };
+            index
+                .entry(fold(word, all_chars))
+                .or_default()
+                .push((offset, len as usize));
+        }
+
+        let (file, dictzip) = match File::open(format!("{prefix}.dict")) {
+            Ok(file) => (Some(file), None),
+            Err(_) => (None, Some(Dictzip::open(&format!("{prefix}.dict.dz"))?)),
+        };
+
+        let mut dictd = Self {
+            name: String::new(),
+            index,
+            all_chars,
+            file,
+            dictzip,
+        };
+        dictd.name = dictd
+            .get(SHORT_NAME)?
+            .and_then(|entry| {
+                entry
+                    .lines()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty() && *line != SHORT_NAME)
+                    .map(String::from)
+            })
+            .unwrap_or_else(|| {
+                std::path::Path::new(&prefix)
+                    .file_name()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+        Ok(dictd)
+    }
+
+    /// Grabs every entry of the specified word, joined together.
+    pub fn get(&mut self, word: &str) -> io::Result<Option<String>> {
+        let Some(locations) = self.index.get(&fold(word, self.all_chars)).cloned() else {
+            return Ok(None);
+        };
+
+        let mut entries = vec![];
+        for (offset, len) in locations {
+            let buf = match (self.file.as_mut(), self.dictzip.as_mut()) {
+                (Some(file), _) => {
+                    file.seek(SeekFrom::Start(offset))?;
+                    let mut buf = vec![];
+                    file.take(len as u64).read_to_end(&mut buf)?;
+                    buf
+                }
+                (None, Some(dictzip)) => dictzip.read(offset, len)?,
+                (None, None) => vec![],
+            };
+            entries.push(String::from_utf8_lossy(&buf).trim_end().to_string());
+        }
+        Ok(Some(entries.join("\n\n")))
+    }
+}
+
+/// Strips the file extension off of any of the database's files.
+pub fn dictd_prefix(path: &str) -> String {
+    [".index", ".dict.dz", ".dict"]
+        .iter()
+        .find_map(|ext| path.strip_suffix(ext))
+        .unwrap_or(path)
+        .to_string()
+}
+
+/// Decodes dictd's base64 numbers, which are written most
+/// significant digit first without any padding.
+fn decode_b64(encoded: &str) -> Option<u64> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    encoded.bytes().try_fold(0u64, |number, digit| {
+        let value = ALPHABET.iter().position(|&chr| chr == digit)?;
+        number.checked_mul(64)?.checked_add(value as u64)
+    })
+}
+
+/// Folds a headword the way dictd compares them: case-insensitively,
+/// and unless every character counts, ignoring non-alphanumeric ones.
+fn fold(word: &str, all_chars: bool) -> String {
+    let lowered = word.trim().to_lowercase();
+    if all_chars || word.starts_with("00-database") {
+        return lowered;
+    }
+    lowered
+        .chars()
+        .filter(|chr| chr.is_alphanumeric() || chr.is_whitespace())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn encode_b64(mut number: u64) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut encoded = vec![ALPHABET[(number % 64) as usize]];
+        while number >= 64 {
+            number /= 64;
+            encoded.insert(0, ALPHABET[(number % 64) as usize]);
+        }
+        String::from_utf8(encoded).expect("Alphabet should be ASCII")
+    }
+
+    #[test]
+    fn b64_should_decode() {
+        assert_eq!(Some(0), decode_b64("A"));
+        assert_eq!(Some(64), decode_b64("BA"));
+        assert_eq!(Some(4096 + 2 * 64 + 3), decode_b64("BCD"));
+        assert_eq!(None, decode_b64("A=B"));
+    }
+
+    #[test]
+    fn dictd_lookup_test() {
+        let dir = TempDir::new("dictd");
+        let prefix = dir.join("test").to_string_lossy().to_string();
+
+        let entries = [
+            (SHORT_NAME, "00-database-short\n     Test Database\n"),
+            ("Jargon", "Jargon\n  The language of hackers.\n"),
+            ("jargon", "jargon\n  A second entry.\n"),
+        ];
+        let (mut index, mut dict) = (String::new(), String::new());
+        for (word, entry) in entries.iter() {
+            index.push_str(&format!(
+                "{word}\t{}\t{}\n",
+                encode_b64(dict.len() as u64),
+                encode_b64(entry.len() as u64)
+            ));
+            dict.push_str(entry);
+        }
+        fs::write(format!("{prefix}.index"), index).expect("Index should be written");
+        fs::write(format!("{prefix}.dict"), dict).expect("Dict should be written");
+
+        let mut dictd = Dictd::new(&format!("{prefix}.index")).expect("Database should parse");
+        assert_eq!("Test Database", dictd.name);
+        assert_eq!(
+            Some("Jargon\n  The language of hackers.\n\njargon\n  A second entry.".to_string()),
+            dictd.get("JAR-GON").expect("Database should be readable")
+        );
+        assert_eq!(
+            None,
+            dictd.get("missing").expect("Database should be readable")
+        );
+    }
+}
@@ -1,64 +1,198 @@
-use crate::colored_display::print_colored;
+mod errors;
+
+use crate::{
+    cache::CacheKey,
+    http::{self, Client},
+    provider::{Entry, Recording, Sense},
+};
+pub use errors::DictionaryError;
 use serde::Deserialize;
 use std::fmt::{Display, Formatter};
 
 /// A deeply-nested struct containing the main word defined,
 /// the list of phonetic metadata, the list of meanings,
-/// and the source URLs for more information.
+/// the license of the entry and the source URLs for more information.
 #[derive(Debug, Deserialize)]
 pub struct WordDefinition {
     word: String,
+    #[serde(default)]
+    phonetic: Option<String>,
     phonetics: Vec<Phonetics>,
     meanings: Vec<Meaning>,
+    #[serde(default)]
+    license: Option<License>,
     #[serde(rename = "sourceUrls")]
     read_more: Vec<String>,
+    #[serde(default)]
+    origin: Option<String>,
 }
 
 /// A container struct holding the phonetic string
-/// for the word.
+/// for the word, along with a recording of it.
 #[derive(Debug, Deserialize)]
-struct Phonetics {
+pub struct Phonetics {
     text: Option<String>,
+    #[serde(default)]
+    audio: Option<String>,
+    #[serde(rename = "sourceUrl", default)]
+    source_url: Option<String>,
+    #[serde(default)]
+    license: Option<License>,
 }
 
 /// A nested struct containing the specific definition of
 /// the word. Contains the part of speech, the exact
 /// definition of the word, its synonyms and its antonyms.
 #[derive(Debug, Deserialize)]
-struct Meaning {
+pub struct Meaning {
     #[serde(rename = "partOfSpeech")]
     part_of_speech: String,
     definitions: Vec<DefinitionString>,
+    #[serde(default)]
     synonyms: Vec<String>,
+    #[serde(default)]
     antonyms: Vec<String>,
 }
 
-/// A container struct holding the exact definition of the word.
+/// A container struct holding the exact definition of the word,
+/// an example of its use, and the synonyms and antonyms of that sense.
 #[derive(Debug, Deserialize)]
 struct DefinitionString {
     #[serde(rename = "definition")]
     definition_str: String,
+    #[serde(default)]
+    example: Option<String>,
+    #[serde(default)]
+    synonyms: Vec<String>,
+    #[serde(default)]
+    antonyms: Vec<String>,
 }
 
-const URL: &str = "https://api.dictionaryapi.dev/api/v2/entries/en/";
-type Error = Box<dyn std::error::Error>;
-pub fn define(word: &str) -> Result<Vec<WordDefinition>, Error> {
-    let request_url = format!("{URL}{word}");
-    let response = reqwest::blocking::get(request_url)?.text()?;
+/// The license an entry or a recording is published under.
+#[derive(Clone, Debug, Deserialize)]
+pub struct License {
+    name: String,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+/// The language the API is searched in unless told otherwise.
+pub const DEFAULT_LANG: &str = "en";
+type DictionaryResult<T> = std::result::Result<T, DictionaryError>;
+pub async fn define(
+    client: &Client,
+    word: &str,
+    lang: &str,
+) -> DictionaryResult<Vec<WordDefinition>> {
+    let request_url = format!(
+        "{}/entries/{}/{}",
+        client.endpoints.free_dictionary,
+        http::encode(lang),
+        http::encode(word)
+    );
+    let response = client
+        .fetch(&CacheKey::new("free-dictionary", lang, word), &request_url)
+        .await?;
+
+    parse_response(
+        response.status,
+        response.retry_after.as_deref(),
+        &response.text(),
+    )
+}
 
-    Ok(serde_json::from_str(response.as_str())?)
+/// The body the API sends along with a 404.
+#[derive(Deserialize)]
+struct NotFound {
+    title: String,
+    message: String,
+    #[serde(default)]
+    resolution: String,
 }
 
+/// Turns the API's response into definitions, or into the reason there are none.
+fn parse_response(
+    status: u16,
+    retry_after: Option<&str>,
+    body: &str,
+) -> DictionaryResult<Vec<WordDefinition>> {
+    match status {
+        200..=299 => Ok(serde_json::from_str(body)?),
+        404 => {
+            // Mirrors and stand-ins may not explain themselves
+            let not_found = serde_json::from_str(body).unwrap_or_else(|_| NotFound {
+                title: "No Definitions Found".to_string(),
+                message: "The word has no definitions.".to_string(),
+                resolution: String::new(),
+            });
+            Err(DictionaryError::NotFound {
+                title: not_found.title,
+                message: not_found.message,
+                resolution: not_found.resolution,
+            })
+        }
+        429 => Err(DictionaryError::RateLimited(
+            retry_after.and_then(|seconds| seconds.trim().parse().ok()),
+        )),
+        status => Err(DictionaryError::Status(status)),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Semantic {
     Synonym,
     Antonym,
+    Hypernym,
+    Hyponym,
+    Meronym,
+    DerivedForm,
+}
+
+impl Semantic {
+    /// The heading the related words are listed under.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Semantic::Synonym => "Synonyms",
+            Semantic::Antonym => "Antonyms",
+            Semantic::Hypernym => "Hypernyms",
+            Semantic::Hyponym => "Hyponyms",
+            Semantic::Meronym => "Meronyms",
+            Semantic::DerivedForm => "Derived forms",
+        }
+    }
+
+    /// Whether the relation is part of the definitions the API returns,
+    /// rather than only being available from WordNet.
+    pub fn is_in_definitions(&self) -> bool {
+        matches!(self, Semantic::Synonym | Semantic::Antonym)
+    }
 }
 
 impl WordDefinition {
+    /// Builds a definition out of its parts, for sources other than the API.
+    pub fn new(
+        word: &str,
+        phonetics: Vec<Phonetics>,
+        meanings: Vec<Meaning>,
+        read_more: Vec<String>,
+        origin: Option<String>,
+    ) -> Self {
+        Self {
+            word: word.to_string(),
+            phonetics,
+            meanings,
+            phonetic: None,
+            license: None,
+            read_more,
+            origin,
+        }
+    }
+
     pub fn get_word(&self) -> String {
         self.word.clone()
     }
-    /// Returns a list of
+    /// Returns a list of the phonetic strings of the word, falling
+    /// back to its main phonetic string if there are none.
     pub fn get_phonetics(&self) -> Vec<String> {
         let mut phonetic_strings = vec![];
         for ph in self.phonetics.iter() {
@@ -66,15 +200,23 @@ impl WordDefinition {
                 phonetic_strings.push(p.clone());
             }
         }
+        if phonetic_strings.is_empty() {
+            phonetic_strings.extend(self.phonetic.clone());
+        }
         phonetic_strings
     }
 
+    pub fn get_license(&self) -> Option<&License> {
+        self.license.as_ref()
+    }
+
     pub fn get_semantics(&self, semantic: &Semantic) -> Vec<String> {
         self.meanings
             .iter()
             .flat_map(|meaning| match semantic {
                 Semantic::Synonym => meaning.get_synonyms(),
                 Semantic::Antonym => meaning.get_antonyms(),
+                _ => vec![],
             })
             .collect()
     }
@@ -84,7 +226,52 @@ impl WordDefinition {
     }
 }
 
+impl Phonetics {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: Some(text.to_string()),
+            audio: None,
+            source_url: None,
+            license: None,
+        }
+    }
+
+    pub fn get_audio(&self) -> Option<&str> {
+        self.audio.as_deref().filter(|audio| !audio.is_empty())
+    }
+
+    pub fn get_source_url(&self) -> Option<&str> {
+        self.source_url.as_deref()
+    }
+
+    pub fn get_license(&self) -> Option<&License> {
+        self.license.as_ref()
+    }
+}
+
 impl Meaning {
+    pub fn new(
+        part_of_speech: &str,
+        definitions: Vec<String>,
+        synonyms: Vec<String>,
+        antonyms: Vec<String>,
+    ) -> Self {
+        Self {
+            part_of_speech: part_of_speech.to_string(),
+            definitions: definitions
+                .into_iter()
+                .map(|definition_str| DefinitionString {
+                    definition_str,
+                    example: None,
+                    synonyms: vec![],
+                    antonyms: vec![],
+                })
+                .collect(),
+            synonyms,
+            antonyms,
+        }
+    }
+
     fn get_meanings(&self) -> Vec<String> {
         let mut meaning_list = vec![];
         for meaning in self.definitions.iter() {
@@ -93,37 +280,95 @@ impl Meaning {
         meaning_list
     }
 
+    /// Grabs the synonyms of the meaning, followed by those of its senses.
     fn get_synonyms(&self) -> Vec<String> {
-        self.synonyms.clone()
+        let senses = self.definitions.iter().map(|d| d.synonyms.as_slice());
+        merge(&self.synonyms, senses)
     }
+    /// Grabs the antonyms of the meaning, followed by those of its senses.
     fn get_antonyms(&self) -> Vec<String> {
-        self.antonyms.clone()
+        let senses = self.definitions.iter().map(|d| d.antonyms.as_slice());
+        merge(&self.antonyms, senses)
     }
 }
 
-impl Display for WordDefinition {
-    fn fmt(&self, fmtr: &mut Formatter) -> std::fmt::Result {
-        writeln!(fmtr)?;
-
-        for p in self.get_phonetics().iter() {
-            writeln!(fmtr, "{}    {p}", print_colored(self.get_word().as_str()))?;
+/// Merges the words of a meaning with those of its senses, skipping repeats.
+fn merge<'a>(words: &[String], senses: impl Iterator<Item = &'a [String]>) -> Vec<String> {
+    let mut merged = words.to_vec();
+    for word in senses.flatten() {
+        if !merged.contains(word) {
+            merged.push(word.clone());
         }
-        for d in self.meanings.iter() {
-            writeln!(fmtr, ": {}", d.part_of_speech)?;
-            for (index, meaning) in d.get_meanings().iter().enumerate() {
-                writeln!(fmtr, "    {}) {meaning}", index + 1)?;
-            }
+    }
+    merged
+}
+
+impl DefinitionString {
+    fn get_example(&self) -> Option<&str> {
+        self.example.as_deref()
+    }
+}
+
+impl Display for License {
+    fn fmt(&self, fmtr: &mut Formatter) -> std::fmt::Result {
+        match self.url.as_ref() {
+            Some(url) => write!(fmtr, "{} ({url})", self.name),
+            None => write!(fmtr, "{}", self.name),
         }
-        writeln!(fmtr)?;
-        writeln!(fmtr, "Read More: {:?}", self.get_read_more())?;
+    }
+}
 
-        Ok(())
+impl From<WordDefinition> for Entry {
+    fn from(definition: WordDefinition) -> Self {
+        let senses = definition
+            .meanings
+            .iter()
+            .flat_map(|meaning| {
+                let senses = meaning
+                    .get_meanings()
+                    .into_iter()
+                    .zip(meaning.definitions.iter());
+                senses.map(|(meaning_str, sense)| Sense {
+                    part_of_speech: Some(meaning.part_of_speech.clone()),
+                    definition: meaning_str,
+                    example: sense.get_example().map(String::from),
+                    synonyms: sense.synonyms.clone(),
+                    antonyms: sense.antonyms.clone(),
+                    author: None,
+                })
+            })
+            .collect();
+        let recordings = definition
+            .phonetics
+            .iter()
+            .filter_map(|phonetics| {
+                Some(Recording {
+                    url: phonetics.get_audio()?.to_string(),
+                    source_url: phonetics.get_source_url().map(String::from),
+                    license: phonetics.get_license().cloned(),
+                })
+            })
+            .collect();
+
+        Entry {
+            word: definition.get_word(),
+            phonetics: definition.get_phonetics(),
+            origin: definition.origin.clone(),
+            senses,
+            synonyms: definition.get_semantics(&Semantic::Synonym),
+            antonyms: definition.get_semantics(&Semantic::Antonym),
+            recordings,
+            license: definition.get_license().cloned(),
+            source_urls: definition.get_read_more(),
+            ..Entry::default()
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::provider::DisplayOptions;
 
     fn define(response: &str) -> Vec<WordDefinition> {
         serde_json::from_str(response).expect("Test String failed to parse.")
@@ -191,4 +436,162 @@ mod tests {
 
         assert_eq!(vec!["lmao.".to_string()], data.get_meanings());
     }
+
+    #[test]
+    fn full_schema_should_be_displayed() {
+        const FULL_DATA: &str = r#"
+        [
+            {
+                "word": "hello",
+                "phonetic": "/həˈləʊ/",
+                "phonetics": [
+                    {
+                        "audio": "https://example.com/hello-uk.mp3",
+                        "sourceUrl": "https://commons.wikimedia.org/w/index.php?curid=1",
+                        "license": { "name": "BY-SA 4.0", "url": "https://creativecommons.org/licenses/by-sa/4.0" }
+                    },
+                    { "text": "/həˈloʊ/", "audio": "" }
+                ],
+                "meanings": [
+                    {
+                        "partOfSpeech": "interjection",
+                        "definitions": [
+                            {
+                                "definition": "A greeting.",
+                                "example": "Hello, everyone.",
+                                "synonyms": ["greeting", "hi"],
+                                "antonyms": ["bye"]
+                            }
+                        ],
+                        "synonyms": ["hi"],
+                        "antonyms": []
+                    }
+                ],
+                "license": { "name": "CC BY-SA 3.0", "url": "https://creativecommons.org/licenses/by-sa/3.0" },
+                "sourceUrls": ["https://en.wiktionary.org/wiki/hello"]
+            }
+        ]
+        "#;
+
+        let data = define(FULL_DATA).remove(0);
+        assert_eq!(vec!["/həˈloʊ/".to_string()], data.get_phonetics());
+        assert_eq!(
+            vec!["hi".to_string(), "greeting".to_string()],
+            data.get_semantics(&Semantic::Synonym)
+        );
+        assert_eq!(
+            vec!["bye".to_string()],
+            data.get_semantics(&Semantic::Antonym)
+        );
+
+        let entry = Entry::from(data);
+        assert_eq!(
+            Some("https://example.com/hello-uk.mp3"),
+            entry.get_audio(Some("UK"))
+        );
+        assert!(entry.get_audio(Some("us")).is_none());
+        assert!(entry.get_audio(None).is_some());
+
+        let displayed = entry.display(DisplayOptions::default()).to_string();
+        assert!(displayed.contains("    1) A greeting.\n       Synonyms: greeting, hi\n"));
+        assert!(!displayed.contains("Hello, everyone.") && !displayed.contains("License"));
+
+        let displayed = crate::render::strip_ansi(
+            &entry
+                .display(DisplayOptions {
+                    examples: true,
+                    attribution: true,
+                    color: true,
+                })
+                .to_string(),
+        );
+        assert!(displayed.contains("    1) A greeting.\n       \"Hello, everyone.\"\n"));
+        assert!(displayed
+            .contains("License: CC BY-SA 3.0 (https://creativecommons.org/licenses/by-sa/3.0)\n"));
+        assert!(displayed.contains(
+            "Audio: https://example.com/hello-uk.mp3, BY-SA 4.0 \
+             (https://creativecommons.org/licenses/by-sa/4.0), \
+             from https://commons.wikimedia.org/w/index.php?curid=1\n"
+        ));
+    }
+
+    #[test]
+    fn responses_should_map_onto_errors() {
+        const NOT_FOUND: &str = r#"{
+            "title": "No Definitions Found",
+            "message": "Sorry pal, we couldn't find definitions for the word you were looking for.",
+            "resolution": "You can try the search again at later time or head to the web instead."
+        }"#;
+
+        let error = parse_response(404, None, NOT_FOUND).expect_err("Word should be missing");
+        assert_eq!(3, error.exit_code());
+        assert_eq!(
+            "No Definitions Found: Sorry pal, we couldn't find definitions for the word you were \
+             looking for. You can try the search again at later time or head to the web instead.",
+            error.to_string()
+        );
+
+        assert!(matches!(
+            parse_response(404, None, "<html>"),
+            Err(DictionaryError::NotFound { .. })
+        ));
+        assert!(matches!(
+            parse_response(429, Some("30"), ""),
+            Err(DictionaryError::RateLimited(Some(30)))
+        ));
+        assert!(matches!(
+            parse_response(200, None, "<html>"),
+            Err(DictionaryError::Malformed(_))
+        ));
+        assert!(matches!(
+            parse_response(503, None, ""),
+            Err(DictionaryError::Status(503))
+        ));
+        assert!(parse_response(200, None, "[]").is_ok_and(|definitions| definitions.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn definitions_should_be_requested() {
+        use crate::http::{
+            mock::{self, MockServer},
+            Endpoints,
+        };
+
+        const FAKE_DATA: &str =
+            r#"[{"word": "faux", "phonetics": [], "meanings": [], "sourceUrls": []}]"#;
+        const NOT_FOUND: &str =
+            r#"{"title": "No Definitions Found", "message": "Sorry pal.", "resolution": ""}"#;
+        let server = MockServer::start(&[
+            ("/entries/en/faux", 200, FAKE_DATA),
+            ("/entries/en/fawx", 404, NOT_FOUND),
+            ("/entries/en/kick%20the%20bucket", 200, FAKE_DATA),
+        ]);
+        let client = Client::new(
+            mock::transport(),
+            Endpoints {
+                free_dictionary: server.url.clone(),
+                ..Endpoints::default()
+            },
+        );
+
+        let definitions = super::define(&client, "faux", "en")
+            .await
+            .expect("Word should be defined");
+        assert_eq!("faux", definitions[0].get_word());
+        assert!(matches!(
+            super::define(&client, "fawx", "en").await,
+            Err(DictionaryError::NotFound { .. })
+        ));
+        assert!(super::define(&client, "kick the bucket", "en")
+            .await
+            .is_ok());
+        assert_eq!(
+            vec![
+                "/entries/en/faux",
+                "/entries/en/fawx",
+                "/entries/en/kick%20the%20bucket"
+            ],
+            server.requests()
+        );
+    }
 }
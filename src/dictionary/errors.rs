@@ -0,0 +1,98 @@
+//! This module holds the errors for the Free Dictionary
+//! API client.
+
+use crate::http::TransportError;
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+};
+
+/// An enum holding the ways looking a word up in the
+/// Free Dictionary API can fail.
+#[derive(Debug)]
+pub enum DictionaryError {
+    /// The API has no definitions for the word, along with
+    /// its own explanation of why and what to do about it.
+    NotFound {
+        title: String,
+        message: String,
+        resolution: String,
+    },
+    Network(TransportError),
+    /// Too many requests were sent, along with how many
+    /// seconds to wait before trying again if the API said.
+    RateLimited(Option<u64>),
+    Malformed(serde_json::Error),
+    Status(u16),
+    /// A local dictionary couldn't be read, i.e.: its files are corrupt.
+    Unreadable {
+        dictionary: String,
+        error: std::io::Error,
+    },
+}
+
+impl DictionaryError {
+    /// The exit code the program ends with, so that
+    /// scripts can tell the failures apart.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            DictionaryError::NotFound { .. } => 3,
+            DictionaryError::Network(_) => 4,
+            DictionaryError::RateLimited(_) => 5,
+            DictionaryError::Malformed(_) => 6,
+            DictionaryError::Status(_) => 7,
+            DictionaryError::Unreadable { .. } => 8,
+        }
+    }
+}
+
+impl Display for DictionaryError {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        match self {
+            DictionaryError::NotFound {
+                title,
+                message,
+                resolution,
+            } if resolution.is_empty() => write!(fmt, "{title}: {message}"),
+            DictionaryError::NotFound {
+                title,
+                message,
+                resolution,
+            } => write!(fmt, "{title}: {message} {resolution}"),
+            DictionaryError::Network(e) => {
+                write!(fmt, "Failed to reach the Free Dictionary API: {e}")
+            }
+            DictionaryError::RateLimited(Some(seconds)) => write!(
+                fmt,
+                "The Free Dictionary API is rate limiting requests, please try again in {seconds} seconds."
+            ),
+            DictionaryError::RateLimited(None) => write!(
+                fmt,
+                "The Free Dictionary API is rate limiting requests, please try again later."
+            ),
+            DictionaryError::Malformed(e) => {
+                write!(fmt, "The Free Dictionary API sent a malformed response: {e}")
+            }
+            DictionaryError::Status(status) => {
+                write!(fmt, "The Free Dictionary API responded with HTTP {status}")
+            }
+            DictionaryError::Unreadable { dictionary, error } => {
+                write!(fmt, "Failed to read {dictionary}: {error}")
+            }
+        }
+    }
+}
+
+impl Error for DictionaryError {}
+
+impl From<TransportError> for DictionaryError {
+    fn from(err: TransportError) -> Self {
+        Self::Network(err)
+    }
+}
+
+impl From<serde_json::Error> for DictionaryError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Malformed(err)
+    }
+}
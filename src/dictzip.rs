@@ -0,0 +1,225 @@
+//! A module containing the reader for dictzip files, the
+//! random-access gzip variant used by both StarDict and dictd.
+//!
+
+use flate2::{read::GzDecoder, Decompress, FlushDecompress};
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+};
+
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+const FRESERVED: u8 = 0xE0;
+
+/// A struct reading ranges of a dictzip file.
+///
+/// dictzip files hold an `RA` field in their gzip header listing
+/// independently compressed chunks, so only the chunks holding the
+/// requested range have to be decompressed. Plain gzip files are
+/// decompressed entirely and kept in memory instead.
+pub struct Dictzip {
+    file: File,
+    chunk_len: usize,
+    /// The offset and compressed size of every chunk.
+    chunks: Vec<(u64, usize)>,
+    content: Option<Vec<u8>>,
+}
+
+impl Dictzip {
+    /// Opens a dictzip (or plain gzip) file.
+    pub fn open(path: &str) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut header = [0u8; 10];
+        file.read_exact(&mut header)?;
+        if header[..3] != [0x1f, 0x8b, 0x08] || header[3] & FRESERVED != 0 {
+            return Err(invalid_data("Not a gzip file."));
+        }
+        let flags = header[3];
+
+        let mut chunk_len = 0;
+        let mut chunk_sizes = vec![];
+        let mut data_start = 10;
+        if flags & FEXTRA != 0 {
+            let mut xlen = [0u8; 2];
+            file.read_exact(&mut xlen)?;
+            let mut extra = vec![0u8; u16::from_le_bytes(xlen) as usize];
+            file.read_exact(&mut extra)?;
+            data_start += 2 + extra.len() as u64;
+            if let Some((len, sizes)) = parse_ra_field(&extra) {
+                if len == 0 && !sizes.is_empty() {
+                    return Err(invalid_data("Chunks can't be empty."));
+                }
+                chunk_len = len;
+                chunk_sizes = sizes;
+            }
+        }
+        for flag in [FNAME, FCOMMENT] {
+            if flags & flag != 0 {
+                data_start += skip_past_null(&mut file)?;
+            }
+        }
+        if flags & FHCRC != 0 {
+            data_start += 2;
+        }
+
+        if chunk_sizes.is_empty() {
+            file.seek(SeekFrom::Start(0))?;
+            let mut content = vec![];
+            GzDecoder::new(&file).read_to_end(&mut content)?;
+            return Ok(Self {
+                file,
+                chunk_len: 0,
+                chunks: vec![],
+                content: Some(content),
+            });
+        }
+
+        let mut chunks = Vec::with_capacity(chunk_sizes.len());
+        let mut offset = data_start;
+        for size in chunk_sizes {
+            chunks.push((offset, size));
+            offset += size as u64;
+        }
+        Ok(Self {
+            file,
+            chunk_len,
+            chunks,
+            content: None,
+        })
+    }
+
+    /// Reads `len` bytes of the uncompressed data starting at `offset`.
+    pub fn read(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let (start, end) = (offset as usize, offset as usize + len);
+        if let Some(content) = self.content.as_ref() {
+            return content
+                .get(start..end)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| invalid_data("Range lies outside of the file."));
+        }
+        if len == 0 {
+            return Ok(vec![]);
+        }
+
+        let (first, last) = (start / self.chunk_len, (end - 1) / self.chunk_len);
+        if last >= self.chunks.len() {
+            return Err(invalid_data("Range lies outside of the file."));
+        }
+
+        let mut data = Vec::with_capacity((last - first + 1) * self.chunk_len);
+        for chunk in first..=last {
+            data.extend(self.read_chunk(chunk)?);
+        }
+        let skip = start - first * self.chunk_len;
+        data.get(skip..skip + len)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| invalid_data("Range lies outside of the file."))
+    }
+
+    fn read_chunk(&mut self, chunk: usize) -> io::Result<Vec<u8>> {
+        let (offset, size) = self.chunks[chunk];
+        let mut compressed = vec![0u8; size];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut compressed)?;
+
+        // Every chunk is a raw deflate stream ending in a full flush
+        let mut decompressed = Vec::with_capacity(self.chunk_len);
+        Decompress::new(false)
+            .decompress_vec(&compressed, &mut decompressed, FlushDecompress::Sync)
+            .map_err(|e| invalid_data(&e.to_string()))?;
+        Ok(decompressed)
+    }
+}
+
+/// Parses the `RA` subfield of a gzip extra field into the
+/// chunk length and the compressed size of every chunk.
+fn parse_ra_field(extra: &[u8]) -> Option<(usize, Vec<usize>)> {
+    let u16_at = |bytes: &[u8], i: usize| {
+        Some(u16::from_le_bytes([*bytes.get(i)?, *bytes.get(i + 1)?]) as usize)
+    };
+
+    let mut rest = extra;
+    while rest.len() >= 4 {
+        let len = u16_at(rest, 2)?;
+        let field = rest.get(4..4 + len)?;
+        if &rest[..2] == b"RA" {
+            let (chunk_len, chunk_count) = (u16_at(field, 2)?, u16_at(field, 4)?);
+            let sizes = (0..chunk_count)
+                .map(|i| u16_at(field, 6 + i * 2))
+                .collect::<Option<Vec<_>>>()?;
+            return Some((chunk_len, sizes));
+        }
+        rest = &rest[4 + len..];
+    }
+    None
+}
+
+/// Skips past a null-terminated header field, returning its length.
+fn skip_past_null(file: &mut File) -> io::Result<u64> {
+    let mut skipped = 0;
+    let mut byte = [0u8; 1];
+    loop {
+        file.read_exact(&mut byte)?;
+        skipped += 1;
+        if byte[0] == 0 {
+            return Ok(skipped);
+        }
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    const FILEDIR: &str = "src/testdata/stardict-EnglishEtymology-2.4.2/EnglishEtymology";
+
+    #[test]
+    fn dictzip_should_match_gzip() {
+        let path = format!("{FILEDIR}.dict.dz");
+        let mut content = vec![];
+        GzDecoder::new(File::open(&path).expect("File should exist"))
+            .read_to_end(&mut content)
+            .expect("File should decompress");
+
+        let mut dictzip = Dictzip::open(&path).expect("File should parse properly");
+        assert!(dictzip.content.is_none());
+
+        // A range spanning across two chunks
+        let start = dictzip.chunk_len - 10;
+        let range = dictzip
+            .read(start as u64, 30)
+            .expect("Range should be readable");
+        assert_eq!(&content[start..start + 30], range.as_slice());
+
+        let end = content.len() - 5;
+        let range = dictzip
+            .read(end as u64, 5)
+            .expect("Range should be readable");
+        assert_eq!(&content[end..], range.as_slice());
+        assert!(dictzip.read(end as u64, 6).is_err());
+    }
+
+    #[test]
+    fn empty_chunks_should_be_rejected() {
+        let dir = TempDir::new("dictzip");
+        let path = dir.join("test.dz");
+        // A gzip header whose RA field holds a single chunk of length 0
+        let mut header = vec![0x1f, 0x8b, 0x08, FEXTRA, 0, 0, 0, 0, 0, 3, 12, 0];
+        header.extend(b"RA");
+        header.extend([8, 0, 1, 0, 0, 0, 1, 0, 5, 0]);
+        std::fs::write(&path, header).expect("File should be written");
+
+        let error = Dictzip::open(&path.to_string_lossy()).map(|_| ());
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            error.expect_err("Chunks should be rejected").kind()
+        );
+    }
+}
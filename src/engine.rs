@@ -0,0 +1,290 @@
+//! A module containing the lookup engine, which queries every
+//! provider at once and hands their results over as they arrive.
+//!
+
+use crate::provider::{Capabilities, DictionaryProvider, Lookup, ProviderError, Registry};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::task::JoinSet;
+
+/// How many words of a batch are looked up at once unless configured otherwise.
+pub const DEFAULT_JOBS: usize = 4;
+
+/// How many seconds a provider gets to look a word up unless configured otherwise.
+pub const DEFAULT_TIMEOUT: u64 = 20;
+
+/// What a provider came back with.
+pub struct Outcome {
+    pub capabilities: Capabilities,
+    pub result: Result<Lookup, ProviderError>,
+}
+
+/// A struct looking words up in every registered provider at once.
+#[derive(Clone)]
+pub struct Engine {
+    registry: Registry,
+    timeout: Duration,
+}
+
+impl Engine {
+    pub fn new(registry: Registry, timeout: Duration) -> Self {
+        Self { registry, timeout }
+    }
+
+    /// Queries every provider at once, each within its own timeout, handing
+    /// the outcomes over in the order they arrive.
+    pub async fn lookup(&self, word: &str, mut on_outcome: impl FnMut(Outcome)) {
+        let mut lookups = JoinSet::new();
+        for provider in self.registry.providers() {
+            let (provider, word, timeout) = (Arc::clone(provider), word.to_string(), self.timeout);
+            lookups.spawn(async move {
+                let result = match tokio::time::timeout(timeout, provider.lookup(&word)).await {
+                    Ok(result) => result,
+                    Err(_) => Err(timed_out(provider.as_ref(), timeout)),
+                };
+                Outcome {
+                    capabilities: provider.capabilities(),
+                    result,
+                }
+            });
+        }
+
+        while let Some(outcome) = lookups.join_next().await {
+            match outcome {
+                Ok(outcome) => on_outcome(outcome),
+                Err(e) => eprintln!("A lookup failed: {e}"),
+            }
+        }
+    }
+
+    /// Looks every word up, `jobs` of them at once, handing the outcomes of a
+    /// word over once all of its providers answered, in the order of the words.
+    pub async fn lookup_batch(
+        &self,
+        words: Vec<String>,
+        jobs: usize,
+        mut on_word: impl FnMut(String, Vec<Outcome>),
+    ) {
+        let mut words = words.into_iter().enumerate();
+        let mut lookups = JoinSet::new();
+        // The words that are done, waiting for the ones before them
+        let mut finished = BTreeMap::new();
+        let mut next = 0;
+        loop {
+            while lookups.len() < jobs.max(1) {
+                let Some((index, word)) = words.next() else {
+                    break;
+                };
+                let (engine, searched) = (self.clone(), word.clone());
+                // The lookup runs in a task of its own, so that the word
+                // is still known if it panics
+                let lookup = tokio::spawn(async move {
+                    let mut outcomes = vec![];
+                    engine
+                        .lookup(&searched, |outcome| outcomes.push(outcome))
+                        .await;
+                    outcomes
+                });
+                lookups.spawn(async move { (index, word, lookup.await) });
+            }
+
+            let Some(lookup) = lookups.join_next().await else {
+                break;
+            };
+            match lookup {
+                Ok((index, word, Ok(outcomes))) => {
+                    finished.insert(index, (word, outcomes));
+                }
+                Ok((index, word, Err(e))) => {
+                    let failed = Outcome {
+                        capabilities: Capabilities::default(),
+                        result: Err(format!("The lookup of {word} failed: {e}").into()),
+                    };
+                    finished.insert(index, (word, vec![failed]));
+                }
+                // Only awaiting the lookup, which can't panic
+                Err(e) => eprintln!("A lookup failed: {e}"),
+            }
+            while let Some((word, outcomes)) = finished.remove(&next) {
+                on_word(word, outcomes);
+                next += 1;
+            }
+        }
+
+        // Only left over if a lookup before them failed
+        for (word, outcomes) in finished.into_values() {
+            on_word(word, outcomes);
+        }
+    }
+
+    /// Gathers the headwords close to the word from every provider
+    /// able to suggest them, skipping those that fail or take too long.
+    pub async fn suggest(&self, word: &str) -> Vec<String> {
+        let mut suggestions: Vec<String> = vec![];
+        for provider in self.registry.providers() {
+            if !provider.capabilities().suggestions {
+                continue;
+            }
+            let Ok(Ok(found)) = tokio::time::timeout(self.timeout, provider.suggest(word)).await
+            else {
+                continue;
+            };
+            for suggestion in found {
+                if !suggestions.contains(&suggestion) {
+                    suggestions.push(suggestion);
+                }
+            }
+        }
+        suggestions
+    }
+}
+
+/// The error of a provider that took too long to answer.
+fn timed_out(provider: &dyn DictionaryProvider, timeout: Duration) -> ProviderError {
+    format!(
+        "{} gave no answer within {} seconds",
+        provider.name(),
+        timeout.as_secs()
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{
+        mock::{self, MockServer},
+        Client, Endpoints,
+    };
+    use crate::provider::{
+        FreeDictionaryProvider, LocalProvider, ProviderFuture, UrbanDictionaryProvider,
+    };
+
+    /// A provider that never answers.
+    struct Unresponsive;
+
+    impl DictionaryProvider for Unresponsive {
+        fn name(&self) -> String {
+            "Unresponsive".to_string()
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                suggestions: true,
+                ..Capabilities::default()
+            }
+        }
+
+        fn lookup<'a>(&'a self, _word: &'a str) -> ProviderFuture<'a, Lookup> {
+            Box::pin(std::future::pending())
+        }
+
+        fn suggest<'a>(&'a self, _word: &'a str) -> ProviderFuture<'a, Vec<String>> {
+            Box::pin(std::future::pending())
+        }
+    }
+
+    fn client(server: &MockServer) -> Arc<Client> {
+        Arc::new(Client::new(
+            mock::transport(),
+            Endpoints {
+                free_dictionary: server.url.clone(),
+                urban_dictionary: server.url.clone(),
+            },
+        ))
+    }
+
+    #[tokio::test]
+    async fn providers_should_be_queried_at_once() {
+        const DEFINITIONS: &str =
+            r#"[{"word": "run", "phonetics": [], "meanings": [], "sourceUrls": []}]"#;
+        const URBAN: &str = r#"{"list": [{"definition": "To go fast.", "author": "someone"}]}"#;
+        let server = MockServer::start(&[
+            ("/entries/en/run", 200, DEFINITIONS),
+            ("/define?term=running", 200, URBAN),
+        ]);
+        let client = client(&server);
+        let mut registry = Registry::default();
+        registry.register(FreeDictionaryProvider::new(Arc::clone(&client), "en"));
+        registry.register(UrbanDictionaryProvider::new(client));
+        registry.register(Unresponsive);
+        let engine = Engine::new(registry, Duration::from_secs(2));
+
+        let mut outcomes = vec![];
+        engine
+            .lookup("running", |outcome| outcomes.push(outcome))
+            .await;
+
+        assert_eq!(3, outcomes.len());
+        for outcome in outcomes {
+            let Ok(lookup) = outcome.result else {
+                let error = outcome.result.expect_err("Provider should time out");
+                assert_eq!(
+                    "Unresponsive gave no answer within 2 seconds",
+                    error.to_string()
+                );
+                continue;
+            };
+            assert!(lookup.is_found());
+            match lookup.entries[0].source.as_str() {
+                "Free Dictionary API" => {
+                    assert_eq!(("run", 1), (lookup.lemma.as_str(), lookup.entries.len()));
+                    assert!(outcome.capabilities.semantics);
+                }
+                source => {
+                    assert_eq!("Urban Dictionary", source);
+                    assert_eq!(1, lookup.entries[0].senses.len());
+                }
+            }
+        }
+        // The Free Dictionary request for "running" itself comes first
+        assert!(server
+            .requests()
+            .contains(&"/entries/en/running".to_string()));
+    }
+
+    #[tokio::test]
+    async fn batches_should_keep_their_order() {
+        const DEFINITIONS: &str =
+            r#"[{"word": "word", "phonetics": [], "meanings": [], "sourceUrls": []}]"#;
+        let server = MockServer::start(&[
+            ("/entries/en/a", 200, DEFINITIONS),
+            ("/entries/en/c", 200, DEFINITIONS),
+        ]);
+        let mut registry = Registry::default();
+        registry.register(FreeDictionaryProvider::new(client(&server), "en"));
+        let engine = Engine::new(registry, Duration::from_secs(30));
+
+        let words: Vec<String> = ["a", "b", "c", "d"].iter().map(|w| w.to_string()).collect();
+        let mut found = vec![];
+        engine
+            .lookup_batch(words, 2, |word, outcomes| {
+                let defined = outcomes
+                    .iter()
+                    .any(|outcome| outcome.result.as_ref().is_ok_and(Lookup::is_found));
+                found.push((word, defined));
+            })
+            .await;
+
+        let expected = [("a", true), ("b", false), ("c", true), ("d", false)];
+        assert_eq!(
+            expected
+                .map(|(word, defined)| (word.to_string(), defined))
+                .to_vec(),
+            found
+        );
+    }
+
+    #[tokio::test]
+    async fn suggestions_should_be_gathered() {
+        let mut registry = Registry::default();
+        registry.register(
+            LocalProvider::open("src/testdata/stardict-EnglishEtymology-2.4.2/EnglishEtymology")
+                .expect("Dictionary should open"),
+        );
+        registry.register(Unresponsive);
+        let engine = Engine::new(registry, Duration::from_secs(1));
+
+        let suggestions = engine.suggest("dictionory").await;
+        assert_eq!(Some("dictionary"), suggestions.first().map(String::as_str));
+    }
+}
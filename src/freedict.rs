@@ -0,0 +1,581 @@
+//! A module containing the reader for FreeDict's TEI P5 dictionaries.
+//!
+
+use crate::{
+    render::{self, Token},
+    stardict::builder::SDBuilder,
+};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// A single `<entry>` of a TEI dictionary.
+#[derive(Debug, Default, PartialEq)]
+pub struct TeiEntry {
+    pub headwords: Vec<String>,
+    pronunciations: Vec<String>,
+    senses: Vec<TeiSense>,
+}
+
+/// A single `<sense>` of an entry, holding its translations.
+#[derive(Debug, Default, PartialEq)]
+struct TeiSense {
+    part_of_speech: Option<String>,
+    usages: Vec<String>,
+    translations: Vec<String>,
+    definitions: Vec<String>,
+    examples: Vec<Example>,
+}
+
+/// An example sentence, along with its translation.
+#[derive(Debug, Default, PartialEq)]
+struct Example {
+    quote: String,
+    translation: Option<String>,
+}
+
+/// The element whose text is being collected.
+struct Capture {
+    name: String,
+    text: String,
+}
+
+impl TeiEntry {
+    /// Parses the markup of a single `<entry>`.
+    pub fn parse(markup: &str) -> Option<Self> {
+        let mut entry = Self::default();
+        let mut entry_pos = None;
+        // The open elements, along with their `type` attribute
+        let mut stack: Vec<(String, Option<String>)> = vec![];
+        let mut capture: Option<Capture> = None;
+
+        for token in render::tokenize(markup) {
+            match token {
+                Token::Open(tag) => {
+                    if tag.name == "sense" {
+                        entry.senses.push(TeiSense::default());
+                    }
+                    if capture.is_none()
+                        && ["orth", "pron", "pos", "quote", "tr", "def", "usg"]
+                            .contains(&tag.name.as_str())
+                    {
+                        capture = Some(Capture {
+                            name: tag.name.clone(),
+                            text: String::new(),
+                        });
+                    }
+                    if !tag.self_closing {
+                        stack.push((tag.name, tag.attrs.get("type").cloned()));
+                    }
+                }
+                Token::Text(text) => {
+                    if let Some(capture) = capture.as_mut() {
+                        capture.text.push_str(&text);
+                    }
+                }
+                Token::Close(name) => {
+                    if capture.as_ref().is_some_and(|capture| capture.name == name) {
+                        if let Some(Capture { name, text }) = capture.take() {
+                            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                            if !text.is_empty() {
+                                entry.add(&name, text, &stack, &mut entry_pos);
+                            }
+                        }
+                    }
+                    if let Some(open) = stack.iter().rposition(|(open, _)| *open == name) {
+                        stack.truncate(open);
+                    }
+                }
+            }
+        }
+
+        for sense in entry.senses.iter_mut() {
+            if sense.part_of_speech.is_none() {
+                sense.part_of_speech.clone_from(&entry_pos);
+            }
+        }
+        if entry.headwords.is_empty() {
+            return None;
+        }
+        Some(entry)
+    }
+
+    /// Files away the text of an element, based on the elements it sits in.
+    fn add(
+        &mut self,
+        name: &str,
+        text: String,
+        stack: &[(String, Option<String>)],
+        entry_pos: &mut Option<String>,
+    ) {
+        let in_sense = stack.iter().any(|(open, _)| open == "sense");
+        let citations: Vec<&str> = stack
+            .iter()
+            .filter(|(open, _)| open == "cit")
+            .map(|(_, kind)| kind.as_deref().unwrap_or("trans"))
+            .collect();
+
+        match name {
+            "orth" if !in_sense && citations.is_empty() => self.headwords.push(text),
+            "pron" if !in_sense => self.pronunciations.push(text),
+            "pos" if !in_sense => *entry_pos = Some(text),
+            "pos" => self.sense().part_of_speech = Some(text),
+            "usg" => self.sense().usages.push(text),
+            "def" => self.sense().definitions.push(text),
+            "quote" | "tr" => match citations.as_slice() {
+                [.., "example", "trans" | "translation"] => {
+                    if let Some(example) = self.sense().examples.last_mut() {
+                        example.translation = Some(text);
+                    }
+                }
+                [.., "example"] => self.sense().examples.push(Example {
+                    quote: text,
+                    translation: None,
+                }),
+                _ => self.sense().translations.push(text),
+            },
+            _ => {}
+        }
+    }
+
+    /// Grabs the latest sense, making one up for
+    /// translations placed right inside of the entry.
+    fn sense(&mut self) -> &mut TeiSense {
+        if self.senses.is_empty() {
+            self.senses.push(TeiSense::default());
+        }
+        let last = self.senses.len() - 1;
+        &mut self.senses[last]
+    }
+
+    /// Renders the entry as HTML, for storing it in a StarDict dictionary.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        for pronunciation in self.pronunciations.iter() {
+            html.push_str(&format!(
+                "<p>/{}/</p>",
+                render::escape_markup(pronunciation)
+            ));
+        }
+        for (part_of_speech, senses) in self.grouped_senses() {
+            if let Some(part_of_speech) = part_of_speech {
+                html.push_str(&format!(
+                    "<p><i>{}</i></p>",
+                    render::escape_markup(&part_of_speech)
+                ));
+            }
+            html.push_str("<ol>");
+            for sense in senses {
+                html.push_str(&format!("<li>{}", render::escape_markup(&sense.summary())));
+                for example in sense.examples.iter() {
+                    html.push_str(&format!(
+                        "<br><i>{}</i>",
+                        render::escape_markup(&example.to_string())
+                    ));
+                }
+                html.push_str("</li>");
+            }
+            html.push_str("</ol>");
+        }
+        html
+    }
+
+    /// Groups consecutive senses sharing the same part of speech.
+    fn grouped_senses(&self) -> Vec<(Option<String>, Vec<&TeiSense>)> {
+        let mut groups: Vec<(Option<String>, Vec<&TeiSense>)> = vec![];
+        for sense in self.senses.iter() {
+            let pos = sense.part_of_speech.as_deref().map(expand_part_of_speech);
+            match groups.last_mut() {
+                Some((last_pos, senses)) if *last_pos == pos => senses.push(sense),
+                _ => groups.push((pos, vec![sense])),
+            }
+        }
+        groups
+    }
+}
+
+impl TeiSense {
+    /// Sums the sense up in a single line: its usage labels,
+    /// then its translations and definitions.
+    fn summary(&self) -> String {
+        let mut summary = String::new();
+        if !self.usages.is_empty() {
+            summary.push_str(&format!("({}) ", self.usages.join(", ")));
+        }
+        summary.push_str(&self.translations.join(", "));
+        for definition in self.definitions.iter() {
+            if !self.translations.is_empty() {
+                summary.push_str(" — ");
+            }
+            summary.push_str(definition);
+        }
+        summary
+    }
+}
+
+impl Display for Example {
+    fn fmt(&self, fmtr: &mut Formatter) -> std::fmt::Result {
+        write!(fmtr, "\"{}\"", self.quote)?;
+        if let Some(translation) = self.translation.as_ref() {
+            write!(fmtr, " → {translation}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TeiEntry {
+    fn fmt(&self, fmtr: &mut Formatter) -> std::fmt::Result {
+        let word = self.headwords.first().map(String::as_str).unwrap_or("");
+        for p in self.pronunciations.iter() {
+            writeln!(fmtr, "{word}    /{p}/")?;
+        }
+        for (part_of_speech, senses) in self.grouped_senses() {
+            if let Some(part_of_speech) = part_of_speech {
+                writeln!(fmtr, ": {part_of_speech}")?;
+            }
+            for (index, sense) in senses.iter().enumerate() {
+                writeln!(fmtr, "    {}) {}", index + 1, sense.summary())?;
+                for example in sense.examples.iter() {
+                    writeln!(fmtr, "       {example}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Spells out the abbreviated parts of speech FreeDict uses.
+fn expand_part_of_speech(pos: &str) -> String {
+    let expanded = match pos.trim_end_matches('.') {
+        "n" => "noun",
+        "v" | "vi" | "vt" => "verb",
+        "adj" => "adjective",
+        "adv" => "adverb",
+        "pron" | "pn" => "pronoun",
+        "prep" => "preposition",
+        "conj" => "conjunction",
+        "int" | "interj" => "interjection",
+        "num" => "numeral",
+        "art" => "article",
+        "ptcl" => "particle",
+        other => other,
+    };
+    expanded.to_string()
+}
+
+/// Streams through a TEI file, calling `on_entry` with the byte offset
+/// and raw markup of every `<entry>`. Returns the dictionary's title, taken
+/// from the `<teiHeader>`, if it has one.
+pub fn read_entries(
+    mut reader: impl BufRead,
+    mut on_entry: impl FnMut(u64, &[u8]),
+) -> io::Result<Option<String>> {
+    let mut header = String::new();
+    let mut seen_entry = false;
+    let mut entry: Option<(u64, Vec<u8>)> = None;
+    let mut line = vec![];
+    let mut line_offset = 0;
+
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+
+        let mut pos = 0;
+        while pos < line.len() {
+            let rest = &line[pos..];
+            match entry.as_mut() {
+                Some((_, markup)) => match find(rest, b"</entry>") {
+                    Some(end) => {
+                        markup.extend_from_slice(&rest[..end + b"</entry>".len()]);
+                        pos += end + b"</entry>".len();
+                        if let Some((offset, markup)) = entry.take() {
+                            on_entry(offset, &markup);
+                        }
+                    }
+                    None => {
+                        markup.extend_from_slice(rest);
+                        pos = line.len();
+                    }
+                },
+                None => match find_entry_start(rest) {
+                    Some(start) => {
+                        if !seen_entry {
+                            header.push_str(&String::from_utf8_lossy(&rest[..start]));
+                        }
+                        seen_entry = true;
+                        entry = Some((line_offset + (pos + start) as u64, vec![]));
+                        pos += start;
+                    }
+                    None => {
+                        if !seen_entry {
+                            header.push_str(&String::from_utf8_lossy(rest));
+                        }
+                        pos = line.len();
+                    }
+                },
+            }
+        }
+        line_offset += read as u64;
+    }
+
+    Ok(header_title(&header))
+}
+
+/// Grabs the first `<title>` of the header.
+fn header_title(header: &str) -> Option<String> {
+    let tokens = render::tokenize(header);
+    let start = tokens
+        .iter()
+        .position(|token| matches!(token, Token::Open(tag) if tag.name == "title"))?;
+    let title: String = tokens[start + 1..]
+        .iter()
+        .map_while(|token| match token {
+            Token::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    Some(title.trim().to_string()).filter(|title| !title.is_empty())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Finds the start of an `<entry>` tag, skipping `<entryFree>` and the like.
+fn find_entry_start(haystack: &[u8]) -> Option<usize> {
+    let mut searched = 0;
+    while let Some(start) = find(&haystack[searched..], b"<entry") {
+        let start = searched + start;
+        match haystack.get(start + b"<entry".len()) {
+            Some(b' ' | b'>' | b'\t' | b'\r' | b'\n') => return Some(start),
+            _ => searched = start + 1,
+        }
+    }
+    None
+}
+
+/// A struct for looking words up in a TEI dictionary.
+///
+/// The file is streamed through once to index the offset of every
+/// entry; lookups then only parse the entries of the requested word.
+pub struct Tei {
+    file: File,
+    name: String,
+    index: BTreeMap<String, Vec<(u64, usize)>>,
+}
+
+impl Tei {
+    /// Opens and indexes the TEI file found at the given path.
+    pub fn new(path: &str) -> io::Result<Self> {
+        let mut index: BTreeMap<String, Vec<(u64, usize)>> = BTreeMap::new();
+        let title = read_entries(BufReader::new(File::open(path)?), |offset, markup| {
+            let Some(entry) = TeiEntry::parse(&String::from_utf8_lossy(markup)) else {
+                return;
+            };
+            for headword in entry.headwords.iter() {
+                index
+                    .entry(headword.to_lowercase())
+                    .or_default()
+                    .push((offset, markup.len()));
+            }
+        })?;
+
+        let name = title.unwrap_or_else(|| {
+            Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+        Ok(Self {
+            file: File::open(path)?,
+            name,
+            index,
+        })
+    }
+
+    /// Grabs the name of the dictionary.
+    pub fn get_bookname(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Grabs every headword of the dictionary, lowercased.
+    pub fn headwords(&self) -> impl Iterator<Item = &str> {
+        self.index.keys().map(String::as_str)
+    }
+
+    /// Grabs every entry of the specified word, laid out for the terminal.
+    pub fn get(&mut self, word: &str) -> io::Result<Option<String>> {
+        let Some(locations) = self.index.get(&word.to_lowercase()).cloned() else {
+            return Ok(None);
+        };
+
+        let mut entries = vec![];
+        for (offset, len) in locations {
+            self.file.seek(SeekFrom::Start(offset))?;
+            let mut markup = vec![0u8; len];
+            self.file.read_exact(&mut markup)?;
+            if let Some(entry) = TeiEntry::parse(&String::from_utf8_lossy(&markup)) {
+                entries.push(entry.to_string());
+            }
+        }
+        Ok(Some(entries.join("\n")))
+    }
+}
+
+/// Converts a TEI dictionary into a StarDict dictionary of HTML entries,
+/// named after the TEI header's title if it has one.
+pub fn to_stardict(reader: impl BufRead, bookname: &str) -> io::Result<SDBuilder> {
+    let mut entries: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut synonyms = vec![];
+    let title = read_entries(reader, |_, markup| {
+        let Some(entry) = TeiEntry::parse(&String::from_utf8_lossy(markup)) else {
+            return;
+        };
+        let headword = &entry.headwords[0];
+        entries
+            .entry(headword.clone())
+            .or_default()
+            .push(entry.to_html());
+        for synonym in entry.headwords[1..].iter() {
+            synonyms.push((synonym.clone(), headword.clone()));
+        }
+    })?;
+
+    let mut builder = SDBuilder::new(title.as_deref().unwrap_or(bookname), 'h');
+    for (word, htmls) in entries.iter() {
+        builder.add_entry(word, &htmls.join("<hr>"));
+    }
+    for (synonym, word) in synonyms.iter() {
+        builder.add_synonym(synonym, word);
+    }
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    const TEI: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TEI xmlns="http://www.tei-c.org/ns/1.0">
+  <teiHeader><fileDesc><titleStmt><title>English-German FreeDict Dictionary</title></titleStmt></fileDesc></teiHeader>
+  <text><body>
+    <entry xml:id="cat"><form><orth>cat</orth><orth>kitty</orth><pron>kæt</pron></form>
+      <gramGrp><pos>n</pos></gramGrp>
+      <sense n="1">
+        <cit type="trans"><quote>Katze</quote></cit>
+        <cit type="trans"><quote>Kater</quote></cit>
+        <cit type="example"><quote>the cat sat</quote>
+          <cit type="trans"><quote>die Katze saß</quote></cit></cit>
+      </sense>
+      <sense n="2"><usg type="dom">slang</usg><cit type="trans"><quote>Typ</quote></cit></sense>
+    </entry>
+    <entry xml:id="run"><form><orth>run</orth></form>
+      <sense><gramGrp><pos>v</pos></gramGrp><cit type="trans"><quote>laufen</quote></cit></sense>
+    </entry><entry xml:id="run2"><form><orth>run</orth></form>
+      <sense><gramGrp><pos>n</pos></gramGrp><def>the act of running</def></sense>
+    </entry>
+  </body></text>
+</TEI>
+"#;
+
+    #[test]
+    fn tei_entries_should_stream() {
+        let mut entries = vec![];
+        let title = read_entries(TEI.as_bytes(), |offset, markup| {
+            assert_eq!(
+                &TEI.as_bytes()[offset as usize..offset as usize + markup.len()],
+                markup
+            );
+            let markup = String::from_utf8_lossy(markup);
+            entries.push(TeiEntry::parse(&markup).expect("Entry should parse"));
+        })
+        .expect("Entries should be readable");
+
+        assert_eq!(
+            Some("English-German FreeDict Dictionary".to_string()),
+            title
+        );
+        assert_eq!(3, entries.len());
+        assert_eq!(
+            vec!["cat".to_string(), "kitty".to_string()],
+            entries[0].headwords
+        );
+        assert_eq!(
+            TeiSense {
+                part_of_speech: Some("n".to_string()),
+                translations: vec!["Katze".to_string(), "Kater".to_string()],
+                examples: vec![Example {
+                    quote: "the cat sat".to_string(),
+                    translation: Some("die Katze saß".to_string()),
+                }],
+                ..Default::default()
+            },
+            entries[0].senses[0]
+        );
+        assert_eq!(vec!["slang".to_string()], entries[0].senses[1].usages);
+    }
+
+    #[test]
+    fn tei_entries_should_display() {
+        let entry = TEI
+            .split("<entry")
+            .nth(1)
+            .and_then(|entry| TeiEntry::parse(&format!("<entry{entry}")))
+            .expect("Entry should parse");
+        assert_eq!(
+            ": noun\n    1) Katze, Kater\n       \"the cat sat\" → die Katze saß\n    2) (slang) Typ\n",
+            entry.to_string()
+                .lines()
+                .skip(1)
+                .map(|line| format!("{line}\n"))
+                .collect::<String>()
+        );
+    }
+
+    #[test]
+    fn tei_should_convert_to_stardict() {
+        let dir = TempDir::new("tei");
+        let prefix = dir.join("test").to_string_lossy().to_string();
+
+        let builder = to_stardict(TEI.as_bytes(), "fallback").expect("TEI should convert");
+        assert_eq!("English-German FreeDict Dictionary", builder.bookname);
+        builder
+            .write(&prefix)
+            .expect("Dictionary should be written");
+
+        let mut dict = crate::stardict::Stardict::new(&prefix).expect("Dictionary should parse");
+        let run = dict.get("run").expect("Word should exist");
+        assert_eq!(
+            "<p><i>verb</i></p><ol><li>laufen</li></ol><hr><p><i>noun</i></p><ol><li>the act of running</li></ol>",
+            run
+        );
+        assert!(dict
+            .get("kitty")
+            .expect("Synonym should exist")
+            .starts_with("<p>/kæt/</p>"));
+    }
+
+    #[test]
+    fn invalid_utf8_should_keep_offsets() {
+        let dir = TempDir::new("tei-utf8");
+        let path = dir.join("test.tei");
+        // Every invalid byte is read as a 3-byte replacement character
+        let mut tei = b"<TEI><text><body><entry><form><orth>bad</orth></form><sense><def>".to_vec();
+        tei.extend([0xFF; 32]);
+        tei.extend(b"</def></sense></entry></body></text></TEI>");
+        std::fs::write(&path, tei).expect("TEI should be written");
+
+        let mut dict = Tei::new(&path.to_string_lossy()).expect("TEI should be indexed");
+        assert!(dict.get("bad").expect("Entry should be readable").is_some());
+    }
+}
@@ -0,0 +1,753 @@
+//! A module containing the HTTP client the online providers go through,
+//! along with the endpoints they are reached at.
+//!
+
+#[cfg(test)]
+pub mod mock;
+
+use crate::{
+    cache::{Cache, CacheKey},
+    config::Config,
+};
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    env, fs,
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    path::PathBuf,
+    pin::Pin,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// The error a transport fails with, i.e.: a connection error.
+pub type TransportError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The parts of a response the providers care about.
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    /// The `Retry-After` header, sent along with rate limiting.
+    pub retry_after: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    /// Rebuilds a response out of its cached status and body.
+    fn cached(status: u16, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            retry_after: None,
+            body,
+        }
+    }
+}
+
+/// The response a transport eventually comes back with.
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Response, TransportError>> + Send + 'a>>;
+
+/// Something able to send GET requests, so that providers
+/// can be pointed at another transport than the network.
+pub trait Transport: Send + Sync {
+    fn get<'a>(&'a self, url: &'a str) -> TransportFuture<'a>;
+}
+
+/// How long a request gets to answer unless configured otherwise, short
+/// enough for a retry to fit in the time a provider gets for a lookup.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(8);
+
+/// How requests reach the network, as set in the config file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpSettings {
+    /// How long every attempt at a request gets to answer.
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    /// How long a response can go without sending anything.
+    pub read_timeout: Duration,
+    /// How many times requests failing with a connection error,
+    /// a rate limit or a server error are retried.
+    pub retries: u32,
+    /// The delay before the first retry, doubled on every retry after it.
+    pub backoff: Duration,
+    /// How many requests are sent to a host per second, if limited.
+    pub rate_limit: Option<u32>,
+    /// The proxy requests go through, instead of the one of `HTTPS_PROXY`.
+    pub proxy: Option<String>,
+    /// A PEM file of certificate authorities to trust along with the system's.
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(10),
+            retries: 2,
+            backoff: Duration::from_millis(500),
+            rate_limit: Some(5),
+            proxy: None,
+            ca_bundle: None,
+        }
+    }
+}
+
+impl HttpSettings {
+    pub fn from_config(config: &Config) -> Self {
+        let default = Self::default();
+        Self {
+            timeout: config.timeout.unwrap_or(default.timeout),
+            connect_timeout: config.connect_timeout.unwrap_or(default.connect_timeout),
+            read_timeout: config.read_timeout.unwrap_or(default.read_timeout),
+            retries: config.retries.unwrap_or(default.retries),
+            rate_limit: config.rate_limit.map_or(default.rate_limit, |limit| {
+                Some(limit).filter(|&limit| limit > 0)
+            }),
+            proxy: config.proxy.clone(),
+            ca_bundle: config.ca_bundle.clone(),
+            ..default
+        }
+    }
+}
+
+/// The transport sending requests over the network, sharing
+/// its connections between every request.
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+    read_timeout: Duration,
+}
+
+impl ReqwestTransport {
+    pub fn new(settings: &HttpSettings) -> Result<Self, TransportError> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .connect_timeout(settings.connect_timeout);
+        if let Some(proxy) = settings.proxy.as_deref() {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy).map_err(|e| format!("Invalid proxy {proxy}: {e}"))?,
+            );
+        }
+        if let Some(path) = settings.ca_bundle.as_ref() {
+            let bundle = fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+            let certificates = pem_certificates(&bundle);
+            if certificates.is_empty() {
+                return Err(format!("No certificates were found in {}", path.display()).into());
+            }
+            for certificate in certificates {
+                builder = builder
+                    .add_root_certificate(reqwest::Certificate::from_pem(certificate.as_bytes())?);
+            }
+        }
+
+        Ok(Self {
+            client: builder.build()?,
+            read_timeout: settings.read_timeout,
+        })
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn get<'a>(&'a self, url: &'a str) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let mut response = read(self.read_timeout, self.client.get(url).send()).await??;
+            let status = response.status().as_u16();
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from);
+            let mut body = vec![];
+            while let Some(chunk) = read(self.read_timeout, response.chunk()).await?? {
+                body.extend_from_slice(&chunk);
+            }
+            Ok(Response {
+                status,
+                retry_after,
+                body,
+            })
+        })
+    }
+}
+
+/// Waits for the future, unless it stalls for longer than the read timeout.
+async fn read<T>(
+    read_timeout: Duration,
+    future: impl Future<Output = T>,
+) -> Result<T, TransportError> {
+    tokio::time::timeout(read_timeout, future)
+        .await
+        .map_err(|_| {
+            format!(
+                "No data was received for {} seconds",
+                read_timeout.as_secs()
+            )
+            .into()
+        })
+}
+
+/// Splits a PEM bundle into its certificates, since only
+/// the first one of a file would be read otherwise.
+fn pem_certificates(bundle: &str) -> Vec<String> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+    let mut certificates = vec![];
+    let mut rest = bundle;
+    while let Some(start) = rest.find(BEGIN) {
+        let Some(end) = rest[start..].find(END) else {
+            break;
+        };
+        let end = start + end + END.len();
+        certificates.push(format!("{}\n", &rest[start..end]));
+        rest = &rest[end..];
+    }
+    certificates
+}
+
+/// The base URLs of the online providers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Endpoints {
+    pub free_dictionary: String,
+    pub urban_dictionary: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            free_dictionary: "https://api.dictionaryapi.dev/api/v2".to_string(),
+            urban_dictionary: "https://api.urbandictionary.com/v0".to_string(),
+        }
+    }
+}
+
+impl Endpoints {
+    /// Grabs the base URLs from the environment (`RDICT_FREE_DICTIONARY_URL`
+    /// and `RDICT_URBAN_DICTIONARY_URL`), then from the config file.
+    pub fn from_config(config: &Config) -> Self {
+        Self::from_sources(config, |var| env::var(var).ok())
+    }
+
+    /// Grabs the base URLs from the given environment variables, then from the config file.
+    fn from_sources(config: &Config, env: impl Fn(&str) -> Option<String>) -> Self {
+        let default = Self::default();
+        let pick = |var: &str, configured: &Option<String>, default: String| {
+            env(var)
+                .filter(|url| !url.is_empty())
+                .or_else(|| configured.clone())
+                .map_or(default, |url| url.trim_end_matches('/').to_string())
+        };
+        Self {
+            free_dictionary: pick(
+                "RDICT_FREE_DICTIONARY_URL",
+                &config.free_dictionary_url,
+                default.free_dictionary,
+            ),
+            urban_dictionary: pick(
+                "RDICT_URBAN_DICTIONARY_URL",
+                &config.urban_dictionary_url,
+                default.urban_dictionary,
+            ),
+        }
+    }
+}
+
+/// The longest `Retry-After` waited for before retrying; a
+/// response asking for longer is handed over as-is.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// A transport along with the endpoints it reaches the providers
+/// at, and the cache their responses are kept in.
+pub struct Client {
+    transport: Box<dyn Transport>,
+    pub endpoints: Endpoints,
+    cache: Option<Cache>,
+    refresh: bool,
+    retries: u32,
+    backoff: Duration,
+    timeout: Option<Duration>,
+    limiter: Option<RateLimiter>,
+}
+
+impl Client {
+    pub fn new(transport: Box<dyn Transport>, endpoints: Endpoints) -> Self {
+        Self {
+            transport,
+            endpoints,
+            cache: None,
+            refresh: false,
+            retries: 0,
+            backoff: Duration::ZERO,
+            timeout: None,
+            limiter: None,
+        }
+    }
+
+    /// Keeps the successful responses in the given cache, requesting
+    /// them again even while they are fresh if `refresh` is set.
+    pub fn with_cache(mut self, cache: Cache, refresh: bool) -> Self {
+        self.cache = Some(cache);
+        self.refresh = refresh;
+        self
+    }
+
+    /// Times out, retries and spaces out the requests as set in the settings.
+    pub fn with_settings(mut self, settings: &HttpSettings) -> Self {
+        self.retries = settings.retries;
+        self.backoff = settings.backoff;
+        self.timeout = Some(settings.timeout);
+        self.limiter = settings
+            .rate_limit
+            .map(|limit| RateLimiter::new(Duration::from_secs(1) / limit.max(1)));
+        self
+    }
+
+    /// Sends the request, retrying it with a jittered exponential backoff (or
+    /// after its `Retry-After`) while it fails with a connection error, a
+    /// timeout, a rate limit or a server error.
+    pub async fn get(&self, url: &str) -> Result<Response, TransportError> {
+        let mut attempt = 0;
+        loop {
+            if let Some(limiter) = self.limiter.as_ref() {
+                limiter.wait(host(url)).await;
+            }
+            let result = match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, self.transport.get(url))
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(format!(
+                            "No answer was received within {} seconds",
+                            timeout.as_secs()
+                        )
+                        .into())
+                    }),
+                None => self.transport.get(url).await,
+            };
+            let delay = match result.as_ref() {
+                Ok(response) if response.status == 429 => {
+                    response.retry_after.as_deref().and_then(retry_after)
+                }
+                Ok(response) if response.status < 500 => return result,
+                _ => None,
+            }
+            .unwrap_or_else(|| backoff(self.backoff, attempt));
+            if attempt >= self.retries || delay > MAX_RETRY_AFTER {
+                return result;
+            }
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Grabs the response of the key from the cache while it is fresh, or
+    /// requests it, falling back to the stale one if the network is down.
+    ///
+    /// Misses are cached along with definitions, so that words a provider
+    /// doesn't have aren't requested again until they expire.
+    pub async fn fetch(&self, key: &CacheKey, url: &str) -> Result<Response, TransportError> {
+        let Some(cache) = self.cache.as_ref() else {
+            return self.get(url).await;
+        };
+        let cached = cache.get(key);
+        if let Some(cached) = cached.as_ref() {
+            if !self.refresh && cache.is_fresh(cached) {
+                return Ok(Response::cached(cached.status, cached.body.clone()));
+            }
+        }
+
+        match self.get(url).await {
+            Ok(response) => {
+                if response.is_success() || response.status == 404 {
+                    if let Err(e) = cache.put(key, response.status, &response.body) {
+                        eprintln!("Failed to cache the response: {e}");
+                    }
+                }
+                Ok(response)
+            }
+            Err(e) => {
+                let Some(cached) = cached else {
+                    return Err(e);
+                };
+                eprintln!(
+                    "Warning: the request failed ({e}), using a response cached {} ago.",
+                    describe_age(cached.age)
+                );
+                Ok(Response::cached(cached.status, cached.body))
+            }
+        }
+    }
+}
+
+/// Percent-encodes a path segment or query value, keeping only
+/// the unreserved characters of RFC 3986 as they are.
+pub fn encode(component: &str) -> String {
+    component
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Spaces out the requests sent to every host.
+struct RateLimiter {
+    interval: Duration,
+    /// When the next request to every host can be sent.
+    next: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Waits for the turn of the next request to the host.
+    async fn wait(&self, host: &str) {
+        let now = Instant::now();
+        let turn = {
+            let mut next = self.next.lock().expect("Lock isn't poisoned");
+            let turn = next
+                .get(host)
+                .copied()
+                .filter(|&turn| turn > now)
+                .unwrap_or(now);
+            next.insert(host.to_string(), turn + self.interval);
+            turn
+        };
+        tokio::time::sleep(turn - now).await;
+    }
+}
+
+/// Grabs the host of a URL, i.e.: `api.dictionaryapi.dev`.
+fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?']).next().unwrap_or(rest)
+}
+
+/// Picks the delay before a retry, doubling the base delay on every
+/// attempt and scaling it by a random factor between 0.5 and 1.5, so
+/// that concurrent requests don't all retry at once.
+fn backoff(base: Duration, attempt: u32) -> Duration {
+    let jitter = RandomState::new().build_hasher().finish() % 1000;
+    base.saturating_mul(1 << attempt.min(16)) * (500 + jitter as u32) / 1000
+}
+
+/// Reads how long a `Retry-After` asks to wait, given either in
+/// seconds or as the HTTP date to retry at.
+fn retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    // A date in the past means retrying right away
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// Describes how old a cached response is in its largest unit, i.e.: `3 days`.
+fn describe_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+    let (count, unit) = match seconds {
+        0..=59 => (seconds, "second"),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86399 => (seconds / 3600, "hour"),
+        _ => (seconds / 86400, "day"),
+    };
+    match count {
+        1 => format!("1 {unit}"),
+        count => format!("{count} {unit}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use mock::MockServer;
+
+    #[tokio::test]
+    async fn requests_should_reach_the_server() {
+        let server = MockServer::start(&[("/hello", 200, "world")]);
+        let client = Client::new(mock::transport(), Endpoints::default());
+
+        let response = client
+            .get(&format!("{}/hello", server.url))
+            .await
+            .expect("Server should be reachable");
+        assert!(response.is_success());
+        assert_eq!("world", response.text());
+
+        let response = client
+            .get(&format!("{}/missing", server.url))
+            .await
+            .expect("Server should be reachable");
+        assert_eq!(404, response.status);
+        assert_eq!(vec!["/hello", "/missing"], server.requests());
+    }
+
+    /// A transport for when the network is down.
+    struct Offline;
+
+    impl Transport for Offline {
+        fn get<'a>(&'a self, _url: &'a str) -> TransportFuture<'a> {
+            Box::pin(async { Err("the network is unreachable".into()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn responses_should_be_fetched_from_the_cache() {
+        let dir = TempDir::new("http-cache");
+        let cache = |ttl| Cache::new(dir.to_path_buf(), ttl, crate::cache::DEFAULT_MAX_SIZE);
+        let server = MockServer::start(&[("/faux", 200, "[]"), ("/fawx", 404, "{}")]);
+        let url = format!("{}/faux", server.url);
+        let key = CacheKey::new("free-dictionary", "en", "faux");
+
+        let client = Client::new(mock::transport(), Endpoints::default())
+            .with_cache(cache(Duration::from_secs(60)), false);
+        for _ in 0..2 {
+            let response = client
+                .fetch(&key, &url)
+                .await
+                .expect("Response should be fetched");
+            assert_eq!("[]", response.text());
+        }
+        assert_eq!(1, server.requests().len());
+
+        let client = Client::new(mock::transport(), Endpoints::default())
+            .with_cache(cache(Duration::from_secs(60)), true);
+        client
+            .fetch(&key, &url)
+            .await
+            .expect("Response should be fetched");
+        assert_eq!(2, server.requests().len());
+
+        let missing = CacheKey::new("free-dictionary", "en", "fawx");
+        let missing_url = format!("{}/fawx", server.url);
+        let client = Client::new(mock::transport(), Endpoints::default())
+            .with_cache(cache(Duration::from_secs(60)), false);
+        for _ in 0..2 {
+            let response = client
+                .fetch(&missing, &missing_url)
+                .await
+                .expect("Response should be fetched");
+            assert_eq!(404, response.status);
+        }
+        assert_eq!(3, server.requests().len());
+
+        let offline = Client::new(Box::new(Offline), Endpoints::default());
+        assert!(offline.fetch(&key, &url).await.is_err());
+        let offline = offline.with_cache(cache(Duration::ZERO), false);
+        let stale = offline
+            .fetch(&key, &url)
+            .await
+            .expect("Stale response should be used");
+        assert_eq!("[]", stale.text());
+    }
+
+    /// A transport answering with the given statuses in turn, then with a 200.
+    struct Flaky {
+        statuses: Mutex<Vec<(u16, Option<&'static str>)>>,
+    }
+
+    impl Flaky {
+        fn new(statuses: &[(u16, Option<&'static str>)]) -> Self {
+            Self {
+                statuses: Mutex::new(statuses.iter().rev().copied().collect()),
+            }
+        }
+    }
+
+    impl Transport for Flaky {
+        fn get<'a>(&'a self, _url: &'a str) -> TransportFuture<'a> {
+            let (status, retry_after) = self
+                .statuses
+                .lock()
+                .expect("Lock isn't poisoned")
+                .pop()
+                .unwrap_or((200, None));
+            Box::pin(async move {
+                Ok(Response {
+                    status,
+                    retry_after: retry_after.map(String::from),
+                    body: vec![],
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_requests_should_be_retried() {
+        let settings = HttpSettings {
+            backoff: Duration::from_millis(1),
+            rate_limit: None,
+            ..HttpSettings::default()
+        };
+        let client = |transport| {
+            Client::new(Box::new(transport), Endpoints::default()).with_settings(&settings)
+        };
+
+        let flaky = client(Flaky::new(&[(503, None), (429, Some("0"))]));
+        let response = flaky
+            .get("http://host/")
+            .await
+            .expect("Request should succeed");
+        assert_eq!(200, response.status);
+
+        // Two retries, so the fourth response is never reached
+        let failing = client(Flaky::new(&[(500, None); 3]));
+        let response = failing
+            .get("http://host/")
+            .await
+            .expect("Request should be sent");
+        assert_eq!(500, response.status);
+
+        // Not found is an answer, and waiting an hour isn't worth it
+        for (status, retry_after) in [(404, None), (429, Some("3600"))] {
+            let response = client(Flaky::new(&[(status, retry_after)]))
+                .get("http://host/")
+                .await
+                .expect("Request should be sent");
+            assert_eq!(status, response.status);
+        }
+    }
+
+    /// A transport that never answers.
+    struct Stalled;
+
+    impl Transport for Stalled {
+        fn get<'a>(&'a self, _url: &'a str) -> TransportFuture<'a> {
+            Box::pin(std::future::pending())
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_should_time_out() {
+        let settings = HttpSettings {
+            timeout: Duration::from_millis(50),
+            retries: 1,
+            backoff: Duration::from_millis(1),
+            rate_limit: None,
+            ..HttpSettings::default()
+        };
+        let client = Client::new(Box::new(Stalled), Endpoints::default()).with_settings(&settings);
+        let start = Instant::now();
+        let error = client
+            .get("http://host/")
+            .await
+            .expect_err("Request should time out");
+        assert!(error
+            .to_string()
+            .starts_with("No answer was received within"));
+        // Every attempt gets its own timeout
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn retry_after_should_parse() {
+        assert_eq!(Some(Duration::from_secs(120)), retry_after(" 120 "));
+        assert_eq!(
+            Some(Duration::ZERO),
+            retry_after("Wed, 21 Oct 2015 07:28:00 GMT")
+        );
+        let later = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(600));
+        assert!(retry_after(&later).is_some_and(|delay| delay > Duration::from_secs(500)));
+        assert_eq!(None, retry_after("soon"));
+    }
+
+    #[tokio::test]
+    async fn requests_should_be_rate_limited() {
+        let limiter = RateLimiter::new(Duration::from_millis(50));
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.wait("api.dictionaryapi.dev").await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        // Other hosts have their own turns
+        let start = Instant::now();
+        limiter.wait("api.urbandictionary.com").await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn retries_should_back_off() {
+        let base = Duration::from_millis(100);
+        for attempt in 0..3 {
+            let delay = backoff(base, attempt);
+            assert!(delay >= base * (1 << attempt) / 2);
+            assert!(delay <= base * (1 << attempt) * 3 / 2);
+        }
+        assert_eq!(
+            "api.dictionaryapi.dev",
+            host("https://api.dictionaryapi.dev/api/v2")
+        );
+        assert_eq!("localhost:8080", host("http://localhost:8080?term=a"));
+    }
+
+    #[test]
+    fn bundles_should_be_split() {
+        let bundle = "# Corporate CA\n\
+                      -----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n\
+                      -----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----\n";
+        assert_eq!(
+            vec![
+                "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n",
+                "-----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----\n",
+            ],
+            pem_certificates(bundle)
+        );
+    }
+
+    #[test]
+    fn components_should_be_encoded() {
+        assert_eq!("kick%20the%20bucket", encode("kick the bucket"));
+        assert_eq!("what%3F%23%26%2F", encode("what?#&/"));
+        assert_eq!("caf%C3%A9", encode("café"));
+        assert_eq!("rock-n_roll.~", encode("rock-n_roll.~"));
+    }
+
+    #[test]
+    fn ages_should_be_described() {
+        assert_eq!("1 second", describe_age(Duration::from_secs(1)));
+        assert_eq!("5 minutes", describe_age(Duration::from_secs(300)));
+        assert_eq!("2 days", describe_age(Duration::from_secs(2 * 86400 + 5)));
+    }
+
+    #[test]
+    fn endpoints_should_come_from_the_config() {
+        let config = Config {
+            free_dictionary_url: Some("http://localhost:8080/api/".to_string()),
+            ..Config::default()
+        };
+        let endpoints = Endpoints::from_sources(&config, |_| None);
+        assert_eq!("http://localhost:8080/api", endpoints.free_dictionary);
+        assert_eq!(
+            Endpoints::default().urban_dictionary,
+            endpoints.urban_dictionary
+        );
+
+        let endpoints = Endpoints::from_sources(&config, |var| {
+            (var == "RDICT_FREE_DICTIONARY_URL").then(|| "http://mirror/api/".to_string())
+        });
+        assert_eq!("http://mirror/api", endpoints.free_dictionary);
+    }
+}
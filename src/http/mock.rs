@@ -0,0 +1,78 @@
+//! A stand-in HTTP server, for testing the online
+//! providers without reaching the network.
+
+use super::{HttpSettings, ReqwestTransport, Transport};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// A server answering every request with the response of its path,
+/// or with a 404, and keeping track of the paths requested.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    /// Starts serving the given routes, made of a path
+    /// (along with its query), a status and a body.
+    pub fn start(routes: &[(&str, u16, &str)]) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Mock server should bind");
+        let url = format!(
+            "http://{}",
+            listener.local_addr().expect("Mock server has an address")
+        );
+        let routes: Vec<(String, u16, String)> = routes
+            .iter()
+            .map(|(path, status, body)| (path.to_string(), *status, body.to_string()))
+            .collect();
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let requested = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).is_err() {
+                    continue;
+                }
+                // Skip the headers
+                let mut header = String::new();
+                while reader.read_line(&mut header).is_ok_and(|read| read > 2) {
+                    header.clear();
+                }
+
+                let path = request_line.split(' ').nth(1).unwrap_or("/").to_string();
+                let (status, body) = routes
+                    .iter()
+                    .find(|(route, _, _)| *route == path)
+                    .map_or((404, ""), |(_, status, body)| (*status, body.as_str()));
+                requested.lock().expect("Lock isn't poisoned").push(path);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Grabs the paths requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().expect("Lock isn't poisoned").clone()
+    }
+}
+
+/// A transport reaching the mock server with the default settings.
+pub fn transport() -> Box<dyn Transport> {
+    Box::new(ReqwestTransport::new(&HttpSettings::default()).expect("Transport should build"))
+}
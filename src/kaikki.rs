@@ -0,0 +1,347 @@
+//! A module containing the reader for the Wiktionary extracts
+//! published by kaikki.org, which hold one JSON entry per line.
+//!
+
+use crate::{
+    dictionary::{Meaning, Phonetics, WordDefinition},
+    provider::{DisplayOptions, Entry},
+    stardict::builder::SDBuilder,
+};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// A single line of a kaikki.org extract: one part of speech of a word.
+#[derive(Debug, Deserialize)]
+struct KaikkiEntry {
+    word: String,
+    #[serde(default)]
+    lang: Option<String>,
+    #[serde(default)]
+    pos: String,
+    #[serde(default)]
+    senses: Vec<KaikkiSense>,
+    #[serde(default)]
+    sounds: Vec<Sound>,
+    #[serde(default)]
+    etymology_text: Option<String>,
+    #[serde(default)]
+    forms: Vec<Form>,
+    #[serde(default)]
+    synonyms: Vec<Related>,
+    #[serde(default)]
+    antonyms: Vec<Related>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KaikkiSense {
+    #[serde(default)]
+    glosses: Vec<String>,
+    #[serde(default)]
+    synonyms: Vec<Related>,
+    #[serde(default)]
+    antonyms: Vec<Related>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Sound {
+    #[serde(default)]
+    ipa: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Form {
+    form: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// A synonym or antonym.
+#[derive(Debug, Deserialize)]
+struct Related {
+    word: String,
+}
+
+/// Just enough of an entry to index it.
+#[derive(Deserialize)]
+struct Headword {
+    word: String,
+    #[serde(default)]
+    lang: Option<String>,
+}
+
+/// The tags of the forms that are inflection table
+/// metadata rather than actual forms of the word.
+const TABLE_TAGS: &[&str] = &["table-tags", "inflection-template", "class"];
+
+impl KaikkiEntry {
+    /// Maps the entry onto the same model the online API uses.
+    fn into_definition(self) -> WordDefinition {
+        let mut phonetics = vec![];
+        for ipa in self.sounds.iter().filter_map(|sound| sound.ipa.as_deref()) {
+            if !phonetics.contains(&ipa) {
+                phonetics.push(ipa);
+            }
+        }
+
+        let words = |related: &[Related]| -> Vec<String> {
+            related.iter().map(|related| related.word.clone()).collect()
+        };
+        let mut synonyms = words(&self.synonyms);
+        let mut antonyms = words(&self.antonyms);
+        let mut definitions = vec![];
+        for sense in self.senses.iter() {
+            synonyms.extend(words(&sense.synonyms));
+            antonyms.extend(words(&sense.antonyms));
+            // Subsenses repeat the glosses of their parent sense first
+            if let Some(gloss) = sense.glosses.last() {
+                definitions.push(gloss.clone());
+            }
+        }
+        // Senses repeat words anywhere in the list, not only next to each other
+        let mut seen = HashSet::new();
+        synonyms.retain(|word| seen.insert(word.clone()));
+        let mut seen = HashSet::new();
+        antonyms.retain(|word| seen.insert(word.clone()));
+
+        WordDefinition::new(
+            &self.word,
+            phonetics.into_iter().map(Phonetics::new).collect(),
+            vec![Meaning::new(
+                &expand_part_of_speech(&self.pos),
+                definitions,
+                synonyms,
+                antonyms,
+            )],
+            vec![format!(
+                "https://en.wiktionary.org/wiki/{}",
+                self.word.replace(' ', "_")
+            )],
+            self.etymology_text
+                .filter(|etymology| !etymology.is_empty()),
+        )
+    }
+
+    /// Grabs the inflected forms of the word, i.e.: its plural.
+    fn forms(&self) -> Vec<String> {
+        self.forms
+            .iter()
+            .filter(|form| form.form != self.word && !form.form.is_empty())
+            .filter(|form| {
+                !form
+                    .tags
+                    .iter()
+                    .any(|tag| TABLE_TAGS.contains(&tag.as_str()))
+            })
+            .map(|form| form.form.clone())
+            .collect()
+    }
+}
+
+/// Spells out the abbreviated parts of speech kaikki.org uses.
+fn expand_part_of_speech(pos: &str) -> String {
+    let expanded = match pos {
+        "adj" => "adjective",
+        "adv" => "adverb",
+        "det" => "determiner",
+        "intj" => "interjection",
+        "conj" => "conjunction",
+        "prep" => "preposition",
+        "pron" => "pronoun",
+        "num" => "numeral",
+        "name" => "proper noun",
+        other => other,
+    };
+    expanded.to_string()
+}
+
+/// Names a dictionary after the language of its entries.
+fn dictionary_name(lang: Option<&str>, fallback: &str) -> String {
+    match lang {
+        Some(lang) => format!("Wiktionary ({lang})"),
+        None => fallback.to_string(),
+    }
+}
+
+/// A struct for looking words up in a kaikki.org extract.
+///
+/// The file is streamed through once to index the offset of every
+/// line; lookups then only parse the lines of the requested word.
+pub struct Kaikki {
+    file: File,
+    name: String,
+    index: BTreeMap<String, Vec<(u64, usize)>>,
+}
+
+impl Kaikki {
+    /// Opens and indexes the extract found at the given path.
+    pub fn new(path: &str) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut index: BTreeMap<String, Vec<(u64, usize)>> = BTreeMap::new();
+        let mut lang = None;
+        let (mut line, mut offset) = (String::new(), 0);
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                break;
+            }
+            if let Ok(headword) = serde_json::from_str::<Headword>(&line) {
+                lang = lang.or(headword.lang);
+                index
+                    .entry(headword.word.to_lowercase())
+                    .or_default()
+                    .push((offset, read));
+            }
+            offset += read as u64;
+        }
+
+        let stem = Path::new(path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok(Self {
+            file: reader.into_inner(),
+            name: dictionary_name(lang.as_deref(), &stem),
+            index,
+        })
+    }
+
+    /// Grabs the name of the dictionary.
+    pub fn get_bookname(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Grabs every headword of the dictionary, lowercased.
+    pub fn headwords(&self) -> impl Iterator<Item = &str> {
+        self.index.keys().map(String::as_str)
+    }
+
+    /// Grabs every definition of the specified word.
+    pub fn define(&mut self, word: &str) -> io::Result<Vec<WordDefinition>> {
+        let Some(locations) = self.index.get(&word.to_lowercase()).cloned() else {
+            return Ok(vec![]);
+        };
+
+        let mut definitions = vec![];
+        for (offset, len) in locations {
+            self.file.seek(SeekFrom::Start(offset))?;
+            let mut line = vec![0u8; len];
+            self.file.read_exact(&mut line)?;
+            let entry: KaikkiEntry = serde_json::from_slice(&line)?;
+            definitions.push(entry.into_definition());
+        }
+        Ok(definitions)
+    }
+}
+
+/// Lays a definition out for the terminal.
+fn display(definition: WordDefinition) -> String {
+    Entry::from(definition)
+        .display(DisplayOptions::default())
+        .to_string()
+}
+
+/// Converts a kaikki.org extract into a StarDict dictionary of plain
+/// text entries, with the inflected forms of every word as synonyms.
+pub fn to_stardict(reader: impl BufRead, bookname: &str) -> io::Result<SDBuilder> {
+    let mut entries: BTreeMap<String, String> = BTreeMap::new();
+    let mut synonyms = vec![];
+    let mut lang = None;
+    for line in reader.lines() {
+        let Ok(entry) = serde_json::from_str::<KaikkiEntry>(&line?) else {
+            continue;
+        };
+        lang = lang.or(entry.lang.clone());
+        for form in entry.forms() {
+            synonyms.push((form, entry.word.clone()));
+        }
+
+        let word = entry.word.clone();
+        let definition = display(entry.into_definition());
+        entries.entry(word).or_default().push_str(&definition);
+    }
+
+    let mut builder = SDBuilder::new(&dictionary_name(lang.as_deref(), bookname), 'm');
+    for (word, definition) in entries.iter() {
+        builder.add_entry(word, definition.trim_matches('\n'));
+    }
+    for (synonym, word) in synonyms.iter() {
+        builder.add_synonym(synonym, word);
+    }
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dictionary::Semantic;
+    use crate::testing::TempDir;
+
+    const EXTRACT: &str = r#"{"word": "cat", "lang": "English", "lang_code": "en", "pos": "noun", "sounds": [{"ipa": "/kæt/"}, {"ipa": "/kæt/", "tags": ["US"]}, {"audio": "cat.ogg"}], "etymology_text": "From Old English catt.", "forms": [{"form": "cats", "tags": ["plural"]}, {"form": "en-noun", "tags": ["inflection-template"]}], "synonyms": [{"word": "moggy"}], "senses": [{"glosses": ["A domesticated feline."], "synonyms": [{"word": "kitty"}]}, {"glosses": ["A person.", "A man, a guy."], "synonyms": [{"word": "moggy"}], "antonyms": [{"word": "dog"}]}]}
+{"word": "cat", "lang": "English", "lang_code": "en", "pos": "verb", "senses": [{"glosses": ["To hoist the anchor."]}]}
+not json
+{"word": "kitten", "lang": "English", "lang_code": "en", "pos": "noun", "senses": [{"glosses": ["A young cat."]}]}
+"#;
+
+    #[test]
+    fn kaikki_entries_should_map_onto_definitions() {
+        let entry: KaikkiEntry =
+            serde_json::from_str(EXTRACT.lines().next().expect("Extract should have lines"))
+                .expect("Entry should parse");
+        assert_eq!(vec!["cats".to_string()], entry.forms());
+
+        let definition = entry.into_definition();
+        assert_eq!(vec!["/kæt/".to_string()], definition.get_phonetics());
+        assert_eq!(
+            vec!["moggy".to_string(), "kitty".to_string()],
+            definition.get_semantics(&Semantic::Synonym)
+        );
+        assert_eq!(
+            vec!["dog".to_string()],
+            definition.get_semantics(&Semantic::Antonym)
+        );
+        let displayed = display(definition);
+        assert!(displayed.contains("Origin: From Old English catt.\n: noun\n"));
+        assert!(displayed.contains("    1) A domesticated feline.\n    2) A man, a guy.\n"));
+    }
+
+    #[test]
+    fn kaikki_lookup_test() {
+        let dir = TempDir::new("kaikki");
+        let path = dir.join("kaikki.jsonl").to_string_lossy().to_string();
+        std::fs::write(&path, EXTRACT).expect("Extract should be written");
+
+        let mut kaikki = Kaikki::new(&path).expect("Extract should be indexed");
+        assert_eq!("Wiktionary (English)", kaikki.get_bookname());
+        let definitions = kaikki.define("Cat").expect("Extract should be readable");
+        assert_eq!(2, definitions.len());
+        let mut definitions = kaikki.define("kitten").expect("Extract should be readable");
+        assert!(display(definitions.remove(0)).contains("A young cat."));
+        assert!(kaikki
+            .define("dog")
+            .expect("Extract should be readable")
+            .is_empty());
+    }
+
+    #[test]
+    fn kaikki_should_convert_to_stardict() {
+        let dir = TempDir::new("kaikki-sd");
+        let prefix = dir.join("test").to_string_lossy().to_string();
+
+        let builder = to_stardict(EXTRACT.as_bytes(), "fallback").expect("Extract should convert");
+        assert_eq!("Wiktionary (English)", builder.bookname);
+        assert_eq!(2, builder.len());
+        builder
+            .write(&prefix)
+            .expect("Dictionary should be written");
+
+        let mut dict = crate::stardict::Stardict::new(&prefix).expect("Dictionary should parse");
+        let cat = dict.get("cats").expect("Form should exist");
+        assert!(cat.contains(": noun") && cat.contains(": verb"));
+    }
+}
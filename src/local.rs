@@ -0,0 +1,199 @@
+//! A module bringing the readers for local dictionary
+//! formats together under a single lookup path.
+//!
+
+use crate::{
+    dictd::Dictd,
+    dictionary::WordDefinition,
+    freedict::Tei,
+    kaikki::Kaikki,
+    mdict::Mdict,
+    provider,
+    slob::Slob,
+    stardict::{Stardict, StardictError},
+};
+use std::{io, path::Path};
+
+type Error = Box<dyn std::error::Error>;
+
+/// An entry of a local dictionary.
+pub enum LocalEntry {
+    /// Text marked up the way the dictionary's entry type says
+    Text(String),
+    /// Definitions the dictionary keeps structured
+    Definitions(Vec<WordDefinition>),
+}
+
+/// A dictionary stored on the local machine.
+pub enum LocalDictionary {
+    Stardict(Box<Stardict>),
+    Dictd(Dictd),
+    Mdict(Box<Mdict>),
+    Slob(Box<Slob>),
+    Tei(Tei),
+    Kaikki(Kaikki),
+}
+
+impl LocalDictionary {
+    /// Opens the dictionary found at the given path, telling its
+    /// format apart by which of its files exist.
+    pub fn open(path: &str) -> Result<Self, Error> {
+        let stardict_prefix = path.strip_suffix(".ifo").unwrap_or(path);
+        let stardict_prefix = stardict_prefix
+            .strip_suffix(".dict.dz")
+            .or_else(|| stardict_prefix.strip_suffix(".dict"))
+            .or_else(|| stardict_prefix.strip_suffix(".idx"))
+            .unwrap_or(stardict_prefix);
+        if Path::new(&format!("{stardict_prefix}.ifo")).exists() {
+            return Ok(Self::Stardict(Box::new(Stardict::new(stardict_prefix)?)));
+        }
+
+        let dictd_prefix = crate::dictd::dictd_prefix(path);
+        if Path::new(&format!("{dictd_prefix}.index")).exists() {
+            return Ok(Self::Dictd(Dictd::new(&dictd_prefix)?));
+        }
+
+        let mdict_prefix = crate::mdict::mdict_prefix(path);
+        if Path::new(&format!("{mdict_prefix}.mdx")).exists() {
+            return Ok(Self::Mdict(Box::new(Mdict::new(&mdict_prefix)?)));
+        }
+
+        if path.ends_with(".slob") && Path::new(path).exists() {
+            return Ok(Self::Slob(Box::new(Slob::new(path)?)));
+        }
+
+        if path.ends_with(".tei") && Path::new(path).exists() {
+            return Ok(Self::Tei(Tei::new(path)?));
+        }
+
+        if path.ends_with(".jsonl") && Path::new(path).exists() {
+            return Ok(Self::Kaikki(Kaikki::new(path)?));
+        }
+
+        Err(format!("No supported dictionary was found at {path}").into())
+    }
+
+    /// Grabs the name of the dictionary.
+    pub fn get_bookname(&self) -> String {
+        match self {
+            Self::Stardict(dict) => dict.get_bookname(),
+            Self::Dictd(dict) => dict.name.clone(),
+            Self::Mdict(dict) => dict.get_bookname(),
+            Self::Slob(dict) => dict.get_bookname(),
+            Self::Tei(dict) => dict.get_bookname(),
+            Self::Kaikki(dict) => dict.get_bookname(),
+        }
+    }
+
+    /// Grabs the StarDict type of the dictionary's entries,
+    /// which decides how they are rendered.
+    pub fn entry_type(&self) -> char {
+        match self {
+            Self::Stardict(dict) => dict.entry_type(),
+            Self::Dictd(_) | Self::Tei(_) | Self::Kaikki(_) => 'm',
+            Self::Mdict(_) | Self::Slob(_) => 'h',
+        }
+    }
+
+    /// Whether the headwords of the dictionary can be gone through for suggestions.
+    pub fn can_suggest(&self) -> bool {
+        !matches!(self, Self::Mdict(_) | Self::Slob(_))
+    }
+
+    /// Suggests the headwords closest to a word the dictionary doesn't have.
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        match self {
+            Self::Stardict(dict) => {
+                provider::closest(word, dict.idx.idx_content.keys().map(String::as_str))
+            }
+            Self::Dictd(dict) => provider::closest(word, dict.index.keys().map(String::as_str)),
+            Self::Tei(dict) => provider::closest(word, dict.headwords()),
+            Self::Kaikki(dict) => provider::closest(word, dict.headwords()),
+            Self::Mdict(_) | Self::Slob(_) => vec![],
+        }
+    }
+
+    /// Grabs the entry of the specified word, if it exists.
+    pub fn get(&mut self, word: &str) -> io::Result<Option<LocalEntry>> {
+        let text = match self {
+            Self::Stardict(dict) => match dict.get(word) {
+                Ok(entry) => Some(entry),
+                Err(StardictError::WordNotFound(_)) => None,
+                Err(StardictError::IO(e)) => return Err(e),
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            },
+            Self::Dictd(dict) => dict.get(word)?,
+            Self::Mdict(dict) => dict.get(word)?,
+            Self::Slob(dict) => dict.get(word)?,
+            Self::Tei(dict) => dict.get(word)?,
+            Self::Kaikki(dict) => {
+                let definitions = dict.define(word)?;
+                return Ok(
+                    (!definitions.is_empty()).then_some(LocalEntry::Definitions(definitions))
+                );
+            }
+        };
+        Ok(text.map(LocalEntry::Text))
+    }
+}
+
+/// Expands the given paths into the paths of the dictionaries they
+/// hold; directories are searched for the dictionaries right inside
+/// of them, while any other path is kept as-is.
+pub fn discover(paths: &[String]) -> Vec<String> {
+    let mut found = vec![];
+    for path in paths.iter() {
+        let Ok(entries) = std::fs::read_dir(path) else {
+            found.push(path.clone());
+            continue;
+        };
+
+        let mut dicts: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().to_string_lossy().to_string())
+            .filter(|file| {
+                [".ifo", ".index", ".mdx", ".slob", ".tei", ".jsonl"]
+                    .iter()
+                    .any(|ext| file.ends_with(ext))
+            })
+            .collect();
+        dicts.sort();
+        found.extend(dicts);
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const FILEDIR: &str = "src/testdata/stardict-EnglishEtymology-2.4.2/EnglishEtymology";
+
+    #[test]
+    fn stardict_should_open_from_any_file() {
+        for path in [
+            FILEDIR.to_string(),
+            format!("{FILEDIR}.dict.dz"),
+            format!("{FILEDIR}.idx"),
+        ] {
+            let dict = LocalDictionary::open(&path).expect("Dictionary should open");
+            assert!(matches!(dict, LocalDictionary::Stardict(_)));
+            assert_eq!('m', dict.entry_type());
+        }
+    }
+
+    #[test]
+    fn directories_should_be_discovered() {
+        assert_eq!(
+            vec![format!("{FILEDIR}.ifo"), "missing".to_string()],
+            discover(&[
+                "src/testdata/stardict-EnglishEtymology-2.4.2".to_string(),
+                "missing".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn missing_dictionary_should_fail() {
+        assert!(LocalDictionary::open("src/testdata/missing").is_err());
+    }
+}
@@ -5,27 +5,94 @@
 //! app.
 //!
 
+mod audio;
+mod cache;
+mod cmudict;
 mod colored_display;
+mod config;
+mod dictd;
 mod dictionary;
+mod dictzip;
+mod engine;
+mod freedict;
+mod http;
+mod kaikki;
+mod local;
+mod mdict;
+mod morphology;
+mod provider;
+mod render;
+mod slob;
 mod stardict;
+#[cfg(test)]
+mod testing;
 mod urban_dictionary;
+mod wordnet;
 
-use clap::Parser;
-use dictionary::Semantic;
+use cache::Cache;
+use clap::{Parser, Subcommand};
+use cmudict::{Cmudict, Rhyme};
+use colored_display::print_colored;
+use config::Config;
+use dictionary::{DictionaryError, Semantic};
+use engine::{Engine, Outcome};
+use http::{Client, Endpoints, HttpSettings, ReqwestTransport};
+use provider::{
+    DisplayOptions, Entry, FreeDictionaryProvider, LocalProvider, ProviderError, Registry,
+    UrbanDictionaryProvider, WordNetProvider,
+};
+use stardict::{export::ExportFormat, import::ImportFormat, Stardict};
+use std::{io::IsTerminal, sync::Arc, time::Duration};
 
 /// A dictionary CLI tool written in Rust
 /// insired by BetaPictoris' dict as well as
 /// Matthew Hartman's Word Lookup tool.
 #[derive(Parser)]
 #[clap(about, version, author)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[clap(after_help = "EXIT CODES:
+    3    None of the dictionaries has definitions for the word
+    4    A dictionary could not be reached
+    5    The Free Dictionary API is rate limiting requests
+    6    The Free Dictionary API sent a malformed response
+    7    The Free Dictionary API responded with an unexpected HTTP status
+    8    A local dictionary could not be read")]
 struct Cli {
-    /// The word you want to search the definition of
-    word: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// The word or phrase you want to search the definition of, unquoted
+    /// phrases included (read from stdin, one per line, if none is given
+    /// and stdin isn't a terminal)
+    #[clap(value_name = "WORD")]
+    words: Vec<String>,
+
+    /// Search every argument as a word of its own instead of as a phrase
+    #[clap(short, long)]
+    batch: bool,
+
+    /// Also search the words of a file, one per line (`-` for stdin)
+    #[clap(short, long, value_name = "PATH")]
+    file: Option<String>,
+
+    /// How many words to look up at once
+    #[clap(short, long, value_name = "N", default_value_t = engine::DEFAULT_JOBS)]
+    jobs: usize,
+
+    /// How many seconds every dictionary gets to look a word up, retries
+    /// included
+    #[clap(long, value_name = "SECONDS", default_value_t = engine::DEFAULT_TIMEOUT)]
+    timeout: u64,
 
     /// Search in Urban Dictionary
     #[clap(short, long)]
     urban: bool,
 
+    /// Search in the Free Dictionary API along with --urban or --dict
+    /// (it is searched by default otherwise)
+    #[clap(short, long)]
+    online: bool,
+
     /// Enable synonyms (will be prioritized over antonyms)
     #[clap(short, long = "syn")]
     synonyms: bool,
@@ -33,66 +100,726 @@ struct Cli {
     /// Enable antonyms
     #[clap(short, long = "ant")]
     antonyms: bool,
+
+    /// Enable hypernyms, the broader terms (needs --wordnet)
+    #[clap(long = "hyper")]
+    hypernyms: bool,
+
+    /// Enable hyponyms, the narrower terms (needs --wordnet)
+    #[clap(long = "hypo")]
+    hyponyms: bool,
+
+    /// Enable meronyms, the parts and members (needs --wordnet)
+    #[clap(long = "mero")]
+    meronyms: bool,
+
+    /// Enable derivationally related forms (needs --wordnet)
+    #[clap(long = "derived")]
+    derived_forms: bool,
+
+    /// Search in the Princeton WordNet database inside a directory
+    #[clap(short, long, value_name = "DIR")]
+    wordnet: Option<String>,
+
+    /// Show an example of use under every definition
+    #[clap(short, long)]
+    examples: bool,
+
+    /// Show the licenses and sources of the definitions and their recordings
+    #[clap(long)]
+    attribution: bool,
+
+    /// Download the pronunciation recording, of a region such as us, uk or
+    /// au if given (i.e.: --audio=uk), and print where it was saved
+    #[clap(long, value_name = "REGION", require_equals = true)]
+    audio: Option<Option<String>>,
+
+    /// Where to save the recording, which must be a directory when looking
+    /// several words up (defaults to the cache directory)
+    #[clap(long, value_name = "PATH", requires = "audio")]
+    audio_output: Option<String>,
+
+    /// Play the recording with the `player` command of the config file
+    #[clap(long, requires = "audio")]
+    play: bool,
+
+    /// The language to search the Free Dictionary API in, i.e.: es or de
+    /// (defaults to the `lang` setting of the config file, then to English)
+    #[clap(short, long, value_name = "CODE")]
+    lang: Option<String>,
+
+    /// Search in a local StarDict, dictd, MDict, slob, FreeDict TEI or
+    /// kaikki.org JSONL dictionary, or every dictionary inside a directory
+    /// (can be repeated)
+    #[clap(short, long = "dict", value_name = "PATH")]
+    dicts: Vec<String>,
+
+    /// Show ARPAbet pronunciations from the CMU Pronouncing Dictionary at
+    /// this path
+    #[clap(long, value_name = "PATH")]
+    cmudict: Option<String>,
+
+    /// List the perfect and near rhymes of the word instead of defining it
+    #[clap(short, long, requires = "cmudict")]
+    rhymes: bool,
+
+    /// Only list rhymes with this stress pattern, i.e.: 010 for "banana"
+    /// (0 for unstressed, 1 for primary and 2 for secondary stress), or
+    /// without --rhymes, list every word with it
+    #[clap(long, value_name = "PATTERN", requires = "cmudict")]
+    stress: Option<String>,
+
+    /// Request the definitions again even if they are cached
+    #[clap(long)]
+    refresh: bool,
+
+    /// Disable colored output (also disabled by setting NO_COLOR)
+    #[clap(long)]
+    no_color: bool,
 }
 
-fn main() {
+#[derive(Subcommand)]
+enum Command {
+    /// Manage local StarDict dictionaries
+    #[clap(subcommand)]
+    Dict(DictCommand),
+}
+
+#[derive(Subcommand)]
+enum DictCommand {
+    /// Merge several StarDict dictionaries into one
+    Merge {
+        /// The dictionaries to merge
+        #[clap(required = true, min_values = 2, value_name = "PATH")]
+        dicts: Vec<String>,
+
+        /// Where to write the merged dictionary
+        #[clap(short, long, value_name = "PATH")]
+        output: String,
+
+        /// The name of the merged dictionary
+        #[clap(short, long)]
+        name: Option<String>,
+    },
+    /// Export a StarDict dictionary to JSON Lines, CSV, TSV or tabfile
+    ///
+    /// The dictionary's metadata is written next to the output file
+    /// as `<OUTPUT>.meta.json`.
+    Export {
+        /// The dictionary to export
+        #[clap(value_name = "PATH")]
+        dict: String,
+
+        /// The format to export to
+        #[clap(short, long, arg_enum, default_value = "jsonl")]
+        format: ExportFormat,
+
+        /// Where to write the exported entries, along with their metadata in
+        /// PATH.meta.json (defaults to stdout, with the metadata on stderr)
+        #[clap(short, long, value_name = "PATH")]
+        output: Option<String>,
+
+        /// Render the entries as plain text instead of keeping their markup
+        #[clap(long)]
+        plain: bool,
+    },
+    /// Import a tabfile, ABBYY Lingvo DSL, FreeDict TEI or kaikki.org JSONL
+    /// file as a StarDict dictionary
+    Import {
+        /// The file to import
+        #[clap(value_name = "PATH")]
+        source: String,
+
+        /// The format of the file (guessed from its extension by default)
+        #[clap(short, long, arg_enum)]
+        format: Option<ImportFormat>,
+
+        /// Where to write the imported dictionary
+        #[clap(short, long, value_name = "PATH")]
+        output: String,
+
+        /// The name of the imported dictionary
+        #[clap(short, long)]
+        name: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() {
     let args = Cli::parse();
+    let color = !args.no_color && std::env::var_os("NO_COLOR").is_none();
+
+    if let Some(Command::Dict(command)) = args.command {
+        run_dict_command(command);
+        return;
+    }
+    if let Some(pattern) = args.stress.as_deref().filter(|_| !args.rhymes) {
+        // Clap requires the CMU Pronouncing Dictionary along with the pattern
+        let cmudict =
+            open_cmudict(args.cmudict.as_deref().unwrap_or_default()).unwrap_or_else(|e| {
+                eprintln!("{e}");
+                std::process::exit(1);
+            });
+        try_list_stressed(&cmudict, pattern);
+        return;
+    }
+    let words = match read_words(args.words, args.batch, args.file.as_deref()) {
+        Ok(words) => words,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    // Every recording would overwrite the one before it otherwise
+    if let Some(output) = args.audio_output.as_deref() {
+        if words.len() > 1 && !std::path::Path::new(output).is_dir() {
+            eprintln!("--audio-output has to be a directory when looking several words up.");
+            std::process::exit(1);
+        }
+    }
+    let config = Config::load();
+    let lang = args
+        .lang
+        .or(config.lang.clone())
+        .unwrap_or_else(|| dictionary::DEFAULT_LANG.to_string());
+    let settings = HttpSettings::from_config(&config);
+    let transport = match ReqwestTransport::new(&settings) {
+        Ok(transport) => transport,
+        Err(e) => {
+            eprintln!("Failed to set up the HTTP client: {e}");
+            std::process::exit(1);
+        }
+    };
+    let mut client =
+        Client::new(Box::new(transport), Endpoints::from_config(&config)).with_settings(&settings);
+    if let Some(dir) = cache::cache_dir() {
+        let cache = Cache::new(
+            dir.join("responses"),
+            config.cache_ttl.unwrap_or(cache::DEFAULT_TTL),
+            config.cache_size.unwrap_or(cache::DEFAULT_MAX_SIZE),
+        );
+        client = client.with_cache(cache, args.refresh);
+    }
+
+    let cmudict = match args.cmudict.as_deref().map(open_cmudict).transpose() {
+        Ok(cmudict) => cmudict,
+        // Pronunciations are only an addition to definitions, unlike rhymes
+        Err(e) if !args.rhymes => {
+            eprintln!("{e}");
+            None
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    if args.rhymes {
+        if let Some(cmudict) = cmudict.as_ref() {
+            for word in words.iter() {
+                try_get_rhymes(cmudict, word, args.stress.as_deref(), color);
+            }
+        }
+        return;
+    }
+
+    let options = DisplayOptions {
+        examples: args.examples,
+        attribution: args.attribution,
+        color,
+    };
+    let mut semantics = match (args.synonyms, args.antonyms) {
+        (true, _) => vec![Semantic::Synonym],
+        (_, true) => vec![Semantic::Antonym],
+        (false, false) => vec![],
+    };
+    let relations = [
+        (args.hypernyms, Semantic::Hypernym),
+        (args.hyponyms, Semantic::Hyponym),
+        (args.meronyms, Semantic::Meronym),
+        (args.derived_forms, Semantic::DerivedForm),
+    ];
+    semantics.extend(
+        relations
+            .into_iter()
+            .filter_map(|(enabled, semantic)| enabled.then_some(semantic)),
+    );
+
+    if args.wordnet.is_none() {
+        for semantic in semantics
+            .iter()
+            .filter(|semantic| !semantic.is_in_definitions())
+        {
+            println!("{} are only available with --wordnet.", semantic.label());
+        }
+    }
+
+    let client = Arc::new(client);
+    let dict_paths = local::discover(&args.dicts);
+    let mut registry = Registry::default();
     if args.urban {
-        try_define_urban(&args.word);
+        registry.register(UrbanDictionaryProvider::new(Arc::clone(&client)));
+    }
+    for path in dict_paths.iter() {
+        match LocalProvider::open(path) {
+            Ok(provider) => registry.register(provider),
+            Err(e) => println!("Failed to open the dictionary at {path}: {e}"),
+        }
+    }
+    if let Some(dir) = args.wordnet.as_deref() {
+        match WordNetProvider::open(dir) {
+            Ok(provider) => registry.register(provider),
+            Err(e) => println!("Failed to open WordNet: {e}"),
+        }
+    }
+    // Synonyms and antonyms are looked up online unless WordNet has them
+    let offline = args.urban || !dict_paths.is_empty() || args.wordnet.is_some();
+    let online_semantics = args.wordnet.is_none() && (args.synonyms || args.antonyms);
+    if !offline || args.online || online_semantics {
+        registry.register(FreeDictionaryProvider::new(Arc::clone(&client), &lang));
+    }
+
+    let engine = Engine::new(registry, Duration::from_secs(args.timeout));
+    let printer = Printer {
+        cmudict: cmudict.as_ref(),
+        options,
+        semantics: &semantics,
+        batch: words.len() > 1,
+    };
+    let mut reports = vec![];
+    if let [word] = words.as_slice() {
+        let mut report = Report::default();
+        engine
+            .lookup(word, |outcome| printer.print(word, outcome, &mut report))
+            .await;
+        reports.push((word.clone(), report));
+    } else {
+        engine
+            .lookup_batch(words, args.jobs, |word, outcomes| {
+                let mut report = Report::default();
+                for outcome in outcomes {
+                    printer.print(&word, outcome, &mut report);
+                }
+                reports.push((word, report));
+            })
+            .await;
+    }
+    // Words some provider failed on may well be defined there
+    let missing = reports
+        .iter()
+        .filter(|(_, report)| !report.found && report.error.is_none());
+    for (word, report) in missing {
+        printer.print_missing(word, report, engine.suggest(word).await);
+    }
+
+    if let Some(region) = args.audio.as_ref() {
+        let player = config.player.as_deref().filter(|_| args.play);
+        if args.play && player.is_none() {
+            println!("No player is set, please add `player = <command>` to the config file.");
+        }
+        for (_, report) in reports.iter().filter(|(_, report)| report.found) {
+            try_get_audio(
+                &client,
+                &report.entries,
+                region.as_deref(),
+                args.audio_output.as_deref(),
+                player,
+            )
+            .await;
+        }
+    }
+    if printer.batch {
+        print_summary(&reports);
+    }
+    if let Some(e) = reports.iter().find_map(|(_, report)| report.error.as_ref()) {
+        std::process::exit(exit_code(e));
+    }
+    if reports.iter().any(|(_, report)| !report.found) {
+        std::process::exit(3);
+    }
+}
+
+/// The exit code of a provider's failure, telling the Free Dictionary
+/// API's and the local dictionaries' failures apart and counting any
+/// other as a connection error.
+fn exit_code(e: &ProviderError) -> i32 {
+    e.downcast_ref::<DictionaryError>()
+        .map_or(4, DictionaryError::exit_code)
+}
+
+/// Gathers the words to look up from the command line, joined into a phrase
+/// unless looking them up as a batch, and from the file (`-` standing for
+/// stdin), or from stdin if neither has any and it is piped.
+fn read_words(args: Vec<String>, batch: bool, file: Option<&str>) -> Result<Vec<String>, String> {
+    let mut words = if batch || args.is_empty() {
+        args
     } else {
-        try_define(&args.word);
+        vec![args.join(" ")]
+    };
+    let read_stdin = || {
+        std::io::read_to_string(std::io::stdin()).map_err(|e| format!("Failed to read stdin: {e}"))
+    };
+    let text = match file {
+        Some("-") => Some(read_stdin()?),
+        Some(path) => {
+            Some(std::fs::read_to_string(path).map_err(|e| format!("Failed to read {path}: {e}"))?)
+        }
+        None if words.is_empty() && !std::io::stdin().is_terminal() => Some(read_stdin()?),
+        None => None,
+    };
+    if let Some(text) = text {
+        words.extend(text.lines().map(String::from));
     }
+    // Runs of whitespace are collapsed so that phrases match their headwords
+    let words: Vec<String> = words
+        .iter()
+        .map(|word| word.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .filter(|word| !word.is_empty())
+        .collect();
 
-    let semantic = match (args.synonyms, args.antonyms) {
-        (true, _) => Semantic::Synonym,
-        (_, true) => Semantic::Antonym,
-        (false, false) => return,
+    if words.is_empty() {
+        return Err(
+            "No words were given, pass them as arguments, with --file or through stdin."
+                .to_string(),
+        );
+    }
+    Ok(words)
+}
+
+/// Lists the words none of the providers had, once a batch is done.
+fn print_summary(reports: &[(String, Report)]) {
+    let missing: Vec<&str> = reports
+        .iter()
+        .filter(|(_, report)| !report.found)
+        .map(|(word, _)| word.as_str())
+        .collect();
+    eprintln!();
+    eprintln!(
+        "Found {} of {} words.",
+        reports.len() - missing.len(),
+        reports.len()
+    );
+    if !missing.is_empty() {
+        eprintln!("Missing: {}", missing.join(", "));
+    }
+}
+
+//* Dictionary Commands *//
+
+fn run_dict_command(command: DictCommand) {
+    let result = match command {
+        DictCommand::Merge {
+            dicts,
+            output,
+            name,
+        } => try_merge(&dicts, &output, name),
+        DictCommand::Export {
+            dict,
+            format,
+            output,
+            plain,
+        } => try_export(&dict, format, output.as_deref(), plain),
+        DictCommand::Import {
+            source,
+            format,
+            output,
+            name,
+        } => try_import(&source, format, &output, name),
     };
-    try_get_semantics(&args.word, semantic);
+    if let Err(e) = result {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn try_merge(dict_paths: &[String], output: &str, name: Option<String>) -> Result<(), String> {
+    let mut dicts = dict_paths
+        .iter()
+        .map(|path| {
+            Stardict::new(path).map_err(|e| format!("Failed to open the dictionary at {path}: {e}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let name = name.unwrap_or_else(|| {
+        let names: Vec<String> = dicts.iter().map(Stardict::get_bookname).collect();
+        names.join(" + ")
+    });
+    let merged = stardict::merge::merge(&mut dicts, &name)
+        .map_err(|e| format!("Failed to merge the dictionaries: {e}"))?;
+    if merged.is_empty() {
+        return Err("The dictionaries have no entries to merge.".to_string());
+    }
+
+    let entry_count = merged.len();
+    merged
+        .write(output)
+        .map_err(|e| format!("Failed to write the dictionary to {output}: {e}"))?;
+    println!("Merged {entry_count} entries into {name} ({output}).");
+    Ok(())
 }
 
 //* Definition Wrappers *//
 
-fn try_define(word: &str) {
-    if let Ok(definitions) = dictionary::define(word) {
-        for definition in definitions.iter() {
-            print!("{definition}");
+/// How the outcomes of the providers are printed.
+struct Printer<'a> {
+    cmudict: Option<&'a Cmudict>,
+    options: DisplayOptions,
+    /// The semantics to print under every definition.
+    semantics: &'a [Semantic],
+    /// Whether several words are looked up, so that failures name their word.
+    batch: bool,
+}
+
+/// What the lookup of a word came to.
+#[derive(Default)]
+struct Report {
+    /// Whether any provider defined the word.
+    found: bool,
+    /// The entries of the providers with recordings.
+    entries: Vec<Entry>,
+    /// Why the providers that explained themselves don't have the word.
+    notes: Vec<String>,
+    error: Option<ProviderError>,
+}
+
+impl Printer<'_> {
+    fn print(&self, word: &str, outcome: Outcome, report: &mut Report) {
+        let lookup = match outcome.result {
+            Ok(lookup) if !lookup.is_found() => {
+                report.notes.extend(lookup.notes);
+                return;
+            }
+            Ok(lookup) => lookup,
+            Err(e) => {
+                eprintln!("{}{e}", self.prefix(word));
+                report.error.get_or_insert(e);
+                return;
+            }
+        };
+
+        report.found = true;
+        for note in lookup.notes.iter() {
+            eprintln!("{}{note}", self.prefix(word));
+        }
+        print_lemma(word, &lookup.lemma);
+        for mut entry in lookup.entries {
+            decorate(&mut entry, self.cmudict);
+            print!("{}", entry.display(self.options));
+            if outcome.capabilities.semantics {
+                for semantic in self.semantics.iter() {
+                    if let Some(related) = entry.get_semantics(semantic) {
+                        println!("{}: {related:?} ", semantic.label());
+                    }
+                }
+            }
+            if outcome.capabilities.audio {
+                report.entries.push(entry);
+            }
+        }
+    }
+
+    /// Reports a word none of the providers had, along with the
+    /// headwords close to it.
+    fn print_missing(&self, word: &str, report: &Report, suggestions: Vec<String>) {
+        if report.notes.is_empty() {
+            println!("{}Definition for {word} not found.", self.prefix(word));
+        }
+        for note in report.notes.iter() {
+            println!("{}{note}", self.prefix(word));
+        }
+        if !suggestions.is_empty() {
+            println!(
+                "{}Did you mean: {}?",
+                self.prefix(word),
+                suggestions.join(", ")
+            );
+        }
+    }
+
+    fn prefix(&self, word: &str) -> String {
+        if self.batch {
+            format!("{word}: ")
+        } else {
+            String::new()
         }
-        return;
     }
-    println!("Definition for {word} not found.");
 }
 
-fn try_define_urban(word: &str) {
-    if let Ok(dictionary_def) = urban_dictionary::define(word) {
-        print!("{dictionary_def}");
-        return;
+/// Sets the ARPAbet pronunciations of the defined word next to its phonetics.
+fn decorate(entry: &mut Entry, cmudict: Option<&Cmudict>) {
+    if let Some(cmudict) = cmudict {
+        entry.arpabet = cmudict.pronunciations(&entry.word);
     }
-    println!("Urban Dictionary failed to make a connection. Please try again later.");
 }
 
-//* Semantic Wrappers *//
+/// Downloads the recording of the defined word, then plays it if a player is given.
+async fn try_get_audio(
+    client: &Client,
+    entries: &[Entry],
+    region: Option<&str>,
+    output: Option<&str>,
+    player: Option<&str>,
+) {
+    let Some(url) = entries.iter().find_map(|entry| entry.get_audio(region)) else {
+        match region {
+            Some(region) => println!("No {region} recording of the word was found."),
+            None => println!("No recording of the word was found."),
+        }
+        return;
+    };
 
-fn try_get_semantics(word: &str, semantic: Semantic) {
-    let definitions = match dictionary::define(word) {
-        Ok(definitions) => definitions,
-        Err(_) => {
-            println!("Definition for {word} not found.");
+    let path = match audio::download(client, url, output).await {
+        Ok(path) => path,
+        Err(e) => {
+            println!("Failed to download {url}: {e}");
             return;
         }
     };
+    println!("Saved the recording to {}", path.display());
 
-    for definition in definitions.iter() {
-        print!("{definition}");
-        match semantic {
-            Semantic::Synonym => {
-                let synonyms = definition.get_semantics(&semantic);
-                println!("Synonyms: {synonyms:?} ");
-            }
-            Semantic::Antonym => {
-                let antonyms = definition.get_semantics(&semantic);
-                println!("Antonyms: {antonyms:?} ");
-            }
+    if let Some(player) = player {
+        if let Err(e) = audio::play(player, &path) {
+            println!("Failed to play the recording: {e}");
         }
     }
 }
+
+/// Reports the lemma used whenever the word itself was not found.
+fn print_lemma(word: &str, lemma: &str) {
+    if word != lemma {
+        println!("{word} → {lemma}");
+    }
+}
+
+fn try_export(
+    dict_path: &str,
+    format: ExportFormat,
+    output: Option<&str>,
+    plain: bool,
+) -> Result<(), String> {
+    let mut dict = Stardict::new(dict_path)
+        .map_err(|e| format!("Failed to open the dictionary at {dict_path}: {e}"))?;
+
+    let Some(output) = output else {
+        let mut stdout = std::io::stdout().lock();
+        stardict::export::export(&mut dict, format, plain, &mut stdout)
+            .map_err(|e| format!("Failed to export the dictionary: {e}"))?;
+        // Keeps the metadata apart from the entries, which may be piped elsewhere
+        stardict::export::export_metadata(&dict, &mut std::io::stderr().lock())
+            .map_err(|e| format!("Failed to export the metadata: {e}"))?;
+        return Ok(());
+    };
+
+    let write_error = |e: &dyn std::fmt::Display| format!("Failed to write to {output}: {e}");
+    let file = std::fs::File::create(output).map_err(|e| write_error(&e))?;
+    let mut writer = std::io::BufWriter::new(file);
+    let entry_count = stardict::export::export(&mut dict, format, plain, &mut writer)
+        .map_err(|e| write_error(&e))?;
+
+    let metadata_path = format!("{output}.meta.json");
+    let mut metadata = std::fs::File::create(&metadata_path).map_err(|e| write_error(&e))?;
+    stardict::export::export_metadata(&dict, &mut metadata).map_err(|e| write_error(&e))?;
+
+    println!("Exported {entry_count} entries to {output} ({metadata_path}).");
+    Ok(())
+}
+
+fn try_import(
+    source_path: &str,
+    format: Option<ImportFormat>,
+    output: &str,
+    name: Option<String>,
+) -> Result<(), String> {
+    let source =
+        std::fs::read(source_path).map_err(|e| format!("Failed to read {source_path}: {e}"))?;
+    let format = format.unwrap_or_else(|| ImportFormat::from_path(source_path));
+    let name = name.unwrap_or_else(|| {
+        std::path::Path::new(source_path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+
+    let imported = stardict::import::import(format, &source, &name)
+        .map_err(|e| format!("Failed to import {source_path}: {e}"))?;
+    if imported.is_empty() {
+        return Err(format!("No entries were found in {source_path}."));
+    }
+
+    let (entry_count, bookname) = (imported.len(), imported.bookname.clone());
+    imported
+        .write(output)
+        .map_err(|e| format!("Failed to write the dictionary to {output}: {e}"))?;
+    println!("Imported {entry_count} entries into {bookname} ({output}).");
+    Ok(())
+}
+
+//* Rhyme Wrappers *//
+
+fn open_cmudict(path: &str) -> Result<Cmudict, String> {
+    Cmudict::new(path).map_err(|e| format!("Failed to open the CMU Pronouncing Dictionary: {e}"))
+}
+
+fn try_list_stressed(cmudict: &Cmudict, pattern: &str) {
+    let words = cmudict.words_with_stress(pattern);
+    println!("Words stressed {pattern}:");
+    if words.is_empty() {
+        println!("    None found.");
+        return;
+    }
+    for word in words {
+        println!("    {word}");
+    }
+}
+
+fn try_get_rhymes(cmudict: &Cmudict, word: &str, stress: Option<&str>, color: bool) {
+    let pronunciations = cmudict.pronunciations(word);
+    if pronunciations.is_empty() {
+        println!("Pronunciation for {word} not found.");
+        return;
+    }
+
+    println!();
+    for pronunciation in pronunciations.iter() {
+        println!("{}    [{pronunciation}]", print_colored(word, color));
+    }
+    if let (Some(syllables), Some(pattern)) = (cmudict.syllables(word), cmudict.stress(word)) {
+        println!("Syllables: {syllables}, stress: {pattern}");
+    }
+
+    let (perfect, near) = cmudict.rhymes(word);
+    let matching = |rhymes: Vec<Rhyme>| -> Vec<Rhyme> {
+        rhymes
+            .into_iter()
+            .filter(|rhyme| {
+                stress.is_none_or(|pattern| cmudict.matches_stress(&rhyme.word, pattern))
+            })
+            .collect()
+    };
+    print_rhymes("Perfect rhymes", matching(perfect));
+    print_rhymes("Near rhymes", matching(near));
+}
+
+/// Lists the rhymes grouped by their number of syllables.
+fn print_rhymes(label: &str, rhymes: Vec<Rhyme>) {
+    println!("{label}:");
+    if rhymes.is_empty() {
+        println!("    None found.");
+        return;
+    }
+
+    let mut by_syllables: std::collections::BTreeMap<usize, Vec<String>> = Default::default();
+    for rhyme in rhymes {
+        by_syllables
+            .entry(rhyme.syllables)
+            .or_default()
+            .push(rhyme.word);
+    }
+    for (syllables, words) in by_syllables.iter() {
+        let unit = if *syllables == 1 {
+            "syllable"
+        } else {
+            "syllables"
+        };
+        println!("    {syllables} {unit}: {words:?}");
+    }
+}
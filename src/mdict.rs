@@ -0,0 +1,952 @@
+//! A module containing the reader for MDict dictionaries, made
+//! out of a `.mdx` file and optionally a `.mdd` file of resources.
+//!
+
+mod lzo;
+mod ripemd;
+
+use crate::{
+    cache,
+    render::{self, Token},
+};
+use flate2::read::ZlibDecoder;
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// The prefix of entries redirecting to another headword.
+const LINK: &str = "@@@LINK=";
+/// The amount of redirections followed before giving up.
+const MAX_LINKS: usize = 5;
+/// How many times larger than its compressed size a block is trusted to be.
+const MAX_RATIO: usize = 1024;
+
+/// A headword along with the range of its record.
+struct Key {
+    folded: String,
+    start: u64,
+    end: u64,
+}
+
+/// A key block, located in the file, along with the
+/// first and last of its keys, folded.
+struct KeyBlock {
+    first: String,
+    last: String,
+    file_offset: u64,
+    compressed_size: usize,
+    size: usize,
+}
+
+/// A record block, located both in the file and in the decompressed records.
+struct RecordBlock {
+    file_offset: u64,
+    compressed_size: usize,
+    offset: u64,
+    size: u64,
+}
+
+/// A struct for looking keys up in a single .mdx or .mdd file.
+///
+/// Every file is made out of:
+///     - a header ;; an XML tag holding the dictionary's attributes
+///     - the key block index ;; the first and last keys of every key
+///                              block, which is searched for the block
+///                              a key is in
+///     - the key blocks ;; compressed lists of every headword and the
+///                         offset of its record, which are only
+///                         decompressed when needed
+///     - the record blocks ;; compressed records, which are
+///                            only decompressed when needed
+pub struct MdictFile {
+    file: File,
+    pub header: BTreeMap<String, String>,
+    utf16: bool,
+    strip_key: bool,
+    width: usize,
+    key_blocks: Vec<KeyBlock>,
+    /// Every key, sorted, for the files whose key blocks
+    /// aren't in the order keys are folded in.
+    keys: Option<Vec<Key>>,
+    key_cache: Option<(usize, Vec<(String, u64)>)>,
+    blocks: Vec<RecordBlock>,
+    /// The size of the decompressed records, where the last record ends.
+    records_size: u64,
+    cache: Option<(usize, Vec<u8>)>,
+}
+
+/// A struct reading sized numbers out of a buffer.
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let taken = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or_else(|| invalid_data("Unexpected end of data."))?;
+        self.pos += len;
+        Ok(taken)
+    }
+
+    /// Reads a big-endian number `width` bytes long.
+    fn number(&mut self, width: usize) -> io::Result<u64> {
+        Ok(self
+            .take(width)?
+            .iter()
+            .fold(0, |number, &byte| number << 8 | byte as u64))
+    }
+
+    /// Reads the first or last key of a key block, as
+    /// written in the key block index.
+    fn bound_key(&mut self, v2: bool, utf16: bool) -> io::Result<String> {
+        let len = self.number(if v2 { 2 } else { 1 })? as usize + v2 as usize;
+        let text = self.take(if utf16 { len * 2 } else { len })?;
+        let text = match utf16 {
+            true => decode_utf16(text),
+            false => String::from_utf8_lossy(text).to_string(),
+        };
+        Ok(text.trim_end_matches('\0').to_string())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+}
+
+impl MdictFile {
+    /// Opens an MDict file, reading its header and the index of its keys.
+    pub fn open(path: &str) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let is_mdd = path.ends_with(".mdd");
+
+        let header_len = read_number(&mut file, 4)? as usize;
+        let header_bytes = read_bytes(&mut file, header_len)?;
+        // The header is followed by its checksum
+        read_bytes(&mut file, 4)?;
+        let header = parse_header(&decode_utf16(&header_bytes))
+            .ok_or_else(|| invalid_data("Invalid MDict header."))?;
+
+        let version: f32 = header
+            .get("generatedbyengineversion")
+            .and_then(|version| version.trim().parse().ok())
+            .unwrap_or(1.0);
+        if version >= 3.0 {
+            return Err(invalid_data(&format!(
+                "MDict version {version} is not supported."
+            )));
+        }
+        let v2 = version >= 2.0;
+        let width = if v2 { 8 } else { 4 };
+
+        let encrypted = match header.get("encrypted").map(String::as_str) {
+            None | Some("") | Some("No") => 0,
+            Some("Yes") => 1,
+            Some(flags) => flags.parse().unwrap_or(0),
+        };
+        if encrypted & 1 != 0 {
+            return Err(invalid_data(
+                "Dictionaries requiring registration are not supported.",
+            ));
+        }
+
+        let encoding = header
+            .get("encoding")
+            .map(|encoding| encoding.to_uppercase())
+            .unwrap_or_default();
+        let utf16 = match encoding.as_str() {
+            _ if is_mdd => true,
+            "" | "UTF-8" | "UTF8" => false,
+            "UTF-16" | "UTF-16LE" => true,
+            _ => {
+                return Err(invalid_data(&format!(
+                    "The {encoding} encoding is not supported."
+                )))
+            }
+        };
+        let strip_key = !is_mdd && header.get("stripkey").is_none_or(|strip| strip != "No");
+
+        // The key section
+        let section = read_bytes(&mut file, if v2 { 5 * width + 4 } else { 4 * width })?;
+        let mut cursor = Cursor::new(&section);
+        let block_count = cursor.number(width)? as usize;
+        cursor.number(width)?;
+        if v2 {
+            cursor.number(width)?;
+        }
+        let info_size = cursor.number(width)? as usize;
+        let key_blocks_size = cursor.number(width)? as usize;
+
+        let mut info = read_bytes(&mut file, info_size)?;
+        if v2 {
+            if encrypted & 2 != 0 {
+                decrypt_key_info(&mut info)?;
+            }
+            info = decompress_block(&info, 0)?;
+        }
+        let mut file_offset = file.stream_position()?;
+        file.seek(SeekFrom::Current(key_blocks_size as i64))?;
+
+        let mut cursor = Cursor::new(&info);
+        let mut key_blocks = Vec::with_capacity(block_count.min(info.len()));
+        for _ in 0..block_count {
+            cursor.number(width)?;
+            let first = fold(&cursor.bound_key(v2, utf16)?, strip_key);
+            let last = fold(&cursor.bound_key(v2, utf16)?, strip_key);
+            let compressed_size = cursor.number(width)? as usize;
+            key_blocks.push(KeyBlock {
+                first,
+                last,
+                file_offset,
+                compressed_size,
+                size: cursor.number(width)? as usize,
+            });
+            file_offset = checked_add(file_offset, compressed_size as u64)?;
+        }
+
+        // The record section
+        let section = read_bytes(&mut file, 4 * width)?;
+        let mut cursor = Cursor::new(&section);
+        let block_count = cursor.number(width)? as usize;
+        cursor.number(width)?;
+        let info_size = cursor.number(width)? as usize;
+        cursor.number(width)?;
+
+        let info = read_bytes(&mut file, info_size)?;
+        let mut cursor = Cursor::new(&info);
+        let mut file_offset = file.stream_position()?;
+        let mut offset = 0;
+        let mut blocks = Vec::with_capacity(block_count.min(info.len()));
+        for _ in 0..block_count {
+            let compressed_size = cursor.number(width)? as usize;
+            let size = cursor.number(width)?;
+            blocks.push(RecordBlock {
+                file_offset,
+                compressed_size,
+                offset,
+                size,
+            });
+            file_offset = checked_add(file_offset, compressed_size as u64)?;
+            offset = checked_add(offset, size)?;
+        }
+
+        let mut mdict = Self {
+            file,
+            header,
+            utf16,
+            strip_key,
+            width,
+            key_blocks,
+            keys: None,
+            key_cache: None,
+            blocks,
+            records_size: offset,
+            cache: None,
+        };
+        // Keys are folded the way most dictionaries sort them, but the few
+        // that don't can only be searched once all of their keys are sorted
+        let sorted = mdict
+            .key_blocks
+            .windows(2)
+            .all(|pair| pair[0].last <= pair[1].first)
+            && mdict
+                .key_blocks
+                .iter()
+                .all(|block| block.first <= block.last);
+        if !sorted {
+            mdict.keys = Some(mdict.sorted_keys()?);
+        }
+        Ok(mdict)
+    }
+
+    /// Decompresses every key block, sorting the keys by their folded form.
+    fn sorted_keys(&mut self) -> io::Result<Vec<Key>> {
+        let mut keys = vec![];
+        for index in 0..self.key_blocks.len() {
+            let block = self.load_keys(index)?;
+            for (i, (word, start)) in block.iter().enumerate() {
+                let end = match block.get(i + 1) {
+                    Some((_, next)) => *next,
+                    None => self.next_start(index + 1)?,
+                };
+                keys.push(Key {
+                    folded: fold(word, self.strip_key),
+                    start: *start,
+                    end,
+                });
+            }
+        }
+        keys.sort_by(|left, right| left.folded.cmp(&right.folded));
+        Ok(keys)
+    }
+
+    /// Grabs the raw records of every key matching the word, decompressing
+    /// only the key blocks whose first and last keys surround it.
+    pub fn lookup(&mut self, word: &str) -> io::Result<Vec<Vec<u8>>> {
+        let folded = fold(word, self.strip_key);
+        let ranges: Vec<(u64, u64)> = match self.keys.as_ref() {
+            Some(keys) => {
+                let first = keys.partition_point(|key| key.folded < folded);
+                keys[first..]
+                    .iter()
+                    .take_while(|key| key.folded == folded)
+                    .map(|key| (key.start, key.end))
+                    .collect()
+            }
+            None => {
+                let mut ranges = vec![];
+                let mut index = self.key_blocks.partition_point(|block| block.last < folded);
+                while index < self.key_blocks.len() && self.key_blocks[index].first <= folded {
+                    let block = self.load_keys(index)?;
+                    for (i, (word, start)) in block.iter().enumerate() {
+                        if fold(word, self.strip_key) != folded {
+                            continue;
+                        }
+                        // Records run up to the start of the next one
+                        let end = match block.get(i + 1) {
+                            Some((_, next)) => *next,
+                            None => self.next_start(index + 1)?,
+                        };
+                        ranges.push((*start, end));
+                    }
+                    index += 1;
+                }
+                ranges
+            }
+        };
+
+        ranges
+            .into_iter()
+            .map(|(start, end)| self.read_record(start, end))
+            .collect()
+    }
+
+    /// Grabs where the record of the first key of a block starts,
+    /// which is where the last record of the block before ends.
+    fn next_start(&mut self, index: usize) -> io::Result<u64> {
+        if index >= self.key_blocks.len() {
+            return Ok(self.records_size);
+        }
+        let block = self.load_keys(index)?;
+        Ok(block.first().map_or(self.records_size, |(_, start)| *start))
+    }
+
+    /// Decompresses a key block into its keys and record offsets,
+    /// keeping the latest one around.
+    fn load_keys(&mut self, index: usize) -> io::Result<Vec<(String, u64)>> {
+        if let Some((cached, keys)) = self.key_cache.as_ref() {
+            if *cached == index {
+                return Ok(keys.clone());
+            }
+        }
+        let block = &self.key_blocks[index];
+        self.file.seek(SeekFrom::Start(block.file_offset))?;
+        let compressed = read_bytes(&mut self.file, block.compressed_size)?;
+        let keys = split_keys(
+            &decompress_block(&compressed, block.size)?,
+            self.width,
+            self.utf16,
+        )?;
+        self.key_cache = Some((index, keys.clone()));
+        Ok(keys)
+    }
+
+    /// Grabs the records of every key matching the word as text.
+    pub fn lookup_text(&mut self, word: &str) -> io::Result<Vec<String>> {
+        let records = self.lookup(word)?;
+        Ok(records
+            .iter()
+            .map(|record| {
+                let text = match self.utf16 {
+                    true => decode_utf16(record),
+                    false => String::from_utf8_lossy(record).to_string(),
+                };
+                text.trim_end_matches(['\0', '\r', '\n']).to_string()
+            })
+            .collect())
+    }
+
+    fn read_record(&mut self, start: u64, end: u64) -> io::Result<Vec<u8>> {
+        let mut record = vec![];
+        let mut index = self
+            .blocks
+            .partition_point(|block| block.offset + block.size <= start);
+        while index < self.blocks.len() && self.blocks[index].offset < end {
+            let block_offset = self.blocks[index].offset;
+            let block = self.load_block(index)?;
+            let from = start.saturating_sub(block_offset) as usize;
+            let to = ((end - block_offset) as usize).min(block.len());
+            record.extend_from_slice(block.get(from..to).unwrap_or_default());
+            index += 1;
+        }
+        Ok(record)
+    }
+
+    /// Decompresses a record block, keeping the latest one around since
+    /// neighbouring records are often looked up together.
+    fn load_block(&mut self, index: usize) -> io::Result<&[u8]> {
+        if self
+            .cache
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != index)
+        {
+            let block = &self.blocks[index];
+            self.file.seek(SeekFrom::Start(block.file_offset))?;
+            let compressed = read_bytes(&mut self.file, block.compressed_size)?;
+            let decompressed = decompress_block(&compressed, block.size as usize)?;
+            self.cache = Some((index, decompressed));
+        }
+        Ok(self
+            .cache
+            .as_ref()
+            .map_or(&[], |(_, block)| block.as_slice()))
+    }
+}
+
+/// A struct for looking words up in an MDict dictionary.
+pub struct Mdict {
+    pub mdx: MdictFile,
+    pub mdd: Option<MdictFile>,
+    name: String,
+    stylesheet: BTreeMap<String, (String, String)>,
+    /// Where resources are extracted to, inside the user's cache.
+    resource_dir: Option<PathBuf>,
+}
+
+impl Mdict {
+    /// Opens the MDict dictionary found at the given path, along with
+    /// the .mdd file of the same name holding its resources, if any.
+    ///
+    /// The path can either be the common prefix of the dictionary
+    /// files or the path of its .mdx or .mdd file.
+    pub fn new(path: &str) -> io::Result<Self> {
+        let prefix = mdict_prefix(path);
+        let mdx = MdictFile::open(&format!("{prefix}.mdx"))?;
+        let mdd = MdictFile::open(&format!("{prefix}.mdd")).ok();
+
+        let name = mdx
+            .header
+            .get("title")
+            .map(|title| title.trim())
+            .filter(|title| !title.is_empty() && !title.starts_with("Title (No HTML"))
+            .map(String::from)
+            .unwrap_or_else(|| {
+                Path::new(&prefix)
+                    .file_name()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+        let stylesheet = mdx
+            .header
+            .get("stylesheet")
+            .map(|stylesheet| parse_stylesheet(stylesheet))
+            .unwrap_or_default();
+        let resource_dir =
+            cache::cache_dir().map(|dir| dir.join("mdict").join(name.replace(['/', '\\'], "_")));
+
+        Ok(Self {
+            mdx,
+            mdd,
+            name,
+            stylesheet,
+            resource_dir,
+        })
+    }
+
+    /// Grabs the name of the dictionary.
+    pub fn get_bookname(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Grabs every entry of the specified word, joined together, with
+    /// redirections followed and resources extracted from the .mdd file.
+    pub fn get(&mut self, word: &str) -> io::Result<Option<String>> {
+        let mut word = word.to_string();
+        for _ in 0..MAX_LINKS {
+            let entries = self.mdx.lookup_text(&word)?;
+            if let Some(target) = entries
+                .first()
+                .and_then(|entry| entry.trim().strip_prefix(LINK))
+            {
+                word = target.trim().to_string();
+                continue;
+            }
+            if entries.is_empty() {
+                return Ok(None);
+            }
+
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|entry| substitute_stylesheet(entry, &self.stylesheet))
+                .collect();
+            return Ok(Some(self.resolve_resources(&entries.join("<hr>"))?));
+        }
+        Ok(None)
+    }
+
+    /// Grabs the resource stored under the given path in the .mdd file.
+    pub fn resource(&mut self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let Some(mdd) = self.mdd.as_mut() else {
+            return Ok(None);
+        };
+        let name = format!("\\{}", name.replace('/', "\\").trim_start_matches('\\'));
+        Ok(mdd.lookup(&name)?.into_iter().next())
+    }
+
+    /// Extracts the sounds and images the entry refers to out of the
+    /// .mdd file, pointing the entry at the extracted files instead.
+    fn resolve_resources(&mut self, entry: &str) -> io::Result<String> {
+        if self.mdd.is_none() {
+            return Ok(entry.to_string());
+        }
+
+        let mut resolved = String::with_capacity(entry.len());
+        let mut rest = entry;
+        while let Some(start) = ["href=\"sound://", "src=\""]
+            .iter()
+            .filter_map(|attr| rest.find(attr).map(|i| i + attr.len()))
+            .min()
+        {
+            resolved.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest.find('"').unwrap_or(rest.len());
+            let name = &rest[..end];
+            rest = &rest[end..];
+
+            match self.extract(name)? {
+                Some(path) => {
+                    // Drop the `sound://` scheme too, if there's one
+                    if resolved.ends_with("sound://") {
+                        resolved.truncate(resolved.len() - "sound://".len());
+                    }
+                    resolved.push_str(&format!("file://{}", path.display()));
+                }
+                None => resolved.push_str(name),
+            }
+        }
+        resolved.push_str(rest);
+        Ok(resolved)
+    }
+
+    /// Writes a resource out to the cache directory, returning its path.
+    ///
+    /// Resources are only ever written once: one that was extracted
+    /// before is pointed at as is, and files are never written through.
+    fn extract(&mut self, name: &str) -> io::Result<Option<PathBuf>> {
+        if name.contains("://") {
+            return Ok(None);
+        }
+        let Some(mut path) = self.resource_dir.clone() else {
+            return Ok(None);
+        };
+        for component in name.split(['/', '\\']) {
+            if !component.is_empty() && component != "." && component != ".." {
+                path.push(component);
+            }
+        }
+        if fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_file()) {
+            return Ok(Some(path));
+        }
+        let Some(resource) = self.resource(name)? else {
+            return Ok(None);
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        File::options()
+            .write(true)
+            .create_new(true)
+            .open(&path)?
+            .write_all(&resource)?;
+        Ok(Some(path))
+    }
+}
+
+/// Strips the file extension off of any of the dictionary's files.
+pub fn mdict_prefix(path: &str) -> String {
+    [".mdx", ".mdd"]
+        .iter()
+        .find_map(|ext| path.strip_suffix(ext))
+        .unwrap_or(path)
+        .to_string()
+}
+
+/// Parses the attributes of the header's `<Dictionary>` tag.
+fn parse_header(header: &str) -> Option<BTreeMap<String, String>> {
+    render::tokenize(header.trim_end_matches('\0'))
+        .into_iter()
+        .find_map(|token| match token {
+            Token::Open(tag) => Some(tag.attrs),
+            _ => None,
+        })
+}
+
+/// Parses the header's stylesheet, made out of groups of three lines: a
+/// style number, followed by the markup replacing the start and end of
+/// the text that style number applies to.
+fn parse_stylesheet(stylesheet: &str) -> BTreeMap<String, (String, String)> {
+    let lines: Vec<&str> = stylesheet.lines().map(str::trim).collect();
+    lines
+        .chunks_exact(3)
+        .map(|style| {
+            (
+                style[0].to_string(),
+                (style[1].to_string(), style[2].to_string()),
+            )
+        })
+        .collect()
+}
+
+/// Replaces the `` `N` `` style markers of a record with
+/// the markup the header's stylesheet assigns them.
+fn substitute_stylesheet(entry: &str, stylesheet: &BTreeMap<String, (String, String)>) -> String {
+    if stylesheet.is_empty() {
+        return entry.to_string();
+    }
+
+    let mut parts = entry.split('`');
+    let mut substituted = parts.next().unwrap_or_default().to_string();
+    while let Some(marker) = parts.next() {
+        let Some((start, end)) = stylesheet.get(marker) else {
+            substituted.push('`');
+            substituted.push_str(marker);
+            continue;
+        };
+        let text = parts.next().unwrap_or_default();
+        substituted.push_str(&format!("{start}{text}{end}"));
+    }
+    substituted
+}
+
+/// Splits a decompressed key block into its keys and record offsets.
+fn split_keys(block: &[u8], width: usize, utf16: bool) -> io::Result<Vec<(String, u64)>> {
+    let mut keys = vec![];
+    let mut cursor = Cursor::new(block);
+    while !cursor.is_empty() {
+        let offset = cursor.number(width)?;
+        let rest = &block[cursor.pos..];
+        let (key, len) = match utf16 {
+            true => {
+                let end = rest
+                    .chunks_exact(2)
+                    .position(|chr| chr == [0, 0])
+                    .map_or(rest.len(), |i| i * 2);
+                (decode_utf16(&rest[..end]), end + 2)
+            }
+            false => {
+                let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+                (String::from_utf8_lossy(&rest[..end]).to_string(), end + 1)
+            }
+        };
+        cursor.pos += len;
+        keys.push((key, offset));
+    }
+    Ok(keys)
+}
+
+/// Decompresses a key or record block, which starts with its
+/// compression type and checksum.
+fn decompress_block(block: &[u8], size: usize) -> io::Result<Vec<u8>> {
+    let Some(data) = block.get(8..) else {
+        return Err(invalid_data("Block is too short."));
+    };
+    // The size comes from the file, so it only hints at how much to allocate
+    let capacity = size.min(data.len().saturating_mul(MAX_RATIO));
+    match block[0] {
+        0 => Ok(data.to_vec()),
+        1 => lzo::decompress(data, capacity).ok_or_else(|| invalid_data("Invalid LZO block.")),
+        2 => {
+            let mut decompressed = Vec::with_capacity(capacity);
+            ZlibDecoder::new(data).read_to_end(&mut decompressed)?;
+            Ok(decompressed)
+        }
+        other => Err(invalid_data(&format!(
+            "Unknown block compression type {other}."
+        ))),
+    }
+}
+
+/// Decrypts the key block index in place; its key is derived
+/// from the index's checksum.
+fn decrypt_key_info(info: &mut [u8]) -> io::Result<()> {
+    if info.len() < 8 {
+        return Err(invalid_data("Key block index is too short."));
+    }
+    let mut seed = info[4..8].to_vec();
+    seed.extend_from_slice(&0x3695u32.to_le_bytes());
+    let key = ripemd::digest(&seed);
+
+    let mut previous = 0x36;
+    for (i, byte) in info[8..].iter_mut().enumerate() {
+        let encrypted = *byte;
+        *byte = encrypted.rotate_left(4) ^ previous ^ (i as u8) ^ key[i % key.len()];
+        previous = encrypted;
+    }
+    Ok(())
+}
+
+/// Folds a key the way MDict compares them: case-insensitively,
+/// and unless the dictionary says otherwise, ignoring punctuation.
+fn fold(word: &str, strip_key: bool) -> String {
+    word.trim()
+        .to_lowercase()
+        .chars()
+        .filter(|chr| !strip_key || chr.is_alphanumeric())
+        .collect()
+}
+
+fn decode_utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Reads `len` bytes, making sure the file has them before
+/// allocating, since lengths come from the file itself.
+fn read_bytes(file: &mut File, len: usize) -> io::Result<Vec<u8>> {
+    let remaining = file
+        .metadata()?
+        .len()
+        .saturating_sub(file.stream_position()?);
+    if len as u64 > remaining {
+        return Err(invalid_data("Unexpected end of file."));
+    }
+    let mut buf = vec![0u8; len];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_number(file: &mut File, width: usize) -> io::Result<u64> {
+    Cursor::new(&read_bytes(file, width)?).number(width)
+}
+
+/// Adds up the offsets of the blocks, which overflow if their sizes are corrupt.
+fn checked_add(offset: u64, size: u64) -> io::Result<u64> {
+    offset
+        .checked_add(size)
+        .ok_or_else(|| invalid_data("Block sizes overflow."))
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use flate2::{write::ZlibEncoder, Compression};
+
+    /// Writes an MDict file holding the given entries, each key and record
+    /// in blocks of their own so that lookups span several blocks.
+    fn write_mdict(path: &str, version: &str, entries: &[(&str, &[u8])]) {
+        let v2 = version == "2.0";
+        let is_mdd = path.ends_with(".mdd");
+        let width = if v2 { 8 } else { 4 };
+        let number = |n: usize| (n as u64).to_be_bytes()[8 - width..].to_vec();
+        let text = |text: &str| -> Vec<u8> {
+            match is_mdd {
+                true => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+                false => text.as_bytes().to_vec(),
+            }
+        };
+        let zlib = |data: &[u8]| {
+            let mut encoder =
+                ZlibEncoder::new(vec![2, 0, 0, 0, 0, 0, 0, 0], Compression::default());
+            encoder.write_all(data).expect("Data should compress");
+            encoder.finish().expect("Data should compress")
+        };
+        // A literal-only LZO stream, followed by the end marker
+        let lzo = |data: &[u8]| {
+            let mut block = vec![1, 0, 0, 0, 0, 0, 0, 0, 17 + data.len() as u8];
+            block.extend_from_slice(data);
+            block.extend_from_slice(&[17, 0, 0]);
+            block
+        };
+        let compress = |data: &[u8]| if v2 { zlib(data) } else { lzo(data) };
+
+        let header = format!(
+            "<Dictionary GeneratedByEngineVersion=\"{version}\" Encrypted=\"{}\" \
+             Encoding=\"UTF-8\" Format=\"Html\" Title=\"Test\" \
+             StyleSheet=\"1\n<i>\n</i>\n\"/>\r\n\0",
+            if v2 { 2 } else { 0 }
+        );
+        let header: Vec<u8> = header.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut out = (header.len() as u32).to_be_bytes().to_vec();
+        out.extend(header);
+        out.extend([0; 4]);
+
+        let terminator = if is_mdd { vec![0, 0] } else { vec![0] };
+        let (mut info, mut key_blocks, mut records) = (vec![], vec![], vec![]);
+        for (word, record) in entries.iter() {
+            let mut key_block = number(records.iter().map(Vec::len).sum());
+            key_block.extend(text(word));
+            key_block.extend(&terminator);
+            records.push(record.to_vec());
+            let compressed = compress(&key_block);
+
+            info.extend(number(1));
+            for _ in 0..2 {
+                let len = word.chars().count();
+                info.extend(if v2 {
+                    (len as u16).to_be_bytes().to_vec()
+                } else {
+                    vec![len as u8]
+                });
+                info.extend(text(word));
+                if v2 {
+                    info.extend(&terminator);
+                }
+            }
+            info.extend(number(compressed.len()));
+            info.extend(number(key_block.len()));
+            key_blocks.extend(compressed);
+        }
+
+        let mut info_block = info.clone();
+        if v2 {
+            info_block = zlib(&info);
+            info_block[4..8].copy_from_slice(&[1, 2, 3, 4]);
+            let mut seed = info_block[4..8].to_vec();
+            seed.extend_from_slice(&0x3695u32.to_le_bytes());
+            let key = ripemd::digest(&seed);
+            let mut previous = 0x36;
+            for (i, byte) in info_block[8..].iter_mut().enumerate() {
+                let encrypted = (*byte ^ previous ^ (i as u8) ^ key[i % key.len()]).rotate_right(4);
+                *byte = encrypted;
+                previous = encrypted;
+            }
+        }
+
+        out.extend(number(entries.len()));
+        out.extend(number(entries.len()));
+        if v2 {
+            out.extend(number(info.len()));
+        }
+        out.extend(number(info_block.len()));
+        out.extend(number(key_blocks.len()));
+        if v2 {
+            out.extend([0; 4]);
+        }
+        out.extend(info_block);
+        out.extend(key_blocks);
+
+        let blocks: Vec<Vec<u8>> = records.iter().map(|record| compress(record)).collect();
+        out.extend(number(blocks.len()));
+        out.extend(number(entries.len()));
+        out.extend(number(blocks.len() * 2 * width));
+        out.extend(number(blocks.iter().map(Vec::len).sum()));
+        for (block, record) in blocks.iter().zip(records.iter()) {
+            out.extend(number(block.len()));
+            out.extend(number(record.len()));
+        }
+        for block in blocks {
+            out.extend(block);
+        }
+
+        fs::write(path, out).expect("Dictionary should be written");
+    }
+
+    #[test]
+    fn mdx_v2_lookup_test() {
+        let dir = TempDir::new("mdict-v2");
+        let prefix = dir.join("test").to_string_lossy().to_string();
+        write_mdict(
+            &format!("{prefix}.mdx"),
+            "2.0",
+            &[
+                (
+                    "apple",
+                    b"<b>fruit</b> <a href=\"sound://apple.mp3\">listen</a>\r\n\0",
+                ),
+                ("Apple-pie", b"`1`dessert\0"),
+                ("pomme", b"@@@LINK=apple\r\n\0"),
+            ],
+        );
+        write_mdict(&format!("{prefix}.mdd"), "2.0", &[("\\apple.mp3", b"ID3")]);
+
+        let mut mdict = Mdict::new(&format!("{prefix}.mdx")).expect("Dictionary should parse");
+        mdict.resource_dir = Some(dir.join("resources"));
+        assert_eq!("Test", mdict.get_bookname());
+        assert_eq!(
+            Some("<i>dessert</i>".to_string()),
+            mdict
+                .get("apple pie")
+                .expect("Dictionary should be readable")
+        );
+
+        let entry = mdict
+            .get("Pomme")
+            .expect("Dictionary should be readable")
+            .expect("Link should be followed");
+        let sound = entry
+            .split("href=\"file://")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .expect("Sound should be extracted");
+        assert!(entry.starts_with("<b>fruit</b>"));
+        assert!(sound.starts_with(&dir.join("resources").to_string_lossy().to_string()));
+        assert_eq!(
+            b"ID3".to_vec(),
+            fs::read(sound).expect("Sound should exist")
+        );
+        // Extracted resources are reused rather than written again
+        assert_eq!(
+            Some(entry),
+            mdict.get("pomme").expect("Dictionary should be readable")
+        );
+        assert_eq!(
+            None,
+            mdict.get("pear").expect("Dictionary should be readable")
+        );
+    }
+
+    #[test]
+    fn mdx_v1_lookup_test() {
+        let dir = TempDir::new("mdict-v1");
+        let path = dir.join("test.mdx").to_string_lossy().to_string();
+        write_mdict(
+            &path,
+            "1.2",
+            &[("cat", b"A pet.\0"), ("dog", b"Another pet.\0")],
+        );
+
+        let mut mdict = Mdict::new(&path).expect("Dictionary should parse");
+        assert!(mdict.mdd.is_none());
+        assert_eq!(
+            Some("Another pet.".to_string()),
+            mdict.get("dog").expect("Dictionary should be readable")
+        );
+        assert_eq!(
+            Some("A pet.".to_string()),
+            mdict.get("CAT").expect("Dictionary should be readable")
+        );
+    }
+
+    #[test]
+    fn corrupt_sizes_should_be_errors() {
+        let mut cursor = Cursor::new(&[1, 2]);
+        cursor.take(1).expect("Byte should be taken");
+        assert!(cursor.take(usize::MAX).is_err());
+
+        let dir = TempDir::new("mdict-corrupt");
+        let path = dir.join("test.mdx");
+        // The header claims to be far longer than the file
+        fs::write(&path, [0xFF, 0xFF, 0xFF, 0xFF, 0]).expect("Temp dir should be writable");
+        assert!(MdictFile::open(&path.to_string_lossy()).is_err());
+        assert!(checked_add(u64::MAX, 1).is_err());
+    }
+}
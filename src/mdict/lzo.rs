@@ -0,0 +1,139 @@
+//! A module containing an LZO1X decompressor, which
+//! MDict uses for some of its key and record blocks.
+//!
+
+/// A struct walking through an LZO1X stream.
+struct Decoder<'a> {
+    src: &'a [u8],
+    ip: usize,
+    out: Vec<u8>,
+}
+
+impl Decoder<'_> {
+    fn byte(&mut self) -> Option<usize> {
+        let byte = *self.src.get(self.ip)?;
+        self.ip += 1;
+        Some(byte as usize)
+    }
+
+    /// Reads a run length spread over zero bytes,
+    /// each worth 255, and a final non-zero byte.
+    fn extended(&mut self) -> Option<usize> {
+        let mut len = 0;
+        while *self.src.get(self.ip)? == 0 {
+            len += 255;
+            self.ip += 1;
+        }
+        Some(len + self.byte()?)
+    }
+
+    fn le16(&mut self) -> Option<usize> {
+        Some(self.byte()? | self.byte()? << 8)
+    }
+
+    fn literals(&mut self, len: usize) -> Option<()> {
+        let literals = self.src.get(self.ip..self.ip + len)?;
+        self.out.extend_from_slice(literals);
+        self.ip += len;
+        Some(())
+    }
+
+    /// Copies bytes from earlier in the output; the
+    /// ranges may overlap, repeating the copied bytes.
+    fn copy_match(&mut self, distance: usize, len: usize) -> Option<()> {
+        let start = self.out.len().checked_sub(distance)?;
+        for i in start..start + len {
+            let byte = self.out[i];
+            self.out.push(byte);
+        }
+        Some(())
+    }
+}
+
+/// Decompresses a raw LZO1X stream, returning `None` if it is malformed.
+pub fn decompress(src: &[u8], capacity: usize) -> Option<Vec<u8>> {
+    let mut decoder = Decoder {
+        src,
+        ip: 0,
+        out: Vec::with_capacity(capacity),
+    };
+
+    // The amount of literals following the last match, which
+    // decides how the next short match is encoded
+    let mut state = 0;
+    if *src.first()? > 17 {
+        let len = decoder.byte()? - 17;
+        decoder.literals(len)?;
+        state = if len < 4 { len } else { 4 };
+    }
+
+    loop {
+        let t = decoder.byte()?;
+        let (distance, len, next);
+        if t < 16 {
+            if state == 0 {
+                let len = match t {
+                    0 => 15 + decoder.extended()?,
+                    _ => t,
+                };
+                decoder.literals(len + 3)?;
+                state = 4;
+                continue;
+            }
+            next = t & 3;
+            let low = (t >> 2) + (decoder.byte()? << 2);
+            (distance, len) = match state {
+                4 => (1 + 0x800 + low, 3),
+                _ => (1 + low, 2),
+            };
+        } else if t >= 64 {
+            next = t & 3;
+            distance = 1 + ((t >> 2) & 7) + (decoder.byte()? << 3);
+            len = (t >> 5) + 1;
+        } else if t >= 32 {
+            len = 2 + match t & 31 {
+                0 => 31 + decoder.extended()?,
+                n => n,
+            };
+            let word = decoder.le16()?;
+            distance = 1 + (word >> 2);
+            next = word & 3;
+        } else {
+            len = 2 + match t & 7 {
+                0 => 7 + decoder.extended()?,
+                n => n,
+            };
+            let word = decoder.le16()?;
+            let far = ((t & 8) << 11) + (word >> 2);
+            // A zero distance marks the end of the stream
+            if far == 0 {
+                break;
+            }
+            distance = far + 0x4000;
+            next = word & 3;
+        }
+
+        decoder.copy_match(distance, len)?;
+        decoder.literals(next)?;
+        state = next;
+    }
+
+    Some(decoder.out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lzo_should_decompress_literals_and_matches() {
+        let stream = [20, b'a', b'b', b'c', 36, 8, 0, 17, 0, 0];
+        assert_eq!(Some(b"abcabcabc".to_vec()), decompress(&stream, 9));
+    }
+
+    #[test]
+    fn lzo_should_reject_truncated_streams() {
+        assert_eq!(None, decompress(&[20, b'a', b'b'], 3));
+        assert_eq!(None, decompress(&[18, b'a', 64, 200, 17, 0, 0], 3));
+    }
+}
@@ -0,0 +1,115 @@
+//! A module containing the RIPEMD-128 hash, which MDict
+//! uses to derive the key of its encrypted key index.
+//!
+
+const LEFT_WORDS: [usize; 64] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, //
+    7, 4, 13, 1, 10, 6, 15, 3, 12, 0, 9, 5, 2, 14, 11, 8, //
+    3, 10, 14, 4, 9, 15, 8, 1, 2, 7, 0, 6, 13, 11, 5, 12, //
+    1, 9, 11, 10, 0, 8, 12, 4, 13, 3, 7, 15, 14, 5, 6, 2,
+];
+const RIGHT_WORDS: [usize; 64] = [
+    5, 14, 7, 0, 9, 2, 11, 4, 13, 6, 15, 8, 1, 10, 3, 12, //
+    6, 11, 3, 7, 0, 13, 5, 10, 14, 15, 8, 12, 4, 9, 1, 2, //
+    15, 5, 1, 3, 7, 14, 6, 9, 11, 8, 12, 2, 10, 0, 4, 13, //
+    8, 6, 4, 1, 3, 11, 15, 0, 5, 12, 2, 13, 9, 7, 10, 14,
+];
+const LEFT_SHIFTS: [u32; 64] = [
+    11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8, //
+    7, 6, 8, 13, 11, 9, 7, 15, 7, 12, 15, 9, 11, 7, 13, 12, //
+    11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5, //
+    11, 12, 14, 15, 14, 15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12,
+];
+const RIGHT_SHIFTS: [u32; 64] = [
+    8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6, //
+    9, 13, 15, 7, 12, 8, 9, 11, 7, 7, 12, 7, 6, 15, 13, 11, //
+    9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5, //
+    15, 5, 8, 11, 14, 14, 6, 14, 6, 9, 12, 9, 12, 5, 15, 8,
+];
+const LEFT_CONSTANTS: [u32; 4] = [0x00000000, 0x5A827999, 0x6ED9EBA1, 0x8F1BBCDC];
+const RIGHT_CONSTANTS: [u32; 4] = [0x50A28BE6, 0x5C4DD124, 0x6D703EF3, 0x00000000];
+
+/// The boolean function of each round.
+fn round_fn(round: usize, x: u32, y: u32, z: u32) -> u32 {
+    match round {
+        0 => x ^ y ^ z,
+        1 => (x & y) | (!x & z),
+        2 => (x | !y) ^ z,
+        _ => (x & z) | (y & !z),
+    }
+}
+
+fn compress(state: &mut [u32; 4], block: &[u8]) {
+    let words: Vec<u32> = block
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .collect();
+
+    let [mut a, mut b, mut c, mut d] = *state;
+    let [mut ra, mut rb, mut rc, mut rd] = *state;
+    for step in 0..64 {
+        let round = step / 16;
+        let t = a
+            .wrapping_add(round_fn(round, b, c, d))
+            .wrapping_add(words[LEFT_WORDS[step]])
+            .wrapping_add(LEFT_CONSTANTS[round])
+            .rotate_left(LEFT_SHIFTS[step]);
+        (a, b, c, d) = (d, t, b, c);
+
+        // The parallel line runs through the functions backwards
+        let t = ra
+            .wrapping_add(round_fn(3 - round, rb, rc, rd))
+            .wrapping_add(words[RIGHT_WORDS[step]])
+            .wrapping_add(RIGHT_CONSTANTS[round])
+            .rotate_left(RIGHT_SHIFTS[step]);
+        (ra, rb, rc, rd) = (rd, t, rb, rc);
+    }
+
+    let t = state[1].wrapping_add(c).wrapping_add(rd);
+    state[1] = state[2].wrapping_add(d).wrapping_add(ra);
+    state[2] = state[3].wrapping_add(a).wrapping_add(rb);
+    state[3] = state[0].wrapping_add(b).wrapping_add(rc);
+    state[0] = t;
+}
+
+/// Hashes the data with RIPEMD-128.
+pub fn digest(data: &[u8]) -> [u8; 16] {
+    let mut state = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476];
+
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((data.len() as u64) * 8).to_le_bytes());
+    for block in padded.chunks_exact(64) {
+        compress(&mut state, block);
+    }
+
+    let mut hash = [0u8; 16];
+    for (bytes, word) in hash.chunks_exact_mut(4).zip(state.iter()) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(hash: [u8; 16]) -> String {
+        hash.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    #[test]
+    fn ripemd128_should_match_test_vectors() {
+        assert_eq!("cdf26213a150dc3ecb610f18f6b38b46", hex(digest(b"")));
+        assert_eq!("c14a12199c66e4ba84636b0f69144c77", hex(digest(b"abc")));
+        assert_eq!(
+            "3f45ef194732c2dbb2c4a2c769795fa3",
+            hex(digest(
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            ))
+        );
+    }
+}
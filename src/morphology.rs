@@ -0,0 +1,327 @@
+//! A module containing a small English morphology layer
+//! used for falling back to a word's lemma whenever the
+//! inflected form itself could not be found.
+//!
+
+/// A list of irregular forms along with their lemmas.
+const IRREGULAR_FORMS: &[(&str, &str)] = &[
+    // Nouns
+    ("children", "child"),
+    ("feet", "foot"),
+    ("geese", "goose"),
+    ("lice", "louse"),
+    ("men", "man"),
+    ("mice", "mouse"),
+    ("oxen", "ox"),
+    ("people", "person"),
+    ("teeth", "tooth"),
+    ("women", "woman"),
+    ("criteria", "criterion"),
+    ("phenomena", "phenomenon"),
+    ("dice", "die"),
+    // Verbs
+    ("am", "be"),
+    ("are", "be"),
+    ("is", "be"),
+    ("was", "be"),
+    ("were", "be"),
+    ("been", "be"),
+    ("ate", "eat"),
+    ("eaten", "eat"),
+    ("began", "begin"),
+    ("begun", "begin"),
+    ("bit", "bite"),
+    ("bitten", "bite"),
+    ("bought", "buy"),
+    ("broke", "break"),
+    ("broken", "break"),
+    ("brought", "bring"),
+    ("built", "build"),
+    ("came", "come"),
+    ("caught", "catch"),
+    ("chose", "choose"),
+    ("chosen", "choose"),
+    ("did", "do"),
+    ("done", "do"),
+    ("does", "do"),
+    ("drank", "drink"),
+    ("drunk", "drink"),
+    ("drove", "drive"),
+    ("driven", "drive"),
+    ("fell", "fall"),
+    ("fallen", "fall"),
+    ("fed", "feed"),
+    ("felt", "feel"),
+    ("fled", "flee"),
+    ("flew", "fly"),
+    ("flown", "fly"),
+    ("fought", "fight"),
+    ("found", "find"),
+    ("forgot", "forget"),
+    ("forgotten", "forget"),
+    ("froze", "freeze"),
+    ("frozen", "freeze"),
+    ("gave", "give"),
+    ("given", "give"),
+    ("went", "go"),
+    ("gone", "go"),
+    ("got", "get"),
+    ("gotten", "get"),
+    ("grew", "grow"),
+    ("grown", "grow"),
+    ("had", "have"),
+    ("has", "have"),
+    ("heard", "hear"),
+    ("held", "hold"),
+    ("hid", "hide"),
+    ("hidden", "hide"),
+    ("kept", "keep"),
+    ("knew", "know"),
+    ("known", "know"),
+    ("led", "lead"),
+    ("left", "leave"),
+    ("lost", "lose"),
+    ("made", "make"),
+    ("meant", "mean"),
+    ("met", "meet"),
+    ("paid", "pay"),
+    ("ran", "run"),
+    ("rode", "ride"),
+    ("ridden", "ride"),
+    ("rose", "rise"),
+    ("risen", "rise"),
+    ("said", "say"),
+    ("sang", "sing"),
+    ("sung", "sing"),
+    ("saw", "see"),
+    ("seen", "see"),
+    ("sent", "send"),
+    ("shook", "shake"),
+    ("shaken", "shake"),
+    ("slept", "sleep"),
+    ("sold", "sell"),
+    ("sought", "seek"),
+    ("spent", "spend"),
+    ("spoke", "speak"),
+    ("spoken", "speak"),
+    ("stole", "steal"),
+    ("stolen", "steal"),
+    ("stood", "stand"),
+    ("struck", "strike"),
+    ("swam", "swim"),
+    ("swum", "swim"),
+    ("taught", "teach"),
+    ("thought", "think"),
+    ("threw", "throw"),
+    ("thrown", "throw"),
+    ("told", "tell"),
+    ("took", "take"),
+    ("taken", "take"),
+    ("tore", "tear"),
+    ("torn", "tear"),
+    ("understood", "understand"),
+    ("woke", "wake"),
+    ("woken", "wake"),
+    ("won", "win"),
+    ("wore", "wear"),
+    ("worn", "wear"),
+    ("wrote", "write"),
+    ("written", "write"),
+    // Adjectives
+    ("better", "good"),
+    ("best", "good"),
+    ("worse", "bad"),
+    ("worst", "bad"),
+    ("more", "much"),
+    ("most", "much"),
+    ("less", "little"),
+    ("least", "little"),
+];
+
+/// A list of (suffix, replacement) rules, loosely based off of
+/// WordNet's morphy. Rules are tried in order, so the more
+/// specific suffixes go first.
+const SUFFIX_RULES: &[(&str, &str)] = &[
+    // Nouns
+    ("ses", "sis"),
+    ("ices", "ex"),
+    ("ices", "ix"),
+    ("ves", "f"),
+    ("ves", "fe"),
+    ("men", "man"),
+    ("ies", "y"),
+    ("ches", "ch"),
+    ("shes", "sh"),
+    ("xes", "x"),
+    ("zes", "z"),
+    ("ses", "s"),
+    ("es", "e"),
+    ("es", ""),
+    ("s", ""),
+    ("ae", "a"),
+    ("a", "um"),
+    ("a", "on"),
+    ("i", "us"),
+    // Verbs
+    ("ied", "y"),
+    ("ed", "e"),
+    ("ed", ""),
+    ("ying", "ie"),
+    ("ing", "e"),
+    ("ing", ""),
+    // Adjectives
+    ("iest", "y"),
+    ("ier", "y"),
+    ("est", "e"),
+    ("est", ""),
+    ("er", "e"),
+    ("er", ""),
+    // Adverbs
+    ("ily", "y"),
+    ("ly", ""),
+];
+
+/// Suffixes which double the final consonant of the stem,
+/// as in "running" or "stopped".
+const DOUBLING_SUFFIXES: &[&str] = &["ing", "ed", "er", "est"];
+
+/// The shortest a candidate lemma can be.
+const MIN_LEMMA_LEN: usize = 2;
+
+/// Generates the possible lemmas of a word, ordered from the
+/// most to the least likely. The word itself is never included.
+///
+/// Phrases are inflected on their first or last word, as
+/// in "kicked the bucket" or "ice creams".
+pub fn lemmas(word: &str) -> Vec<String> {
+    let lowercase = word.to_lowercase();
+    let words: Vec<&str> = lowercase.split_whitespace().collect();
+    let [first, .., last] = words.as_slice() else {
+        return word_lemmas(word);
+    };
+
+    let rest = words[1..].join(" ");
+    let init = words[..words.len() - 1].join(" ");
+    let mut candidates: Vec<String> = word_lemmas(first)
+        .into_iter()
+        .map(|lemma| format!("{lemma} {rest}"))
+        .collect();
+    candidates.extend(
+        word_lemmas(last)
+            .into_iter()
+            .map(|lemma| format!("{init} {lemma}")),
+    );
+    candidates
+}
+
+/// Generates the possible lemmas of a single word.
+fn word_lemmas(word: &str) -> Vec<String> {
+    let word = word.trim().to_lowercase();
+    let mut candidates: Vec<String> = vec![];
+    let mut push = |candidate: String| {
+        if candidate != word
+            && candidate.chars().count() >= MIN_LEMMA_LEN
+            && !candidates.contains(&candidate)
+        {
+            candidates.push(candidate);
+        }
+    };
+
+    for (form, lemma) in IRREGULAR_FORMS.iter() {
+        if *form == word {
+            push(lemma.to_string());
+        }
+    }
+
+    // Since few words end in a doubled consonant other than those of
+    // "fall", "miss", "cliff" or "buzz", the undoubled stems of the
+    // others come ahead of the likes of "runn" and "runne"
+    let (likely, unlikely): (Vec<String>, Vec<String>) = DOUBLING_SUFFIXES
+        .iter()
+        .filter_map(|suffix| word.strip_suffix(suffix).and_then(undouble))
+        .partition(|undoubled| !undoubled.ends_with(['l', 's', 'f', 'z']));
+    likely.into_iter().for_each(&mut push);
+
+    for (suffix, replacement) in SUFFIX_RULES.iter() {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            push(format!("{stem}{replacement}"));
+        }
+    }
+
+    unlikely.into_iter().for_each(&mut push);
+
+    candidates
+}
+
+/// Removes the final consonant of a stem ending in a doubled
+/// consonant (i.e.: "runn" -> "run").
+fn undouble(stem: &str) -> Option<String> {
+    let mut chars = stem.chars().rev();
+    let (last, before) = (chars.next()?, chars.next()?);
+    if last == before && last.is_ascii_alphabetic() && !"aeiou".contains(last) {
+        Some(stem[..stem.len() - last.len_utf8()].to_string())
+    } else {
+        None
+    }
+}
+
+/// Runs the lookup on the word, and failing that, on each of its
+/// candidate lemmas. Returns the form that matched alongside the
+/// result of the lookup.
+pub fn lookup<T>(word: &str, mut lookup: impl FnMut(&str) -> Option<T>) -> Option<(String, T)> {
+    if let Some(found) = lookup(word) {
+        return Some((word.to_string(), found));
+    }
+    lemmas(word)
+        .into_iter()
+        .find_map(|lemma| lookup(&lemma).map(|found| (lemma, found)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regular_forms_should_have_lemmas() {
+        assert!(lemmas("running").contains(&"run".to_string()));
+        assert!(lemmas("analyses").contains(&"analysis".to_string()));
+        assert!(lemmas("stopped").contains(&"stop".to_string()));
+        assert!(lemmas("bigger").contains(&"big".to_string()));
+        assert!(lemmas("knives").contains(&"knife".to_string()));
+        assert!(lemmas("studies").contains(&"study".to_string()));
+    }
+
+    #[test]
+    fn undoubled_stems_should_come_first() {
+        assert_eq!(Some(&"run".to_string()), lemmas("running").first());
+        assert_eq!(Some(&"stop".to_string()), lemmas("stopped").first());
+        let falling = lemmas("falling");
+        let position = |lemma: &str| falling.iter().position(|l| l == lemma);
+        assert!(position("fall") < position("fal"));
+    }
+
+    #[test]
+    fn irregular_forms_should_come_first() {
+        assert_eq!(Some(&"goose".to_string()), lemmas("geese").first());
+        assert_eq!(Some(&"go".to_string()), lemmas("went").first());
+    }
+
+    #[test]
+    fn phrases_should_have_lemmas() {
+        let lemmas = lemmas("Kicked the  bucket");
+        assert!(lemmas.contains(&"kick the bucket".to_string()));
+        assert!(!lemmas.contains(&"kicked the bucket".to_string()));
+        assert!(super::lemmas("ice creams").contains(&"ice cream".to_string()));
+    }
+
+    #[test]
+    fn lookup_should_report_the_matched_lemma() {
+        let dictionary = ["run", "goose"];
+        let find = |w: &str| dictionary.iter().find(|&&d| d == w).copied();
+
+        assert_eq!(Some(("run".to_string(), "run")), lookup("run", find));
+        assert_eq!(Some(("run".to_string(), "run")), lookup("running", find));
+        assert_eq!(Some(("goose".to_string(), "goose")), lookup("geese", find));
+        assert_eq!(None, lookup("xyzzy", find));
+    }
+}
@@ -0,0 +1,153 @@
+//! A module containing the interface every source of definitions
+//! implements, along with the registry they are looked up through.
+//!
+
+mod entry;
+mod local;
+mod online;
+mod wordnet;
+
+pub use entry::{DisplayOptions, Entry, Recording, Sense};
+pub use local::LocalProvider;
+pub use online::{FreeDictionaryProvider, UrbanDictionaryProvider};
+use std::{future::Future, pin::Pin, sync::Arc};
+pub use wordnet::WordNetProvider;
+
+/// The error a provider fails with, i.e.: a connection error.
+pub type ProviderError = Box<dyn std::error::Error + Send + Sync>;
+
+/// What a provider eventually comes back with.
+pub type ProviderFuture<'a, T> =
+    Pin<Box<dyn Future<Output = Result<T, ProviderError>> + Send + 'a>>;
+
+/// What a provider has to offer besides definitions.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Capabilities {
+    /// Its entries list synonyms and antonyms.
+    pub semantics: bool,
+    /// Its entries link to recordings of their pronunciation.
+    pub audio: bool,
+    /// It suggests headwords close to words it doesn't have.
+    pub suggestions: bool,
+}
+
+/// The entries a provider has for a word, along with the form of
+/// the word that had them, i.e.: `run` when looking up `running`.
+#[derive(Debug, Default)]
+pub struct Lookup {
+    pub lemma: String,
+    /// The entries of the word, none if the provider doesn't have it.
+    pub entries: Vec<Entry>,
+    /// What the provider had to say about the lookup, i.e.: why it has
+    /// no entries for the word.
+    pub notes: Vec<String>,
+}
+
+impl Lookup {
+    /// The lookup of a word the provider doesn't have.
+    pub fn missing(notes: Vec<String>) -> Self {
+        Self {
+            notes,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_found(&self) -> bool {
+        !self.entries.is_empty()
+    }
+}
+
+/// A source of definitions, mapping its own into entries.
+pub trait DictionaryProvider: Send + Sync {
+    /// The name shown alongside its entries, i.e.: `Urban Dictionary`.
+    fn name(&self) -> String;
+
+    fn capabilities(&self) -> Capabilities;
+
+    /// Looks the word up, or failing that its lemmas if the provider
+    /// can tell them apart, coming back with no entries if it has neither.
+    fn lookup<'a>(&'a self, word: &'a str) -> ProviderFuture<'a, Lookup>;
+
+    /// Suggests headwords close to a word the provider doesn't have.
+    fn suggest<'a>(&'a self, _word: &'a str) -> ProviderFuture<'a, Vec<String>> {
+        Box::pin(async { Ok(vec![]) })
+    }
+}
+
+/// The providers words are looked up in, in the order they were registered.
+#[derive(Clone, Default)]
+pub struct Registry {
+    providers: Vec<Arc<dyn DictionaryProvider>>,
+}
+
+impl Registry {
+    pub fn register(&mut self, provider: impl DictionaryProvider + 'static) {
+        self.providers.push(Arc::new(provider));
+    }
+
+    pub fn providers(&self) -> &[Arc<dyn DictionaryProvider>] {
+        &self.providers
+    }
+}
+
+/// The most suggestions given for a word.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Picks the headwords closest to the word, allowing for a typo
+/// in short words and two in longer ones.
+pub fn closest<'a>(word: &str, headwords: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let word: Vec<char> = word.to_lowercase().chars().collect();
+    let max_distance = if word.len() < 5 { 1 } else { 2 };
+
+    let mut close: Vec<(usize, &str)> = headwords
+        .filter_map(|headword| {
+            let candidate: Vec<char> = headword.to_lowercase().chars().collect();
+            if candidate.len().abs_diff(word.len()) > max_distance {
+                return None;
+            }
+            let distance = edit_distance(&word, &candidate);
+            (distance > 0 && distance <= max_distance).then_some((distance, headword))
+        })
+        .collect();
+    close.sort();
+    close.dedup_by(|(_, a), (_, b)| a.eq_ignore_ascii_case(b));
+    close
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, headword)| headword.to_string())
+        .collect()
+}
+
+/// Counts the insertions, deletions and substitutions turning one word into the other.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closest_headwords_should_be_suggested() {
+        let headwords = ["dictionary", "diction", "fiction", "cat", "cot", "dog"];
+        assert_eq!(
+            vec!["dictionary"],
+            closest("dictionory", headwords.iter().copied())
+        );
+        assert_eq!(
+            vec!["cat", "cot"],
+            closest("cet", headwords.iter().copied())
+        );
+        assert_eq!(vec!["cot"], closest("cat", headwords.iter().copied()));
+        assert!(closest("xylophone", headwords.iter().copied()).is_empty());
+    }
+}
@@ -0,0 +1,262 @@
+//! The entry model every provider maps its definitions into,
+//! along with the way entries are laid out for the terminal.
+
+use crate::{
+    audio,
+    colored_display::print_colored,
+    dictionary::{License, Semantic},
+    render,
+};
+use std::fmt::{Display, Formatter};
+
+/// A definition of a word, in the shape every provider maps its own into.
+#[derive(Debug, Default)]
+pub struct Entry {
+    /// The word as the provider spells it.
+    pub word: String,
+    /// The name of the provider (or dictionary) the entry comes from.
+    pub source: String,
+    pub phonetics: Vec<String>,
+    /// The ARPAbet pronunciations, shown next to the phonetics.
+    pub arpabet: Vec<String>,
+    pub origin: Option<String>,
+    pub senses: Vec<Sense>,
+    /// A definition the provider has no structure for, i.e.: a StarDict entry.
+    pub text: Option<String>,
+    /// The StarDict type of `text`, which decides how it is rendered.
+    pub text_type: char,
+    /// The synonyms of the entry as a whole, rather than of one of its senses.
+    pub synonyms: Vec<String>,
+    /// The antonyms of the entry as a whole, rather than of one of its senses.
+    pub antonyms: Vec<String>,
+    /// The words related to the entry in the ways only some
+    /// providers know of, i.e.: the hypernyms WordNet has.
+    pub relations: Vec<(Semantic, Vec<String>)>,
+    pub recordings: Vec<Recording>,
+    pub license: Option<License>,
+    /// Where to read more about the word.
+    pub source_urls: Vec<String>,
+}
+
+/// One of the meanings of an entry.
+#[derive(Debug, Default)]
+pub struct Sense {
+    pub part_of_speech: Option<String>,
+    pub definition: String,
+    /// An example of use of the word in this sense.
+    pub example: Option<String>,
+    pub synonyms: Vec<String>,
+    pub antonyms: Vec<String>,
+    /// Who wrote the sense, i.e.: an Urban Dictionary user.
+    pub author: Option<String>,
+}
+
+/// A recording of the pronunciation of an entry.
+#[derive(Debug)]
+pub struct Recording {
+    pub url: String,
+    /// The page the recording was found on.
+    pub source_url: Option<String>,
+    pub license: Option<License>,
+}
+
+/// What gets displayed besides the definitions themselves.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DisplayOptions {
+    /// Show an example of use under every definition.
+    pub examples: bool,
+    /// Show the licenses and sources of the entry and its recordings.
+    pub attribution: bool,
+    /// Style the entry with colors, rather than as plain text.
+    pub color: bool,
+}
+
+impl Entry {
+    /// Grabs the words the entry is related to, those of the entry as
+    /// a whole first, followed by those of its senses, or nothing if
+    /// the provider doesn't relate words in the given way.
+    pub fn get_semantics(&self, semantic: &Semantic) -> Option<Vec<String>> {
+        let (words, senses): (&[String], Vec<&[String]>) = match semantic {
+            Semantic::Synonym => (
+                &self.synonyms,
+                self.senses.iter().map(|s| s.synonyms.as_slice()).collect(),
+            ),
+            Semantic::Antonym => (
+                &self.antonyms,
+                self.senses.iter().map(|s| s.antonyms.as_slice()).collect(),
+            ),
+            _ => {
+                return self
+                    .relations
+                    .iter()
+                    .find(|(relation, _)| relation == semantic)
+                    .map(|(_, words)| words.clone())
+            }
+        };
+
+        let mut merged = words.to_vec();
+        for word in senses.into_iter().flatten() {
+            if !merged.contains(word) {
+                merged.push(word.clone());
+            }
+        }
+        Some(merged)
+    }
+
+    /// Grabs the URL of a recording of the word, from the given region if any.
+    pub fn get_audio(&self, region: Option<&str>) -> Option<&str> {
+        self.recordings
+            .iter()
+            .map(|recording| recording.url.as_str())
+            .find(|url| {
+                region.is_none_or(|region| {
+                    audio::region(url).is_some_and(|found| found.eq_ignore_ascii_case(region))
+                })
+            })
+    }
+
+    /// Lays the entry out for the terminal.
+    pub fn display(&self, options: DisplayOptions) -> EntryDisplay<'_> {
+        EntryDisplay {
+            entry: self,
+            options,
+        }
+    }
+}
+
+/// An entry along with what gets displayed besides its definitions.
+pub struct EntryDisplay<'a> {
+    entry: &'a Entry,
+    options: DisplayOptions,
+}
+
+impl Display for EntryDisplay<'_> {
+    fn fmt(&self, fmtr: &mut Formatter) -> std::fmt::Result {
+        let (entry, options) = (self.entry, self.options);
+        writeln!(fmtr)?;
+
+        if entry.source.is_empty() {
+            writeln!(fmtr, "{}", print_colored(&entry.word, options.color))?;
+        } else {
+            let word = print_colored(&entry.word, options.color);
+            writeln!(fmtr, "{word} ({})", entry.source)?;
+        }
+        for phonetic in entry.phonetics.iter() {
+            writeln!(fmtr, "    {phonetic}")?;
+        }
+        for arpabet in entry.arpabet.iter() {
+            writeln!(fmtr, "    [{arpabet}]")?;
+        }
+        if let Some(origin) = entry.origin.as_ref() {
+            writeln!(fmtr, "Origin: {origin}")?;
+        }
+
+        let mut part_of_speech = None;
+        let mut index = 0;
+        for sense in entry.senses.iter() {
+            let Some(pos) = sense.part_of_speech.as_deref() else {
+                write!(fmtr, "    - {}", sense.definition)?;
+                if let Some(author) = sense.author.as_ref() {
+                    write!(fmtr, " (by: {author})")?;
+                }
+                writeln!(fmtr)?;
+                continue;
+            };
+            if part_of_speech != Some(pos) {
+                writeln!(fmtr, ": {pos}")?;
+                (part_of_speech, index) = (Some(pos), 0);
+            }
+            index += 1;
+            writeln!(fmtr, "    {index}) {}", sense.definition)?;
+            if let Some(example) = sense.example.as_ref().filter(|_| options.examples) {
+                writeln!(fmtr, "       \"{example}\"")?;
+            }
+            if !sense.synonyms.is_empty() {
+                writeln!(fmtr, "       Synonyms: {}", sense.synonyms.join(", "))?;
+            }
+            if !sense.antonyms.is_empty() {
+                writeln!(fmtr, "       Antonyms: {}", sense.antonyms.join(", "))?;
+            }
+        }
+        if let Some(text) = entry.text.as_ref() {
+            writeln!(
+                fmtr,
+                "{}",
+                render::render(entry.text_type, text, options.color)
+            )?;
+        }
+
+        if !entry.source_urls.is_empty() {
+            writeln!(fmtr)?;
+            writeln!(fmtr, "Read More: {:?}", entry.source_urls)?;
+        }
+        if options.attribution {
+            if let Some(license) = entry.license.as_ref() {
+                writeln!(fmtr, "License: {license}")?;
+            }
+            for recording in entry.recordings.iter() {
+                write!(fmtr, "Audio: {}", recording.url)?;
+                if let Some(license) = recording.license.as_ref() {
+                    write!(fmtr, ", {license}")?;
+                }
+                if let Some(source_url) = recording.source_url.as_ref() {
+                    write!(fmtr, ", from {source_url}")?;
+                }
+                writeln!(fmtr)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn senses_should_be_grouped() {
+        let sense = |pos: Option<&str>, definition: &str| Sense {
+            part_of_speech: pos.map(String::from),
+            definition: definition.to_string(),
+            ..Sense::default()
+        };
+        let entry = Entry {
+            word: "run".to_string(),
+            source: "Test".to_string(),
+            phonetics: vec!["/ɹʌn/".to_string()],
+            senses: vec![
+                sense(Some("verb"), "To move quickly."),
+                sense(Some("verb"), "To flee."),
+                sense(Some("noun"), "An act of running."),
+                Sense {
+                    author: Some("someone".to_string()),
+                    ..sense(None, "A jog.")
+                },
+            ],
+            ..Entry::default()
+        };
+
+        assert_eq!(
+            "\n run  (Test)\n    /ɹʌn/\n\
+             : verb\n    1) To move quickly.\n    2) To flee.\n\
+             : noun\n    1) An act of running.\n\
+             \x20   - A jog. (by: someone)\n",
+            entry.display(DisplayOptions::default()).to_string()
+        );
+    }
+
+    #[test]
+    fn text_should_be_rendered_by_its_type() {
+        let entry = Entry {
+            word: "run".to_string(),
+            text: Some("<b>To move</b> quickly.".to_string()),
+            text_type: 'h',
+            ..Entry::default()
+        };
+        assert_eq!(
+            "\n run \nTo move quickly.\n",
+            entry.display(DisplayOptions::default()).to_string()
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! The provider of the dictionaries stored on the local machine.
+
+use super::{Capabilities, DictionaryProvider, Entry, Lookup, ProviderFuture};
+use crate::{
+    dictionary::DictionaryError,
+    local::{LocalDictionary, LocalEntry},
+    morphology,
+};
+use std::sync::{Arc, Mutex};
+
+/// A local dictionary, opened once and read from blocking threads.
+pub struct LocalProvider {
+    name: String,
+    can_suggest: bool,
+    dict: Arc<Mutex<LocalDictionary>>,
+}
+
+impl LocalProvider {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let dict = LocalDictionary::open(path).map_err(|e| e.to_string())?;
+        Ok(Self {
+            name: dict.get_bookname(),
+            can_suggest: dict.can_suggest(),
+            dict: Arc::new(Mutex::new(dict)),
+        })
+    }
+}
+
+impl DictionaryProvider for LocalProvider {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            suggestions: self.can_suggest,
+            ..Capabilities::default()
+        }
+    }
+
+    fn lookup<'a>(&'a self, word: &'a str) -> ProviderFuture<'a, Lookup> {
+        let (dict, word) = (Arc::clone(&self.dict), word.to_string());
+        Box::pin(async move {
+            let (entry_type, found) = tokio::task::spawn_blocking(move || {
+                let mut dict = dict.lock().expect("Lock isn't poisoned");
+                let mut error = None;
+                let found = morphology::lookup(&word, |w| {
+                    dict.get(w).unwrap_or_else(|e| {
+                        error.get_or_insert(e);
+                        None
+                    })
+                });
+                (dict.entry_type(), error.map_or(Ok(found), Err))
+            })
+            .await?;
+            let found = found.map_err(|error| DictionaryError::Unreadable {
+                dictionary: self.name(),
+                error,
+            })?;
+            let Some((lemma, entry)) = found else {
+                return Ok(Lookup::default());
+            };
+
+            let entries = match entry {
+                LocalEntry::Text(text) => vec![Entry {
+                    word: lemma.clone(),
+                    source: self.name(),
+                    text: Some(text),
+                    text_type: entry_type,
+                    ..Entry::default()
+                }],
+                LocalEntry::Definitions(definitions) => definitions
+                    .into_iter()
+                    .map(|definition| Entry {
+                        source: self.name(),
+                        ..Entry::from(definition)
+                    })
+                    .collect(),
+            };
+            Ok(Lookup {
+                entries,
+                lemma,
+                notes: vec![],
+            })
+        })
+    }
+
+    fn suggest<'a>(&'a self, word: &'a str) -> ProviderFuture<'a, Vec<String>> {
+        let (dict, word) = (Arc::clone(&self.dict), word.to_string());
+        Box::pin(async move {
+            Ok(tokio::task::spawn_blocking(move || {
+                dict.lock().expect("Lock isn't poisoned").suggest(&word)
+            })
+            .await?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    const FILEDIR: &str = "src/testdata/stardict-EnglishEtymology-2.4.2/EnglishEtymology";
+
+    #[tokio::test]
+    async fn local_entries_should_be_mapped() {
+        let provider = LocalProvider::open(FILEDIR).expect("Dictionary should open");
+        assert_eq!("English Etymology", provider.name());
+
+        let lookup = provider
+            .lookup("dictionaries")
+            .await
+            .expect("Dictionary should be readable");
+        assert_eq!("dictionary", lookup.lemma);
+        assert!(lookup.entries[0]
+            .text
+            .as_ref()
+            .is_some_and(|text| text.to_lowercase().contains("dictionar")));
+
+        let suggestions = provider
+            .suggest("dictionory")
+            .await
+            .expect("Dictionary should be readable");
+        assert_eq!(Some("dictionary"), suggestions.first().map(String::as_str));
+    }
+
+    #[tokio::test]
+    async fn kaikki_entries_should_keep_their_semantics() {
+        let dir = TempDir::new("local-kaikki");
+        let path = dir.join("kaikki.jsonl").to_string_lossy().to_string();
+        std::fs::write(
+            &path,
+            r#"{"word": "cat", "lang": "English", "pos": "noun", "synonyms": [{"word": "moggy"}], "senses": [{"glosses": ["A feline."], "antonyms": [{"word": "dog"}]}]}"#,
+        )
+        .expect("Extract should be written");
+
+        let provider = LocalProvider::open(&path).expect("Extract should open");
+        let lookup = provider
+            .lookup("cats")
+            .await
+            .expect("Extract should be readable");
+        assert_eq!("cat", lookup.lemma);
+        assert_eq!(1, lookup.entries.len());
+        assert_eq!(None, lookup.entries[0].text);
+        assert_eq!("Wiktionary (English)", lookup.entries[0].source);
+        assert_eq!(vec!["moggy".to_string()], lookup.entries[0].synonyms);
+        assert_eq!(vec!["dog".to_string()], lookup.entries[0].antonyms);
+    }
+
+    #[tokio::test]
+    async fn unreadable_entries_should_be_errors() {
+        let dir = TempDir::new("local-unreadable");
+        let prefix = dir.join("test").to_string_lossy().to_string();
+        let mut builder = crate::stardict::builder::SDBuilder::new("Test", 'm');
+        builder.add_entry("apple", "A fruit.");
+        builder
+            .write(&prefix)
+            .expect("Dictionary should be written");
+        std::fs::write(format!("{prefix}.dict"), [0xFF; 8]).expect("Temp dir should be writable");
+
+        let provider = LocalProvider::open(&prefix).expect("Dictionary should open");
+        let error = provider
+            .lookup("apple")
+            .await
+            .expect_err("Entry should be unreadable");
+        assert_eq!(
+            Some(8),
+            error
+                .downcast_ref::<DictionaryError>()
+                .map(DictionaryError::exit_code)
+        );
+    }
+}
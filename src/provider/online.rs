@@ -0,0 +1,258 @@
+//! The providers reached through the network.
+
+use super::{Capabilities, DictionaryProvider, Entry, Lookup, ProviderFuture};
+use crate::{
+    dictionary::{self, DictionaryError, WordDefinition},
+    http::Client,
+    morphology, urban_dictionary,
+};
+use std::sync::Arc;
+
+/// The Free Dictionary API, searched in a language.
+pub struct FreeDictionaryProvider {
+    client: Arc<Client>,
+    lang: String,
+}
+
+impl FreeDictionaryProvider {
+    pub fn new(client: Arc<Client>, lang: &str) -> Self {
+        Self {
+            client,
+            lang: lang.to_string(),
+        }
+    }
+}
+
+impl DictionaryProvider for FreeDictionaryProvider {
+    fn name(&self) -> String {
+        "Free Dictionary API".to_string()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            semantics: true,
+            audio: true,
+            suggestions: false,
+        }
+    }
+
+    fn lookup<'a>(&'a self, word: &'a str) -> ProviderFuture<'a, Lookup> {
+        Box::pin(async move {
+            let mut notes = vec![];
+            match lookup_definitions(&self.client, word, &self.lang, &mut notes).await {
+                Ok((lemma, definitions)) => Ok(Lookup {
+                    lemma,
+                    entries: definitions
+                        .into_iter()
+                        .map(|definition| Entry {
+                            source: self.name(),
+                            ..Entry::from(definition)
+                        })
+                        .collect(),
+                    notes,
+                }),
+                // The API explains its misses, so the explanation is passed on
+                Err(e @ DictionaryError::NotFound { .. }) => {
+                    notes.push(e.to_string());
+                    Ok(Lookup::missing(notes))
+                }
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+}
+
+/// Looks the word up in the given language, falling back to English
+/// (and to the word's lemma) whenever the language has no definitions,
+/// noting down when it does.
+async fn lookup_definitions(
+    client: &Client,
+    word: &str,
+    lang: &str,
+    notes: &mut Vec<String>,
+) -> Result<(String, Vec<WordDefinition>), DictionaryError> {
+    if lang != dictionary::DEFAULT_LANG {
+        match dictionary::define(client, word, lang).await {
+            Ok(definitions) => return Ok((word.to_string(), definitions)),
+            Err(DictionaryError::NotFound { .. }) => notes.push(format!(
+                "No definitions for {word} were found in {lang}, falling back to English."
+            )),
+            Err(e) => return Err(e),
+        }
+    }
+
+    // The word itself is looked up first, so its miss is the one reported.
+    // Any other failure ends the lookup, as the lemmas would only fail alike
+    let mut not_found = None;
+    for form in std::iter::once(word.to_string()).chain(morphology::lemmas(word)) {
+        match dictionary::define(client, &form, dictionary::DEFAULT_LANG).await {
+            Ok(definitions) => return Ok((form, definitions)),
+            Err(e @ DictionaryError::NotFound { .. }) => {
+                not_found.get_or_insert(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(not_found.expect("The word itself is always looked up"))
+}
+
+/// Urban Dictionary, searched for the word as it is.
+pub struct UrbanDictionaryProvider {
+    client: Arc<Client>,
+}
+
+impl UrbanDictionaryProvider {
+    pub fn new(client: Arc<Client>) -> Self {
+        Self { client }
+    }
+}
+
+impl DictionaryProvider for UrbanDictionaryProvider {
+    fn name(&self) -> String {
+        "Urban Dictionary".to_string()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
+    fn lookup<'a>(&'a self, word: &'a str) -> ProviderFuture<'a, Lookup> {
+        Box::pin(async move {
+            let found = urban_dictionary::define(&self.client, word)
+                .await
+                .map_err(|e| format!("Failed to search Urban Dictionary: {e}"))?;
+            if found.get_definitions().is_empty() {
+                return Ok(Lookup::default());
+            }
+            Ok(Lookup {
+                lemma: word.to_string(),
+                entries: vec![Entry {
+                    source: self.name(),
+                    ..Entry::from(found)
+                }],
+                notes: vec![],
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{
+        mock::{self, MockServer},
+        Endpoints,
+    };
+
+    #[tokio::test]
+    async fn online_entries_should_be_mapped() {
+        const DEFINITIONS: &str = r#"[{"word": "run", "phonetics": [{"text": "/ɹʌn/"}],
+            "meanings": [{"partOfSpeech": "verb", "definitions": [{"definition": "To move fast.",
+            "synonyms": ["sprint"]}]}], "sourceUrls": []}]"#;
+        const URBAN: &str = r#"{"list": [{"definition": "To [go] fast.", "author": "someone"}]}"#;
+        let server = MockServer::start(&[
+            ("/entries/en/run", 200, DEFINITIONS),
+            ("/define?term=running", 200, URBAN),
+            ("/define?term=xyzzy", 200, r#"{"list": []}"#),
+            (
+                "/entries/en/xyzzy",
+                404,
+                r#"{"title": "No Definitions Found", "message": "Sorry pal.", "resolution": ""}"#,
+            ),
+        ]);
+        let client = Arc::new(Client::new(
+            mock::transport(),
+            Endpoints {
+                free_dictionary: server.url.clone(),
+                urban_dictionary: server.url.clone(),
+            },
+        ));
+
+        let free_dictionary = FreeDictionaryProvider::new(Arc::clone(&client), "en");
+        let lookup = free_dictionary
+            .lookup("running")
+            .await
+            .expect("API should be reachable");
+        assert_eq!("run", lookup.lemma);
+        let entry = &lookup.entries[0];
+        assert_eq!(
+            ("Free Dictionary API", vec!["/ɹʌn/".to_string()]),
+            (entry.source.as_str(), entry.phonetics.clone())
+        );
+        assert_eq!(Some("verb"), entry.senses[0].part_of_speech.as_deref());
+        assert_eq!(vec!["sprint"], entry.senses[0].synonyms);
+        let missing = free_dictionary
+            .lookup("xyzzy")
+            .await
+            .expect("API should be reachable");
+        assert!(!missing.is_found());
+        assert_eq!(
+            vec!["No Definitions Found: Sorry pal.".to_string()],
+            missing.notes
+        );
+
+        let urban = UrbanDictionaryProvider::new(client);
+        let lookup = urban
+            .lookup("running")
+            .await
+            .expect("API should be reachable");
+        let sense = &lookup.entries[0].senses[0];
+        assert_eq!(
+            ("To go fast.", Some("someone")),
+            (sense.definition.as_str(), sense.author.as_deref())
+        );
+        assert!(urban
+            .lookup("xyzzy")
+            .await
+            .is_ok_and(|lookup| !lookup.is_found()));
+    }
+
+    #[tokio::test]
+    async fn lemmas_should_only_follow_misses() {
+        let server = MockServer::start(&[("/entries/en/running", 500, "")]);
+        let client = Client::new(
+            mock::transport(),
+            Endpoints {
+                free_dictionary: server.url.clone(),
+                ..Endpoints::default()
+            },
+        );
+
+        assert!(matches!(
+            lookup_definitions(&client, "running", "en", &mut vec![]).await,
+            Err(DictionaryError::Status(500))
+        ));
+        assert_eq!(vec!["/entries/en/running".to_string()], server.requests());
+    }
+
+    #[tokio::test]
+    async fn other_languages_should_fall_back_to_english() {
+        const DEFINITIONS: &str =
+            r#"[{"word": "x", "phonetics": [], "meanings": [], "sourceUrls": []}]"#;
+        let server = MockServer::start(&[
+            ("/entries/es/x", 404, ""),
+            ("/entries/en/x", 200, DEFINITIONS),
+            ("/entries/es/y", 200, DEFINITIONS),
+        ]);
+        let provider = FreeDictionaryProvider::new(
+            Arc::new(Client::new(
+                mock::transport(),
+                Endpoints {
+                    free_dictionary: server.url.clone(),
+                    ..Endpoints::default()
+                },
+            )),
+            "es",
+        );
+
+        let lookup = provider.lookup("x").await.expect("API should be reachable");
+        assert!(lookup.is_found());
+        assert_eq!(
+            vec!["No definitions for x were found in es, falling back to English.".to_string()],
+            lookup.notes
+        );
+        let lookup = provider.lookup("y").await.expect("API should be reachable");
+        assert!(lookup.is_found() && lookup.notes.is_empty());
+        assert!(!server.requests().contains(&"/entries/en/y".to_string()));
+    }
+}
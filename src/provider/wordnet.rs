@@ -0,0 +1,77 @@
+//! The provider of the Princeton WordNet database.
+
+use super::{Capabilities, DictionaryProvider, Entry, Lookup, ProviderFuture};
+use crate::{dictionary::Semantic, morphology, wordnet::WordNet};
+use std::sync::Arc;
+
+/// The relations WordNet has besides synonyms and antonyms.
+const RELATIONS: [Semantic; 4] = [
+    Semantic::Hypernym,
+    Semantic::Hyponym,
+    Semantic::Meronym,
+    Semantic::DerivedForm,
+];
+
+/// A WordNet database, read from blocking threads.
+pub struct WordNetProvider {
+    wordnet: Arc<WordNet>,
+}
+
+impl WordNetProvider {
+    pub fn open(dir: &str) -> Result<Self, String> {
+        let wordnet = WordNet::new(dir).map_err(|e| e.to_string())?;
+        Ok(Self {
+            wordnet: Arc::new(wordnet),
+        })
+    }
+}
+
+impl DictionaryProvider for WordNetProvider {
+    fn name(&self) -> String {
+        "WordNet".to_string()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            semantics: true,
+            ..Capabilities::default()
+        }
+    }
+
+    fn lookup<'a>(&'a self, word: &'a str) -> ProviderFuture<'a, Lookup> {
+        let (wordnet, word) = (Arc::clone(&self.wordnet), word.to_string());
+        Box::pin(async move {
+            let lookup = tokio::task::spawn_blocking(move || -> std::io::Result<Lookup> {
+                let found = morphology::lookup(&word, |w| {
+                    wordnet
+                        .define(w)
+                        .ok()
+                        .filter(|definitions| !definitions.is_empty())
+                });
+                let Some((lemma, definitions)) = found else {
+                    return Ok(Lookup::default());
+                };
+
+                let mut relations = vec![];
+                for semantic in RELATIONS {
+                    relations.push((semantic, wordnet.relations(&lemma, &semantic)?));
+                }
+                let entries = definitions
+                    .into_iter()
+                    .map(|definition| Entry {
+                        source: "WordNet".to_string(),
+                        relations: relations.clone(),
+                        ..Entry::from(definition)
+                    })
+                    .collect();
+                Ok(Lookup {
+                    lemma,
+                    entries,
+                    notes: vec![],
+                })
+            })
+            .await??;
+            Ok(lookup)
+        })
+    }
+}
@@ -0,0 +1,479 @@
+//! A module containing the terminal renderers for the
+//! markup found inside of dictionary entries.
+//!
+
+pub mod html;
+pub mod pango;
+pub mod xdxf;
+
+use crate::colored_display::paint;
+use ansi_term::{Colour, Style};
+use std::collections::BTreeMap;
+
+/// Renders a dictionary entry according to its StarDict type,
+/// styling it only if `color` is set.
+///
+/// Types without a dedicated renderer are returned as-is.
+pub fn render(entry_type: char, entry: &str, color: bool) -> String {
+    match entry_type {
+        'g' => pango::render(entry, color),
+        'x' => xdxf::render(entry, color),
+        'h' => html::render(entry, color),
+        _ => entry.to_string(),
+    }
+}
+
+/// Removes the ANSI escape sequences from styled text.
+pub fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut in_escape = false;
+    for chr in text.chars() {
+        match (in_escape, chr) {
+            (false, '\x1b') => in_escape = true,
+            (true, 'm') => in_escape = false,
+            (true, _) => {}
+            (false, _) => stripped.push(chr),
+        }
+    }
+    stripped
+}
+
+//* Markup Tokenizer *//
+
+/// An opening (or self-closing) tag along with its attributes.
+#[derive(Debug, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    pub attrs: BTreeMap<String, String>,
+    pub self_closing: bool,
+}
+
+/// A single piece of tokenized markup.
+#[derive(Debug, PartialEq)]
+pub enum Token {
+    Text(String),
+    Open(Tag),
+    Close(String),
+}
+
+/// Splits XML-like markup into text and tags. Tag names and attribute
+/// names are lowercased, and entities are decoded. Comments and
+/// processing instructions are dropped.
+///
+/// The tokenizer is lenient; a stray `<` is treated as text.
+pub fn tokenize(markup: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut rest = markup;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            tokens.push(Token::Text(decode_entities(rest)));
+            break;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(decode_entities(&rest[..start])));
+        }
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            tokens.push(Token::Text(cdata[..end].to_string()));
+            rest = cdata.get(end + 3..).unwrap_or("");
+            continue;
+        }
+
+        let Some(end) = find_tag_end(rest) else {
+            tokens.push(Token::Text(decode_entities(rest)));
+            break;
+        };
+        let inner = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if inner.starts_with('?') || inner.starts_with('!') {
+            continue;
+        }
+        match parse_tag(inner) {
+            Some(token) => tokens.push(token),
+            None => tokens.push(Token::Text(decode_entities(&format!("<{inner}>")))),
+        }
+    }
+
+    tokens
+}
+
+/// Finds the closing `>` of a tag, skipping over quoted attribute values.
+fn find_tag_end(markup: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, chr) in markup.char_indices().skip(1) {
+        match (quote, chr) {
+            (None, '"' | '\'') => quote = Some(chr),
+            (Some(q), _) if q == chr => quote = None,
+            (None, '<') => return None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses the inside of a tag, i.e.: `span foreground="red"`.
+fn parse_tag(inner: &str) -> Option<Token> {
+    if let Some(name) = inner.strip_prefix('/') {
+        return Some(Token::Close(name.trim().to_lowercase()));
+    }
+
+    let self_closing = inner.ends_with('/');
+    let inner = inner.trim_end_matches('/');
+    let name_end = inner
+        .find(|chr: char| chr.is_whitespace())
+        .unwrap_or(inner.len());
+    let name = &inner[..name_end];
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|chr| chr.is_alphanumeric() || "-_:".contains(chr))
+    {
+        return None;
+    }
+
+    Some(Token::Open(Tag {
+        name: name.to_lowercase(),
+        attrs: parse_attrs(&inner[name_end..]),
+        self_closing,
+    }))
+}
+
+/// Parses tag attributes, accepting quoted, unquoted and valueless ones.
+fn parse_attrs(attrs: &str) -> BTreeMap<String, String> {
+    let mut parsed = BTreeMap::new();
+    let mut rest = attrs.trim_start();
+
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|chr: char| chr == '=' || chr.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_lowercase();
+        rest = rest[key_end..].trim_start();
+
+        let value = if let Some(value) = rest.strip_prefix('=') {
+            let value = value.trim_start();
+            match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = value[1..].find(quote).map_or(value.len(), |i| i + 1);
+                    rest = value.get(end + 1..).unwrap_or("");
+                    &value[1..end]
+                }
+                _ => {
+                    let end = value
+                        .find(|chr: char| chr.is_whitespace())
+                        .unwrap_or(value.len());
+                    rest = &value[end..];
+                    &value[..end]
+                }
+            }
+        } else {
+            ""
+        };
+
+        if !key.is_empty() {
+            parsed.insert(key, decode_entities(value));
+        }
+        rest = rest.trim_start();
+    }
+
+    parsed
+}
+
+/// Decodes the named and numeric character entities of a string.
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&rest[1..=end]).map(|chr| (chr, end + 2)));
+        match entity {
+            Some((chr, len)) => {
+                decoded.push(chr);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Escapes the characters that would otherwise be read as markup.
+pub fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Decodes a single entity name (without the `&` and `;`).
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(hex) = entity
+        .strip_prefix("#x")
+        .or_else(|| entity.strip_prefix("#X"))
+    {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+    }
+    if let Some(dec) = entity.strip_prefix('#') {
+        return dec.parse().ok().and_then(char::from_u32);
+    }
+    let chr = match entity {
+        "lt" => '<',
+        "gt" => '>',
+        "amp" => '&',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "laquo" => '«',
+        "raquo" => '»',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "middot" => '·',
+        "bull" => '•',
+        "copy" => '©',
+        "reg" => '®',
+        "deg" => '°',
+        "times" => '×',
+        _ => return None,
+    };
+    Some(chr)
+}
+
+//* Styling *//
+
+/// Parses a `#rgb`/`#rrggbb` hex color or a common color name.
+pub fn parse_color(color: &str) -> Option<Colour> {
+    let color = color.trim().to_lowercase();
+    if let Some(hex) = color.strip_prefix('#') {
+        let channel = |range: std::ops::Range<usize>| u8::from_str_radix(hex.get(range)?, 16).ok();
+        return match hex.len() {
+            3 => Some(Colour::RGB(
+                channel(0..1)? * 17,
+                channel(1..2)? * 17,
+                channel(2..3)? * 17,
+            )),
+            6 | 8 => Some(Colour::RGB(channel(0..2)?, channel(2..4)?, channel(4..6)?)),
+            _ => None,
+        };
+    }
+
+    let colour = match color.as_str() {
+        "black" => Colour::Black,
+        "red" => Colour::Red,
+        "green" => Colour::Green,
+        "yellow" => Colour::Yellow,
+        "blue" => Colour::Blue,
+        "magenta" | "purple" | "fuchsia" => Colour::Purple,
+        "cyan" | "aqua" => Colour::Cyan,
+        "white" => Colour::White,
+        "gray" | "grey" | "silver" => Colour::RGB(128, 128, 128),
+        "darkred" | "maroon" => Colour::RGB(139, 0, 0),
+        "darkgreen" => Colour::RGB(0, 100, 0),
+        "darkblue" | "navy" => Colour::RGB(0, 0, 139),
+        "brown" => Colour::RGB(165, 42, 42),
+        "orange" => Colour::RGB(255, 165, 0),
+        "olive" => Colour::RGB(128, 128, 0),
+        "teal" => Colour::RGB(0, 128, 128),
+        "pink" => Colour::RGB(255, 192, 203),
+        "violet" => Colour::RGB(238, 130, 238),
+        _ => return None,
+    };
+    Some(colour)
+}
+
+/// Layers one style over another; whatever the top style
+/// sets takes priority over the bottom one.
+fn layer(bottom: Style, top: Style) -> Style {
+    Style {
+        foreground: top.foreground.or(bottom.foreground),
+        background: top.background.or(bottom.background),
+        is_bold: bottom.is_bold || top.is_bold,
+        is_dimmed: bottom.is_dimmed || top.is_dimmed,
+        is_italic: bottom.is_italic || top.is_italic,
+        is_underline: bottom.is_underline || top.is_underline,
+        is_blink: bottom.is_blink || top.is_blink,
+        is_reverse: bottom.is_reverse || top.is_reverse,
+        is_hidden: bottom.is_hidden || top.is_hidden,
+        is_strikethrough: bottom.is_strikethrough || top.is_strikethrough,
+    }
+}
+
+/// A writer which keeps track of the nested styles of the
+/// tags currently open, and paints text accordingly.
+///
+/// Every line written is prefixed with `indent` spaces.
+#[derive(Default)]
+pub struct Painter {
+    pub out: String,
+    pub indent: usize,
+    /// Whether text gets styled, rather than written as plain text.
+    pub color: bool,
+    styles: Vec<(String, Style)>,
+}
+
+impl Painter {
+    /// Creates a painter, styling text only if `color` is set.
+    pub fn new(color: bool) -> Self {
+        Self {
+            color,
+            ..Self::default()
+        }
+    }
+
+    /// Opens a styled region belonging to the named tag.
+    pub fn push(&mut self, tag: &str, style: Style) {
+        self.styles.push((tag.to_string(), style));
+    }
+
+    /// Closes the innermost region of the named tag, along with
+    /// any unclosed regions nested inside of it.
+    pub fn pop(&mut self, tag: &str) {
+        if let Some(i) = self.styles.iter().rposition(|(name, _)| name == tag) {
+            self.styles.truncate(i);
+        }
+    }
+
+    /// Checks whether a region of the named tag is currently open.
+    pub fn is_open(&self, tag: &str) -> bool {
+        self.styles.iter().any(|(name, _)| name == tag)
+    }
+
+    /// Writes text using the current style.
+    pub fn write(&mut self, text: &str) {
+        let style = self
+            .styles
+            .iter()
+            .fold(Style::new(), |bottom, (_, top)| layer(bottom, *top));
+
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.out.push('\n');
+            }
+            if line.is_empty() {
+                continue;
+            }
+            if self.at_line_start() {
+                self.out.push_str(&" ".repeat(self.indent));
+            }
+            self.out.push_str(&paint(style, line, self.color));
+        }
+    }
+
+    /// Checks whether the next character written starts a new line.
+    pub fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    /// Moves on to a new line, unless already at the start of one.
+    pub fn newline(&mut self) {
+        if !self.at_line_start() {
+            self.out.push('\n');
+        }
+    }
+}
+
+/// Converts text to superscript characters, falling back
+/// to a caret notation if any character has none.
+pub fn superscript(text: &str) -> String {
+    const FROM: &str = "0123456789+-=()ni";
+    const TO: &str = "⁰¹²³⁴⁵⁶⁷⁸⁹⁺⁻⁼⁽⁾ⁿⁱ";
+    map_script(text, FROM, TO).unwrap_or_else(|| format!("^({text})"))
+}
+
+/// Converts text to subscript characters, falling back
+/// to an underscore notation if any character has none.
+pub fn subscript(text: &str) -> String {
+    const FROM: &str = "0123456789+-=()aeox";
+    const TO: &str = "₀₁₂₃₄₅₆₇₈₉₊₋₌₍₎ₐₑₒₓ";
+    map_script(text, FROM, TO).unwrap_or_else(|| format!("_({text})"))
+}
+
+fn map_script(text: &str, from: &str, to: &str) -> Option<String> {
+    text.chars()
+        .map(|chr| {
+            let i = from.chars().position(|c| c == chr)?;
+            to.chars().nth(i)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizer_should_split_tags() {
+        let tokens = tokenize(r#"a <span foreground="red">b &amp; c</span><br/>"#);
+        assert_eq!(
+            vec![
+                Token::Text("a ".to_string()),
+                Token::Open(Tag {
+                    name: "span".to_string(),
+                    attrs: BTreeMap::from([("foreground".to_string(), "red".to_string())]),
+                    self_closing: false,
+                }),
+                Token::Text("b & c".to_string()),
+                Token::Close("span".to_string()),
+                Token::Open(Tag {
+                    name: "br".to_string(),
+                    attrs: BTreeMap::new(),
+                    self_closing: true,
+                }),
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn entities_should_decode() {
+        assert_eq!(
+            "<a> & \"b\" é é",
+            decode_entities("&lt;a&gt; &amp; &quot;b&quot; &#233; &#xE9;")
+        );
+        assert_eq!("AT&T &bogus;", decode_entities("AT&T &bogus;"));
+    }
+
+    #[test]
+    fn colors_should_parse() {
+        assert_eq!(Some(Colour::RGB(255, 0, 0)), parse_color("#ff0000"));
+        assert_eq!(Some(Colour::RGB(255, 255, 0)), parse_color("#ff0"));
+        assert_eq!(Some(Colour::Blue), parse_color("Blue"));
+        assert_eq!(None, parse_color("not-a-color"));
+    }
+
+    #[test]
+    fn ansi_should_strip() {
+        let styled = format!("a {} c", Colour::Red.bold().paint("b"));
+        assert_eq!("a b c", strip_ansi(&styled));
+    }
+
+    #[test]
+    fn scripts_should_convert() {
+        assert_eq!("x²", format!("x{}", superscript("2")));
+        assert_eq!("H₂O", format!("H{}O", subscript("2")));
+        assert_eq!("^(th)", superscript("th"));
+    }
+}
@@ -0,0 +1,553 @@
+//! A module containing the renderer for HTML entries,
+//! StarDict's `h` entry type.
+//!
+
+use super::{parse_color, strip_ansi, subscript, superscript, tokenize, Painter, Tag, Token};
+use ansi_term::{Colour, Style};
+
+/// The amount of spaces nested blocks are indented by.
+const INDENT: usize = 4;
+
+/// Elements which never have any children.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements which are laid out on their own lines.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "center",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// Elements whose contents are never shown.
+const HIDDEN_ELEMENTS: &[&str] = &["head", "script", "style", "template", "title", "noscript"];
+
+/// Elements which are implicitly closed by an opening tag of
+/// one of the listed elements, i.e.: `<li>a<li>b`.
+const IMPLIED_ENDS: &[(&str, &[&str])] = &[
+    ("p", &["p", "div", "ul", "ol", "table", "blockquote", "pre"]),
+    ("li", &["li"]),
+    ("dt", &["dt", "dd"]),
+    ("dd", &["dt", "dd"]),
+    ("tr", &["tr"]),
+    ("td", &["td", "th", "tr"]),
+    ("th", &["td", "th", "tr"]),
+];
+
+/// The bullets used by unordered lists, cycling by nesting depth.
+const BULLETS: &[&str] = &["•", "◦", "▪"];
+
+/// A node of the leniently parsed HTML tree.
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Element(Tag, Vec<Node>),
+}
+
+/// Renders an HTML entry into terminal text.
+pub fn render(markup: &str, color: bool) -> String {
+    let mut renderer = Renderer {
+        painter: Painter::new(color),
+        ..Renderer::default()
+    };
+    for node in parse(markup).iter() {
+        renderer.node(node);
+    }
+
+    let mut out = renderer.painter.out.trim_end().to_string();
+    if !renderer.links.is_empty() {
+        out.push_str("\n\n");
+        for (i, link) in renderer.links.iter().enumerate() {
+            out.push_str(&format!("[{}] {link}\n", i + 1));
+        }
+        out.truncate(out.trim_end().len());
+    }
+    out
+}
+
+//* Tree Building *//
+
+/// Builds a tree out of the tokenized markup, closing
+/// unclosed elements wherever HTML would imply it.
+fn parse(markup: &str) -> Vec<Node> {
+    // A stack of the open elements; the bottom one is the root.
+    let mut stack: Vec<(Option<Tag>, Vec<Node>)> = vec![(None, vec![])];
+
+    fn close_top(stack: &mut Vec<(Option<Tag>, Vec<Node>)>) {
+        if let Some((Some(tag), children)) = stack.pop() {
+            if let Some((_, parent)) = stack.last_mut() {
+                parent.push(Node::Element(tag, children));
+            }
+        }
+    }
+    let open_name = |stack: &[(Option<Tag>, Vec<Node>)]| {
+        stack
+            .last()
+            .and_then(|(tag, _)| tag.as_ref())
+            .map(|tag| tag.name.clone())
+    };
+
+    for token in tokenize(markup) {
+        match token {
+            Token::Text(text) => {
+                if let Some((_, children)) = stack.last_mut() {
+                    children.push(Node::Text(text));
+                }
+            }
+            Token::Open(tag) => {
+                while let Some(name) = open_name(&stack) {
+                    let implied = IMPLIED_ENDS
+                        .iter()
+                        .any(|(open, by)| *open == name && by.contains(&tag.name.as_str()));
+                    if !implied {
+                        break;
+                    }
+                    close_top(&mut stack);
+                }
+
+                if tag.self_closing || VOID_ELEMENTS.contains(&tag.name.as_str()) {
+                    if let Some((_, children)) = stack.last_mut() {
+                        children.push(Node::Element(tag, vec![]));
+                    }
+                } else {
+                    stack.push((Some(tag), vec![]));
+                }
+            }
+            Token::Close(name) => {
+                let is_open = stack
+                    .iter()
+                    .any(|(tag, _)| tag.as_ref().is_some_and(|tag| tag.name == name));
+                if is_open {
+                    while open_name(&stack).is_some_and(|open| open != name) {
+                        close_top(&mut stack);
+                    }
+                    close_top(&mut stack);
+                }
+            }
+        }
+    }
+
+    while stack.len() > 1 {
+        close_top(&mut stack);
+    }
+    stack
+        .pop()
+        .map(|(_, children)| children)
+        .unwrap_or_default()
+}
+
+//* Rendering *//
+
+#[derive(Default)]
+struct Renderer {
+    painter: Painter,
+    links: Vec<String>,
+    list_depth: usize,
+    preformatted: bool,
+}
+
+impl Renderer {
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Text(text) => self.text(text),
+            Node::Element(tag, children) => self.element(tag, children),
+        }
+    }
+
+    fn children(&mut self, children: &[Node]) {
+        for child in children.iter() {
+            self.node(child);
+        }
+    }
+
+    /// Writes text, collapsing whitespace outside of `<pre>`.
+    fn text(&mut self, text: &str) {
+        let text = if self.painter.is_open("sup") {
+            superscript(text.trim())
+        } else if self.painter.is_open("sub") {
+            subscript(text.trim())
+        } else {
+            text.to_string()
+        };
+
+        if self.preformatted {
+            self.painter.write(&text);
+            return;
+        }
+
+        let mut collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.starts_with(char::is_whitespace) && !self.after_space() {
+            collapsed.insert(0, ' ');
+        }
+        if text.ends_with(char::is_whitespace) && !collapsed.is_empty() && !collapsed.ends_with(' ')
+        {
+            collapsed.push(' ');
+        }
+        self.painter.write(&collapsed);
+    }
+
+    /// Checks whether a space written now would be redundant.
+    fn after_space(&self) -> bool {
+        self.painter.at_line_start() || self.painter.out.ends_with(' ')
+    }
+
+    fn element(&mut self, tag: &Tag, children: &[Node]) {
+        let name = tag.name.as_str();
+        if HIDDEN_ELEMENTS.contains(&name) {
+            return;
+        }
+        let is_block = BLOCK_ELEMENTS.contains(&name);
+        if is_block {
+            self.block_break();
+        }
+
+        self.painter.push(name, tag_style(tag));
+        match name {
+            "br" => self.painter.write("\n"),
+            "hr" => {
+                self.block_break();
+                self.painter.write(&"─".repeat(40));
+                self.block_break();
+            }
+            "img" => {
+                if let Some(alt) = tag.attrs.get("alt").filter(|alt| !alt.is_empty()) {
+                    self.painter.write(&format!("[{alt}]"));
+                }
+            }
+            "a" => self.link(tag, children),
+            "ul" | "ol" => self.list(name == "ol", children),
+            "dd" | "blockquote" => self.indented(children),
+            "table" => self.table(children),
+            "pre" => {
+                let preformatted = self.preformatted;
+                self.preformatted = true;
+                self.children(children);
+                self.preformatted = preformatted;
+            }
+            _ => self.children(children),
+        }
+        self.painter.pop(name);
+
+        if is_block {
+            self.block_break();
+        }
+    }
+
+    /// Ends the current line, trimming whatever spaces it ended with.
+    fn block_break(&mut self) {
+        let trimmed = self.painter.out.trim_end_matches(' ').len();
+        self.painter.out.truncate(trimmed);
+        self.painter.newline();
+    }
+
+    fn indented(&mut self, children: &[Node]) {
+        self.painter.indent += INDENT;
+        self.children(children);
+        self.painter.indent -= INDENT;
+    }
+
+    /// Renders a link, adding its target as a footnote. Links into
+    /// the dictionary itself are shown as cross-references instead.
+    fn link(&mut self, tag: &Tag, children: &[Node]) {
+        let href = tag.attrs.get("href").map(String::as_str).unwrap_or("");
+        let is_external = href.starts_with("http://")
+            || href.starts_with("https://")
+            || href.starts_with("mailto:")
+            || href.starts_with("file://");
+        if !is_external {
+            if href.starts_with("bword://") || href.starts_with("entry://") {
+                self.painter.write("→ ");
+            }
+            self.children(children);
+            return;
+        }
+
+        self.children(children);
+        let index = match self.links.iter().position(|link| link == href) {
+            Some(index) => index,
+            None => {
+                self.links.push(href.to_string());
+                self.links.len() - 1
+            }
+        };
+        self.painter.pop("a");
+        self.painter.write(&format!("[{}]", index + 1));
+    }
+
+    fn list(&mut self, ordered: bool, children: &[Node]) {
+        let bullet = BULLETS[self.list_depth % BULLETS.len()];
+        self.list_depth += 1;
+        if self.list_depth > 1 {
+            self.painter.indent += INDENT;
+        }
+
+        let mut number = 0;
+        for child in children.iter() {
+            match child {
+                Node::Element(tag, items) if tag.name == "li" => {
+                    number += 1;
+                    let marker = if ordered {
+                        format!("{number}. ")
+                    } else {
+                        format!("{bullet} ")
+                    };
+                    self.block_break();
+                    self.painter.write(&marker);
+                    self.painter.indent += marker.chars().count();
+                    self.children(items);
+                    self.painter.indent -= marker.chars().count();
+                    self.block_break();
+                }
+                _ => self.node(child),
+            }
+        }
+
+        if self.list_depth > 1 {
+            self.painter.indent -= INDENT;
+        }
+        self.list_depth -= 1;
+    }
+
+    /// Renders a table as aligned columns, with a rule below any header row.
+    fn table(&mut self, children: &[Node]) {
+        let mut rows: Vec<(bool, Vec<String>)> = vec![];
+        collect_rows(children, &mut |cells: &[Node]| {
+            let mut header = false;
+            let mut row = vec![];
+            for cell in cells.iter() {
+                if let Node::Element(tag, contents) = cell {
+                    if tag.name == "td" || tag.name == "th" {
+                        header |= tag.name == "th";
+                        row.push(self.render_cell(tag, contents));
+                    }
+                }
+            }
+            if !row.is_empty() {
+                rows.push((header, row));
+            }
+        });
+
+        let columns = rows.iter().map(|(_, row)| row.len()).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|i| {
+                rows.iter()
+                    .filter_map(|(_, row)| row.get(i))
+                    .map(|cell| visible_width(cell))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for (header, row) in rows.iter() {
+            let line = row
+                .iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{cell}{}", " ".repeat(width - visible_width(cell))))
+                .collect::<Vec<_>>()
+                .join(" │ ");
+            self.painter.write(line.trim_end());
+            self.painter.newline();
+            if *header {
+                let rule: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+                self.painter.write(&rule.join("─┼─"));
+                self.painter.newline();
+            }
+        }
+    }
+
+    /// Renders the contents of a table cell onto a single line, numbering
+    /// its links after the ones found before the table.
+    fn render_cell(&mut self, tag: &Tag, contents: &[Node]) -> String {
+        let mut cell = Renderer {
+            painter: Painter::new(self.painter.color),
+            links: std::mem::take(&mut self.links),
+            ..Renderer::default()
+        };
+        cell.painter.push(&tag.name, tag_style(tag));
+        cell.children(contents);
+        self.links = cell.links;
+        cell.painter
+            .out
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Calls back with the cells of every row of a table,
+/// looking through `<thead>`, `<tbody>` and `<tfoot>`.
+fn collect_rows(nodes: &[Node], on_row: &mut dyn FnMut(&[Node])) {
+    for node in nodes.iter() {
+        if let Node::Element(tag, children) = node {
+            match tag.name.as_str() {
+                "tr" => on_row(children),
+                "thead" | "tbody" | "tfoot" => collect_rows(children, on_row),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Counts the characters of a string, leaving out ANSI escape sequences.
+fn visible_width(text: &str) -> usize {
+    strip_ansi(text).chars().count()
+}
+
+/// Grabs the style of an HTML tag, including its inline CSS.
+fn tag_style(tag: &Tag) -> Style {
+    let mut style = match tag.name.as_str() {
+        "b" | "strong" | "th" | "dt" => Style::new().bold(),
+        "i" | "em" | "cite" | "dfn" | "var" => Style::new().italic(),
+        "u" | "ins" => Style::new().underline(),
+        "s" | "strike" | "del" => Style::new().strikethrough(),
+        "small" | "code" | "kbd" | "samp" | "tt" => Style::new().dimmed(),
+        "h1" | "h2" => Colour::Yellow.bold().underline(),
+        "h3" | "h4" | "h5" | "h6" => Style::new().bold().underline(),
+        "a" => Colour::Blue.underline(),
+        _ => Style::new(),
+    };
+
+    if let Some(color) = tag.attrs.get("color").and_then(|color| parse_color(color)) {
+        style.foreground = Some(color);
+    }
+    if let Some(css) = tag.attrs.get("style") {
+        for declaration in css.split(';') {
+            let Some((property, value)) = declaration.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().to_lowercase();
+            match property.trim().to_lowercase().as_str() {
+                "color" => style.foreground = parse_color(&value).or(style.foreground),
+                "background-color" => style.background = parse_color(&value).or(style.background),
+                "font-weight" => {
+                    style.is_bold = value == "bold"
+                        || value == "bolder"
+                        || value.parse::<u16>().is_ok_and(|weight| weight >= 600)
+                }
+                "font-style" => style.is_italic = value == "italic" || value == "oblique",
+                "text-decoration" | "text-decoration-line" => {
+                    style.is_underline = value.contains("underline");
+                    style.is_strikethrough = value.contains("line-through");
+                }
+                _ => {}
+            }
+        }
+    }
+    style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_should_lay_out_blocks() {
+        let rendered = render(
+            "<div>first   line</div>\n\n<p>second <b>line</b></p>third",
+            true,
+        );
+        assert_eq!(
+            format!(
+                "first line\nsecond {}\nthird",
+                Style::new().bold().paint("line")
+            ),
+            rendered
+        );
+    }
+
+    #[test]
+    fn html_should_render_lists() {
+        let rendered = render(
+            "<ul><li>a<li>b<ul><li>c</li></ul></ul><ol><li>d</li><li>e</li></ol>",
+            true,
+        );
+        assert_eq!("• a\n• b\n      ◦ c\n1. d\n2. e", rendered);
+    }
+
+    #[test]
+    fn html_should_render_tables() {
+        let rendered = render(
+            "<table><tr><td>a</td><td>bbb</td></tr><tr><td>cc</td><td>d</td></tr></table>",
+            true,
+        );
+        assert_eq!("a  │ bbb\ncc │ d", rendered);
+    }
+
+    #[test]
+    fn html_should_footnote_links() {
+        let rendered = render(
+            r#"see <a href="https://example.com">this</a> and <a href="bword://cat">cat</a>"#,
+            true,
+        );
+        assert_eq!(
+            format!(
+                "see {}[1] and {}{}\n\n[1] https://example.com",
+                Colour::Blue.underline().paint("this"),
+                Colour::Blue.underline().paint("→ "),
+                Colour::Blue.underline().paint("cat"),
+            ),
+            rendered
+        );
+    }
+
+    #[test]
+    fn table_links_should_share_footnotes() {
+        let rendered = strip_ansi(&render(
+            r#"<a href="https://a.com">a</a><table><tr><td><a href="https://b.com">b</a></td><td><a href="https://a.com">a</a></td></tr></table>"#,
+            true,
+        ));
+        assert_eq!(
+            "a[1]\nb[2] │ a[1]\n\n[1] https://a.com\n[2] https://b.com",
+            rendered
+        );
+    }
+
+    #[test]
+    fn html_should_apply_inline_css() {
+        assert_eq!(
+            format!("{}", Colour::Red.italic().paint("red")),
+            render(
+                r#"<span style="color: red; font-style: italic">red</span>"#,
+                true
+            )
+        );
+    }
+
+    #[test]
+    fn html_should_skip_hidden_elements() {
+        assert_eq!(
+            "body",
+            render(
+                "<html><head><style>p {}</style></head><body>body</body></html>",
+                true
+            )
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! A module containing the renderer for Pango markup,
+//! StarDict's `g` entry type.
+//!
+
+use super::{parse_color, subscript, superscript, tokenize, Painter, Tag, Token};
+use ansi_term::Style;
+
+/// Renders Pango markup into terminal text.
+pub fn render(markup: &str, color: bool) -> String {
+    let mut painter = Painter::new(color);
+
+    for token in tokenize(markup) {
+        match token {
+            Token::Text(text) if painter.is_open("sup") => painter.write(&superscript(&text)),
+            Token::Text(text) if painter.is_open("sub") => painter.write(&subscript(&text)),
+            Token::Text(text) => painter.write(&text),
+            Token::Open(tag) if tag.self_closing => {}
+            Token::Open(tag) => painter.push(&tag.name, tag_style(&tag)),
+            Token::Close(name) => painter.pop(&name),
+        }
+    }
+
+    painter.out
+}
+
+/// Grabs the style of a Pango tag.
+fn tag_style(tag: &Tag) -> Style {
+    match tag.name.as_str() {
+        "b" => Style::new().bold(),
+        "i" => Style::new().italic(),
+        "u" => Style::new().underline(),
+        "s" => Style::new().strikethrough(),
+        "small" => Style::new().dimmed(),
+        "span" => span_style(tag),
+        _ => Style::new(),
+    }
+}
+
+/// Grabs the style of a `<span>` from its attributes.
+fn span_style(tag: &Tag) -> Style {
+    let mut style = Style::new();
+    for (attr, value) in tag.attrs.iter() {
+        match attr.as_str() {
+            "foreground" | "fgcolor" | "color" => style.foreground = parse_color(value),
+            "background" | "bgcolor" => style.background = parse_color(value),
+            "weight" | "font_weight" => {
+                style.is_bold = matches!(value.as_str(), "bold" | "heavy" | "ultrabold")
+                    || value.parse::<u16>().is_ok_and(|weight| weight >= 600)
+            }
+            "style" | "font_style" => {
+                style.is_italic = matches!(value.as_str(), "italic" | "oblique")
+            }
+            "underline" => style.is_underline = value != "none",
+            "strikethrough" => style.is_strikethrough = value == "true",
+            _ => {}
+        }
+    }
+    style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ansi_term::Colour;
+
+    #[test]
+    fn pango_should_render_styles() {
+        let rendered = render(
+            r#"<b>bold</b> <span foreground="red">red &amp; co</span>"#,
+            true,
+        );
+        assert_eq!(
+            format!(
+                "{} {}",
+                Style::new().bold().paint("bold"),
+                Colour::Red.paint("red & co")
+            ),
+            rendered
+        );
+    }
+
+    #[test]
+    fn pango_should_nest_styles() {
+        let rendered = render("<i>a<u>b</u></i>", true);
+        assert_eq!(
+            format!(
+                "{}{}",
+                Style::new().italic().paint("a"),
+                Style::new().italic().underline().paint("b")
+            ),
+            rendered
+        );
+    }
+
+    #[test]
+    fn pango_should_render_scripts() {
+        assert!(render("x<sup>2</sup>", true).ends_with(&format!("{}", Style::new().paint("²"))));
+    }
+}
@@ -0,0 +1,136 @@
+//! A module containing the renderer for XDXF articles,
+//! StarDict's `x` entry type.
+//!
+
+use super::{parse_color, subscript, superscript, tokenize, Painter, Tag, Token};
+use ansi_term::{Colour, Style};
+
+/// The amount of spaces nested blocks are indented by.
+const INDENT: usize = 4;
+
+/// Renders an XDXF article into terminal text.
+pub fn render(markup: &str, color: bool) -> String {
+    let mut painter = Painter::new(color);
+
+    for token in tokenize(markup) {
+        match token {
+            // Blocks already end their own lines
+            Token::Text(text) if painter.at_line_start() && text.starts_with('\n') => {
+                painter.write(&text[1..])
+            }
+            Token::Text(text) if painter.is_open("sup") => painter.write(&superscript(&text)),
+            Token::Text(text) if painter.is_open("sub") => painter.write(&subscript(&text)),
+            Token::Text(text) => painter.write(&text),
+            Token::Open(tag) if tag.self_closing => match tag.name.as_str() {
+                "br" => painter.write("\n"),
+                "rref" => {
+                    let resource = tag.attrs.get("lctn").cloned().unwrap_or_default();
+                    painter.write(&format!("[{resource}]"));
+                }
+                _ => {}
+            },
+            Token::Open(tag) => {
+                painter.push(&tag.name, tag_style(&tag));
+                open(&mut painter, &tag.name);
+            }
+            Token::Close(name) => {
+                close(&mut painter, &name);
+                painter.pop(&name);
+            }
+        }
+    }
+
+    painter.out.trim_end().to_string()
+}
+
+/// Lays out the start of an XDXF element.
+fn open(painter: &mut Painter, name: &str) {
+    match name {
+        "k" => painter.newline(),
+        "blockquote" | "def" | "ex" => {
+            painter.newline();
+            painter.indent += INDENT;
+        }
+        "tr" => painter.write("["),
+        "kref" => painter.write("→ "),
+        _ => {}
+    }
+}
+
+/// Lays out the end of an XDXF element.
+fn close(painter: &mut Painter, name: &str) {
+    match name {
+        "k" => painter.newline(),
+        "blockquote" | "def" | "ex" => {
+            painter.newline();
+            painter.indent = painter.indent.saturating_sub(INDENT);
+        }
+        "tr" => painter.write("]"),
+        _ => {}
+    }
+}
+
+/// Grabs the style of an XDXF tag.
+fn tag_style(tag: &Tag) -> Style {
+    match tag.name.as_str() {
+        "k" => Colour::Yellow.bold(),
+        "tr" => Style::new().fg(Colour::Cyan),
+        "abr" | "pos" | "gr" => Colour::Green.italic(),
+        "ex" => Style::new().italic().dimmed(),
+        "kref" | "iref" => Colour::Blue.underline(),
+        "co" => Style::new().dimmed(),
+        // <c> defaults to green as per the XDXF standard
+        "c" => Style::new().fg(tag
+            .attrs
+            .get("c")
+            .and_then(|c| parse_color(c))
+            .unwrap_or(Colour::Green)),
+        "b" => Style::new().bold(),
+        "i" => Style::new().italic(),
+        "u" => Style::new().underline(),
+        _ => Style::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xdxf_should_lay_out_articles() {
+        let rendered = render(
+            "<k>cat</k>\n<tr>kæt</tr> <abr>n.</abr>\n<dtrn>кошка</dtrn><ex>a black cat</ex>see <kref>kitten</kref>",
+            true,
+        );
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(5, lines.len());
+        assert_eq!(format!("{}", Colour::Yellow.bold().paint("cat")), lines[0]);
+        assert!(lines[1].contains(&format!("{}", Style::new().fg(Colour::Cyan).paint("kæt"))));
+        assert!(lines[1].contains(&format!("{}", Colour::Green.italic().paint("n."))));
+        assert_eq!("кошка", lines[2]);
+        assert!(lines[3].starts_with("    "));
+        assert!(lines[4].contains(&format!("{}", Colour::Blue.underline().paint("kitten"))));
+    }
+
+    #[test]
+    fn xdxf_should_use_given_colors() {
+        assert_eq!(
+            format!("{}", Colour::Red.paint("red")),
+            render(r#"<c c="red">red</c>"#, true)
+        );
+        assert_eq!(
+            format!("{}", Colour::Green.paint("green")),
+            render("<c>green</c>", true)
+        );
+    }
+
+    #[test]
+    fn xdxf_should_indent_blockquotes() {
+        let rendered = render(
+            "<blockquote>1<blockquote>2</blockquote></blockquote>3",
+            true,
+        );
+        assert_eq!("    1\n        2\n3", rendered);
+    }
+}
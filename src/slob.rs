@@ -0,0 +1,619 @@
+//! A module containing the reader for Aard 2 `.slob` dictionaries.
+//!
+
+use crate::render;
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+const MAGIC: &[u8; 8] = b"!-1SLOB\x1F";
+
+/// The ways a slob file can compress its bins.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Compression {
+    None,
+    Zlib,
+    Bz2,
+    Lzma2,
+}
+
+/// A list of items stored as their positions, followed by the items.
+struct ItemList {
+    count: u64,
+    positions_offset: u64,
+    data_offset: u64,
+}
+
+/// How far the order of the refs is known to follow `sort_key`.
+enum RefOrder {
+    /// Binary searches are trusted until one of them misses.
+    Unchecked,
+    /// Every ref was read and found to be sorted by `sort_key`.
+    Collated,
+    /// The refs were sorted some other way, so they are kept
+    /// in memory along with their sort key instead.
+    Sorted(Vec<(Vec<(u8, char)>, u64)>),
+}
+
+/// A key pointing to an item within one of the bins.
+#[derive(Debug, PartialEq)]
+pub struct Ref {
+    pub key: String,
+    bin_index: u32,
+    item_index: u16,
+}
+
+/// A struct for looking keys up in a slob file.
+///
+/// A .slob is made out of:
+///     - a header ;; the compression used, the tags (i.e.: `label`) and
+///                   the content types of the items
+///     - the refs ;; every key, sorted by collation, along with the bin
+///                   and item it points to
+///     - the store ;; compressed bins, each holding several items
+///
+/// Keys are binary-searched straight from the file, and only the
+/// bin holding the requested item is decompressed.
+///
+/// Since `sort_key` only approximates the collation the refs are sorted
+/// by, the first search that misses checks the order of every ref,
+/// falling back to an index of them sorted by `sort_key` if they differ.
+pub struct Slob {
+    file: File,
+    name: String,
+    pub content_types: Vec<String>,
+    compression: Compression,
+    refs: ItemList,
+    store: ItemList,
+    order: RefOrder,
+    cache: Option<(u32, Vec<u8>, Vec<u8>)>,
+}
+
+impl Slob {
+    /// Opens the slob file found at the given path.
+    pub fn new(path: &str) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        if read_bytes(&mut file, 8)? != MAGIC {
+            return Err(invalid_data("Not a slob file."));
+        }
+        // The UUID of the file
+        read_bytes(&mut file, 16)?;
+
+        let encoding = read_tiny_text(&mut file)?;
+        if !encoding.eq_ignore_ascii_case("utf-8") {
+            return Err(invalid_data(&format!(
+                "The {encoding} encoding is not supported."
+            )));
+        }
+        let compression = match read_tiny_text(&mut file)?.as_str() {
+            "" => Compression::None,
+            "zlib" => Compression::Zlib,
+            "bz2" => Compression::Bz2,
+            "lzma2" => Compression::Lzma2,
+            other => {
+                return Err(invalid_data(&format!(
+                    "The {other} compression is not supported."
+                )))
+            }
+        };
+
+        let mut tags = BTreeMap::new();
+        for _ in 0..read_number(&mut file, 1)? {
+            let key = read_tiny_text(&mut file)?;
+            tags.insert(key, read_tiny_text(&mut file)?);
+        }
+        let mut content_types = vec![];
+        for _ in 0..read_number(&mut file, 1)? {
+            let len = read_number(&mut file, 2)? as usize;
+            content_types.push(read_text(&mut file, len)?);
+        }
+
+        // The blob count and file size
+        read_number(&mut file, 4)?;
+        let store_offset = read_number(&mut file, 8)?;
+        read_number(&mut file, 8)?;
+
+        let refs = ItemList::new(&mut file)?;
+        file.seek(SeekFrom::Start(store_offset))?;
+        let store = ItemList::new(&mut file)?;
+
+        let name = tags
+            .get("label")
+            .filter(|label| !label.is_empty())
+            .cloned()
+            .unwrap_or_else(|| {
+                Path::new(path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+
+        Ok(Self {
+            file,
+            name,
+            content_types,
+            compression,
+            refs,
+            store,
+            order: RefOrder::Unchecked,
+            cache: None,
+        })
+    }
+
+    /// Grabs the name of the dictionary.
+    pub fn get_bookname(&self) -> String {
+        self.name.clone()
+    }
+
+    fn ref_at(&mut self, index: u64) -> io::Result<Ref> {
+        self.refs.seek_to(&mut self.file, index)?;
+        let len = read_number(&mut self.file, 2)? as usize;
+        let key = read_text(&mut self.file, len)?;
+        let bin_index = read_number(&mut self.file, 4)? as u32;
+        let item_index = read_number(&mut self.file, 2)? as u16;
+        Ok(Ref {
+            key,
+            bin_index,
+            item_index,
+        })
+    }
+
+    /// Grabs every ref matching the word, from the closest match to
+    /// the loosest: an identical key, then one differing only by case,
+    /// then one also differing by accents and the like.
+    pub fn find(&mut self, word: &str) -> io::Result<Vec<Ref>> {
+        let wanted = sort_key(word);
+        let mut found = self.search(&wanted)?;
+        if found.is_empty() && matches!(self.order, RefOrder::Unchecked) {
+            self.order = self.check_order()?;
+            found = self.search(&wanted)?;
+        }
+
+        let lowered = word.to_lowercase();
+        found.sort_by_key(|item| match item.key.as_str() {
+            key if key == word => 0,
+            key if key.to_lowercase() == lowered => 1,
+            _ => 2,
+        });
+        Ok(found)
+    }
+
+    /// Grabs every ref whose sort key is the wanted one.
+    fn search(&mut self, wanted: &[(u8, char)]) -> io::Result<Vec<Ref>> {
+        if let RefOrder::Sorted(keys) = &self.order {
+            let first = keys.partition_point(|(key, _)| key.as_slice() < wanted);
+            let indices: Vec<u64> = keys[first..]
+                .iter()
+                .take_while(|(key, _)| key == wanted)
+                .map(|(_, index)| *index)
+                .collect();
+            return indices
+                .into_iter()
+                .map(|index| self.ref_at(index))
+                .collect();
+        }
+
+        let (mut low, mut high) = (0, self.refs.count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match sort_key(&self.ref_at(mid)?.key).as_slice().cmp(wanted) {
+                Ordering::Less => low = mid + 1,
+                _ => high = mid,
+            }
+        }
+
+        let mut found = vec![];
+        for index in low..self.refs.count {
+            let item = self.ref_at(index)?;
+            if sort_key(&item.key) != wanted {
+                break;
+            }
+            found.push(item);
+        }
+        Ok(found)
+    }
+
+    /// Reads every ref, checking whether they are sorted by `sort_key`.
+    fn check_order(&mut self) -> io::Result<RefOrder> {
+        let mut keys = Vec::with_capacity(self.refs.count as usize);
+        for index in 0..self.refs.count {
+            keys.push((sort_key(&self.ref_at(index)?.key), index));
+        }
+        if keys.windows(2).all(|pair| pair[0].0 <= pair[1].0) {
+            return Ok(RefOrder::Collated);
+        }
+        keys.sort();
+        Ok(RefOrder::Sorted(keys))
+    }
+
+    /// Grabs the content type and content of the item a ref points to.
+    pub fn content(&mut self, item: &Ref) -> io::Result<(String, Vec<u8>)> {
+        if self
+            .cache
+            .as_ref()
+            .is_none_or(|(bin_index, _, _)| *bin_index != item.bin_index)
+        {
+            self.store.seek_to(&mut self.file, item.bin_index as u64)?;
+            let count = read_number(&mut self.file, 4)? as usize;
+            let content_type_ids = read_bytes(&mut self.file, count)?;
+            let len = read_number(&mut self.file, 4)? as usize;
+            let compressed = read_bytes(&mut self.file, len)?;
+            let bin = decompress(self.compression, &compressed)?;
+            self.cache = Some((item.bin_index, content_type_ids, bin));
+        }
+        let Some((_, content_type_ids, bin)) = self.cache.as_ref() else {
+            return Err(invalid_data("Bin was not loaded."));
+        };
+
+        let index = item.item_index as usize;
+        let content_type = content_type_ids
+            .get(index)
+            .and_then(|&id| self.content_types.get(id as usize))
+            .cloned()
+            .unwrap_or_default();
+
+        // Bins start with the offset of every item, relative to the
+        // end of the offsets; each item is prefixed with its size
+        let number_at = |pos: usize| {
+            bin.get(pos..pos + 4)
+                .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        };
+        let content = number_at(index * 4)
+            .map(|offset| content_type_ids.len() * 4 + offset)
+            .and_then(|start| Some((start + 4, number_at(start)?)))
+            .and_then(|(start, size)| bin.get(start..start + size))
+            .ok_or_else(|| invalid_data("Item lies outside of its bin."))?;
+        Ok((content_type, content.to_vec()))
+    }
+
+    /// Grabs every textual entry of the specified word as HTML, joined together.
+    pub fn get(&mut self, word: &str) -> io::Result<Option<String>> {
+        let mut seen = vec![];
+        let mut entries = vec![];
+        for item in self.find(word)? {
+            // Several keys often point to the same article
+            if seen.contains(&(item.bin_index, item.item_index)) {
+                continue;
+            }
+            seen.push((item.bin_index, item.item_index));
+
+            let (content_type, content) = self.content(&item)?;
+            let text = String::from_utf8_lossy(&content);
+            if content_type.starts_with("text/html") {
+                entries.push(text.to_string());
+            } else if content_type.starts_with("text/plain") {
+                entries.push(format!("<pre>{}</pre>", render::escape_markup(&text)));
+            }
+        }
+
+        Ok(match entries.is_empty() {
+            true => None,
+            false => Some(entries.join("<hr>")),
+        })
+    }
+}
+
+impl ItemList {
+    /// Reads the item count at the current position of the file.
+    fn new(file: &mut File) -> io::Result<Self> {
+        let count = read_number(file, 4)?;
+        let positions_offset = file.stream_position()?;
+        Ok(Self {
+            count,
+            positions_offset,
+            data_offset: positions_offset + count * 8,
+        })
+    }
+
+    /// Moves the file to the start of the item.
+    fn seek_to(&self, file: &mut File, index: u64) -> io::Result<()> {
+        if index >= self.count {
+            return Err(invalid_data("Item index out of range."));
+        }
+        file.seek(SeekFrom::Start(self.positions_offset + index * 8))?;
+        let position = read_number(file, 8)?;
+        file.seek(SeekFrom::Start(self.data_offset + position))?;
+        Ok(())
+    }
+}
+
+fn decompress(compression: Compression, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decompressed = vec![];
+    match compression {
+        Compression::None => decompressed.extend_from_slice(data),
+        Compression::Zlib => {
+            flate2::read::ZlibDecoder::new(data).read_to_end(&mut decompressed)?;
+        }
+        Compression::Bz2 => {
+            bzip2::read::BzDecoder::new(data).read_to_end(&mut decompressed)?;
+        }
+        Compression::Lzma2 => {
+            lzma_rs::lzma2_decompress(&mut io::BufReader::new(data), &mut decompressed)
+                .map_err(|e| invalid_data(&format!("Invalid LZMA2 bin: {e:?}")))?
+        }
+    }
+    Ok(decompressed)
+}
+
+/// Builds the key slob files are sorted by, an approximation of the
+/// primary strength of the Unicode collation: case and accents are
+/// ignored, and spaces sort before punctuation, symbols, digits and
+/// then letters.
+pub fn sort_key(word: &str) -> Vec<(u8, char)> {
+    let mut key = vec![];
+    for chr in word.chars().flat_map(char::to_lowercase) {
+        match chr {
+            // Combining accents
+            '\u{300}'..='\u{36F}' => {}
+            _ if chr.is_whitespace() => key.push((0, ' ')),
+            _ if chr.is_ascii_punctuation() && !"$+<=>^`|~".contains(chr) => key.push((1, chr)),
+            _ if chr.is_numeric() => key.push((3, chr)),
+            _ if chr.is_alphabetic() => {
+                key.extend(base_letters(chr).chars().map(|base| (4, base)));
+            }
+            _ => key.push((2, chr)),
+        }
+    }
+    key
+}
+
+/// Strips the accents off of Latin letters, expanding ligatures.
+fn base_letters(letter: char) -> String {
+    const ACCENTED: &[(&str, &str)] = &[
+        ("àáâãäåāăą", "a"),
+        ("çćĉċč", "c"),
+        ("ďđ", "d"),
+        ("èéêëēĕėęě", "e"),
+        ("ĝğġģ", "g"),
+        ("ĥħ", "h"),
+        ("ìíîïĩīĭįı", "i"),
+        ("ĵ", "j"),
+        ("ķ", "k"),
+        ("ĺļľŀł", "l"),
+        ("ñńņňŉ", "n"),
+        ("òóôõöøōŏő", "o"),
+        ("ŕŗř", "r"),
+        ("śŝşš", "s"),
+        ("ţťŧ", "t"),
+        ("ùúûüũūŭůűų", "u"),
+        ("ŵ", "w"),
+        ("ýÿŷ", "y"),
+        ("źżž", "z"),
+        ("ß", "ss"),
+        ("æ", "ae"),
+        ("œ", "oe"),
+        ("þ", "th"),
+    ];
+    ACCENTED
+        .iter()
+        .find(|(accented, _)| accented.contains(letter))
+        .map_or_else(|| letter.to_string(), |(_, base)| base.to_string())
+}
+
+fn read_bytes(file: &mut File, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Reads a big-endian number `width` bytes long.
+fn read_number(file: &mut File, width: usize) -> io::Result<u64> {
+    Ok(read_bytes(file, width)?
+        .iter()
+        .fold(0, |number, &byte| number << 8 | byte as u64))
+}
+
+fn read_text(file: &mut File, len: usize) -> io::Result<String> {
+    let text = read_bytes(file, len)?;
+    Ok(String::from_utf8_lossy(&text)
+        .trim_end_matches('\0')
+        .to_string())
+}
+
+/// Reads a text prefixed with its length in a single byte; tag
+/// values are padded with null bytes so they can be edited in place.
+fn read_tiny_text(file: &mut File) -> io::Result<String> {
+    let len = read_number(file, 1)? as usize;
+    read_text(file, len)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use std::{fs, io::Write};
+
+    fn tiny(text: &str) -> Vec<u8> {
+        let mut bytes = vec![text.len() as u8];
+        bytes.extend_from_slice(text.as_bytes());
+        bytes
+    }
+
+    fn item_list(items: &[Vec<u8>]) -> Vec<u8> {
+        let mut list = (items.len() as u32).to_be_bytes().to_vec();
+        let mut position = 0u64;
+        for item in items.iter() {
+            list.extend(position.to_be_bytes());
+            position += item.len() as u64;
+        }
+        for item in items.iter() {
+            list.extend(item);
+        }
+        list
+    }
+
+    /// Writes a zlib-compressed slob file where every bin holds two items.
+    fn write_slob(path: &str, keys: &[(&str, usize)], items: &[(u8, &str)]) {
+        let mut sorted = keys.to_vec();
+        sorted.sort_by(|(left, _), (right, _)| {
+            sort_key(left).cmp(&sort_key(right)).then(left.cmp(right))
+        });
+        write_refs(path, &sorted, items);
+    }
+
+    /// Writes a slob file with the refs in the given order.
+    fn write_refs(path: &str, sorted: &[(&str, usize)], items: &[(u8, &str)]) {
+        let mut header = MAGIC.to_vec();
+        header.extend([0; 16]);
+        header.extend(tiny("utf-8"));
+        header.extend(tiny("zlib"));
+        header.push(1);
+        header.extend(tiny("label"));
+        header.extend(tiny("Test\0\0\0"));
+        header.push(2);
+        for content_type in ["text/html; charset=utf-8", "text/plain; charset=utf-8"] {
+            header.extend((content_type.len() as u16).to_be_bytes());
+            header.extend(content_type.as_bytes());
+        }
+
+        let refs: Vec<Vec<u8>> = sorted
+            .iter()
+            .map(|(key, item)| {
+                let mut entry = (key.len() as u16).to_be_bytes().to_vec();
+                entry.extend(key.as_bytes());
+                entry.extend((*item as u32 / 2).to_be_bytes());
+                entry.extend((*item as u16 % 2).to_be_bytes());
+                entry.extend(tiny(""));
+                entry
+            })
+            .collect();
+
+        let bins: Vec<Vec<u8>> = items
+            .chunks(2)
+            .map(|bin| {
+                let mut offsets = vec![];
+                let mut contents = vec![];
+                for (_, content) in bin.iter() {
+                    offsets.extend((contents.len() as u32).to_be_bytes());
+                    contents.extend((content.len() as u32).to_be_bytes());
+                    contents.extend(content.as_bytes());
+                }
+                offsets.extend(contents);
+                let mut encoder = flate2::write::ZlibEncoder::new(vec![], Default::default());
+                encoder.write_all(&offsets).expect("Bin should compress");
+                let compressed = encoder.finish().expect("Bin should compress");
+
+                let mut stored = (bin.len() as u32).to_be_bytes().to_vec();
+                stored.extend(bin.iter().map(|(content_type, _)| content_type));
+                stored.extend((compressed.len() as u32).to_be_bytes());
+                stored.extend(compressed);
+                stored
+            })
+            .collect();
+
+        let refs = item_list(&refs);
+        let store_offset = header.len() + 4 + 8 + 8 + refs.len();
+        let mut out = header;
+        out.extend((items.len() as u32).to_be_bytes());
+        out.extend((store_offset as u64).to_be_bytes());
+        out.extend([0; 8]);
+        out.extend(refs);
+        out.extend(item_list(&bins));
+        fs::write(path, out).expect("Slob should be written");
+    }
+
+    #[test]
+    fn slob_lookup_test() {
+        let dir = TempDir::new("slob");
+        let path = dir.join("test.slob").to_string_lossy().to_string();
+        write_slob(
+            &path,
+            &[
+                ("café", 0),
+                ("Cafe", 1),
+                ("apple", 2),
+                ("apples", 2),
+                ("Apple Inc.", 3),
+                ("banana", 4),
+            ],
+            &[
+                (0, "<p>A coffee house.</p>"),
+                (0, "<p>A band.</p>"),
+                (0, "<p>A fruit.</p>"),
+                (0, "<p>A company.</p>"),
+                (1, "Yellow & long."),
+            ],
+        );
+
+        let mut slob = Slob::new(&path).expect("Slob should parse");
+        assert_eq!("Test", slob.get_bookname());
+        assert_eq!(
+            Some("<pre>Yellow &amp; long.</pre>".to_string()),
+            slob.get("banana").expect("Slob should be readable")
+        );
+        assert_eq!(
+            Some("<p>A band.</p><hr><p>A coffee house.</p>".to_string()),
+            slob.get("cafe").expect("Slob should be readable")
+        );
+        assert_eq!(
+            Some("<p>A coffee house.</p><hr><p>A band.</p>".to_string()),
+            slob.get("CAFÉ").expect("Slob should be readable")
+        );
+        assert_eq!(
+            Some("<p>A company.</p>".to_string()),
+            slob.get("apple inc.").expect("Slob should be readable")
+        );
+        assert_eq!(None, slob.get("cherry").expect("Slob should be readable"));
+    }
+
+    #[test]
+    fn refs_sorted_otherwise_should_be_found() {
+        let dir = TempDir::new("slob-order");
+        let path = dir.join("test.slob").to_string_lossy().to_string();
+        write_refs(
+            &path,
+            &[("Zebra", 0), ("apple", 1), ("éclair", 2)],
+            &[(1, "Striped."), (1, "A fruit."), (1, "A pastry.")],
+        );
+
+        let mut slob = Slob::new(&path).expect("Slob should parse");
+        assert_eq!(
+            Some("<pre>Striped.</pre>".to_string()),
+            slob.get("zebra").expect("Slob should be readable")
+        );
+        assert_eq!(
+            Some("<pre>A pastry.</pre>".to_string()),
+            slob.get("eclair").expect("Slob should be readable")
+        );
+        assert_eq!(None, slob.get("cherry").expect("Slob should be readable"));
+    }
+
+    #[test]
+    fn bins_should_decompress() {
+        let content = b"An item, and another item.".to_vec();
+
+        let mut compressed = vec![];
+        bzip2::read::BzEncoder::new(content.as_slice(), Default::default())
+            .read_to_end(&mut compressed)
+            .expect("Content should compress");
+        assert_eq!(
+            content,
+            decompress(Compression::Bz2, &compressed).expect("Bin should decompress")
+        );
+
+        let mut compressed = vec![];
+        lzma_rs::lzma2_compress(&mut content.as_slice(), &mut compressed)
+            .expect("Content should compress");
+        assert_eq!(
+            content,
+            decompress(Compression::Lzma2, &compressed).expect("Bin should decompress")
+        );
+    }
+
+    #[test]
+    fn keys_should_collate() {
+        assert_eq!(sort_key("Café"), sort_key("cafe"));
+        assert_eq!(sort_key("Straße"), sort_key("strasse"));
+        assert!(sort_key("a b") < sort_key("a-b"));
+        assert!(sort_key("a-b") < sort_key("a1"));
+        assert!(sort_key("a1") < sort_key("ab"));
+    }
+}
@@ -1,44 +1,168 @@
+pub mod builder;
 pub mod dict;
 mod errors;
+pub mod export;
 pub mod idx;
 pub mod ifo;
+pub mod import;
+pub mod merge;
 pub mod syn;
 
-use errors::StardictError;
-use std::{collections::BTreeMap, fs::File, io::Read};
+pub use errors::StardictError;
+use std::collections::{BTreeMap, HashMap};
 
 type StardictResult<T> = std::result::Result<T, StardictError>;
 
 /// A struct for manipulating StarDict dictionaries.
-pub struct Stardict<'dict> {
-    pub in_memory: bool,
+pub struct Stardict {
     pub ifo: ifo::SDifo,
     pub idx: idx::SDidx,
-    pub dict: dict::SDdict<'dict>,
+    pub dict: dict::SDdict,
     pub syn: Option<syn::SDSyn>,
     pub cache: BTreeMap<String, String>,
+    /// The headwords by their lowercase form, for case-insensitive lookups.
+    folded: HashMap<String, String>,
 }
 
-impl Stardict<'_> {}
+impl Stardict {
+    /// Opens the StarDict dictionary found at the given path.
+    ///
+    /// The path can either be the common prefix of the dictionary
+    /// files (i.e.: `dicts/EnglishEtymology`) or the path of its
+    /// .ifo file.
+    pub fn new(path: &str) -> StardictResult<Self> {
+        let dict_prefix = path.strip_suffix(".ifo").unwrap_or(path);
 
-impl Default for Stardict<'_> {
-    fn default() -> Self {
-        todo!()
+        let ifo = ifo::SDifo::new(dict_prefix)?;
+        let idx = idx::SDidx::new(dict_prefix, &ifo)?;
+        let dict = dict::SDdict::new(dict_prefix, &ifo)?;
+        let syn = syn::SDSyn::new(dict_prefix);
+
+        let mut folded = HashMap::new();
+        for key in idx.idx_content.keys() {
+            folded
+                .entry(key.to_ascii_lowercase())
+                .or_insert_with(|| key.clone());
+        }
+
+        Ok(Self {
+            ifo,
+            idx,
+            dict,
+            syn,
+            cache: BTreeMap::new(),
+            folded,
+        })
+    }
+
+    /// Grabs the name of the dictionary.
+    pub fn get_bookname(&self) -> String {
+        self.ifo.bookname.clone()
+    }
+
+    /// Grabs the type of the dictionary's entries, which are
+    /// turned into plain text when each field has its own type.
+    pub fn entry_type(&self) -> char {
+        self.ifo.sametypesequence.unwrap_or('m')
+    }
+
+    /// Resolves a word into the headword it is stored under.
+    ///
+    /// Exact matches are preferred, followed by the synonyms from
+    /// the .syn file, and lastly a case-insensitive match.
+    pub fn find(&self, word: &str) -> Option<String> {
+        if self.idx.contains(word) {
+            return Some(word.to_string());
+        }
+        if let Some(index) = self.syn.as_ref().and_then(|syn| syn.get(word)) {
+            return self.idx.word_at(index).map(String::from);
+        }
+        self.folded.get(&word.to_ascii_lowercase()).cloned()
+    }
+
+    /// Grabs every synonym from the .syn file, paired
+    /// with the headword it refers to.
+    pub fn synonyms(&self) -> Vec<(String, String)> {
+        let Some(syn) = self.syn.as_ref() else {
+            return vec![];
+        };
+        syn.syn_content
+            .iter()
+            .filter_map(|(synonym, &index)| {
+                Some((synonym.clone(), self.idx.word_at(index)?.to_string()))
+            })
+            .collect()
+    }
+
+    /// Grabs the dictionary entry of the specified word, joining
+    /// the entries of headwords that show up several times.
+    pub fn get(&mut self, word: &str) -> StardictResult<String> {
+        let headword = self
+            .find(word)
+            .ok_or_else(|| StardictError::WordNotFound(word.to_string()))?;
+
+        if let Some(entry) = self.cache.get(&headword) {
+            return Ok(entry.clone());
+        }
+
+        let entries = self.idx[headword.as_str()]
+            .iter()
+            .map(|cords| self.dict.get(cords))
+            .collect::<StardictResult<Vec<_>>>()?;
+        let entry = entries.join(merge::separator(self.entry_type()));
+        self.cache.insert(headword, entry.clone());
+        Ok(entry)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+    use crate::testing::TempDir;
 
-    // const TESTDIR: &str = "src/testdata";
     const FILEDIR: &str = "src/testdata/stardict-EnglishEtymology-2.4.2/EnglishEtymology";
 
     #[test]
-    #[ignore = "Unimplemented"]
-    fn dict_parser_test() {}
+    fn dictionary_test() {
+        let mut dict = Stardict::new(FILEDIR).expect("Dictionary should parse properly");
+        assert_eq!("English Etymology", dict.get_bookname());
+
+        let entry = dict.get("dictionary").expect("Word should exist");
+        assert!(entry.to_lowercase().contains("dictionar"));
+        assert!(dict.cache.contains_key("dictionary"));
+    }
+
+    #[test]
+    fn missing_word_test() {
+        let mut dict = Stardict::new(FILEDIR).expect("Dictionary should parse properly");
+        assert!(matches!(
+            dict.get("qwxzzy"),
+            Err(StardictError::WordNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn ifo_path_test() {
+        let dict = Stardict::new(&format!("{FILEDIR}.ifo"));
+        assert!(dict.is_ok());
+    }
 
     #[test]
-    #[ignore = "Unimplemented"]
-    fn dictionary_test() {}
+    fn homographs_should_be_joined() {
+        let dir = TempDir::new("stardict-homographs");
+        let prefix = dir.join("test").to_string_lossy().to_string();
+
+        let mut builder = builder::SDBuilder::new("Test", 'm');
+        builder.add_entry("bank", "A river side.");
+        builder.add_entry("bank", "A money keeper.");
+        builder
+            .write(&prefix)
+            .expect("Dictionary should be written");
+
+        let mut dict = Stardict::new(&prefix).expect("Dictionary should parse properly");
+        assert_eq!(
+            "A river side.\n\nA money keeper.",
+            dict.get("bank").expect("Word should exist")
+        );
+    }
 }
@@ -0,0 +1,185 @@
+//! A module containing the writer for StarDict dictionaries.
+//!
+
+use super::StardictResult;
+use std::{cmp::Ordering, collections::BTreeMap, fs};
+
+/// A struct collecting entries and synonyms in order to
+/// write them out as a new StarDict dictionary.
+///
+/// Entries are written uncompressed, sorted the way
+/// StarDict expects its .idx and .syn to be sorted.
+#[derive(Default)]
+pub struct SDBuilder {
+    pub bookname: String,
+    pub sametypesequence: char,
+    pub author: Option<String>,
+    pub website: Option<String>,
+    pub desc: Option<String>,
+    pub date: Option<String>,
+    entries: Vec<(String, String)>,
+    synonyms: Vec<(String, String)>,
+}
+
+/// Compares two words the way StarDict sorts its files: case-insensitively
+/// for ASCII letters, with ties broken by a plain byte comparison.
+pub fn stardict_cmp(left: &str, right: &str) -> Ordering {
+    let folded = |word: &str| {
+        word.bytes()
+            .map(|b| b.to_ascii_lowercase())
+            .collect::<Vec<_>>()
+    };
+    folded(left)
+        .cmp(&folded(right))
+        .then_with(|| left.cmp(right))
+}
+
+impl SDBuilder {
+    /// Creates a new builder for a dictionary of the given name and type.
+    pub fn new(bookname: &str, sametypesequence: char) -> Self {
+        Self {
+            bookname: bookname.to_string(),
+            sametypesequence,
+            ..Default::default()
+        }
+    }
+
+    /// Adds an entry for the headword.
+    pub fn add_entry(&mut self, word: &str, definition: &str) {
+        self.entries
+            .push((word.to_string(), definition.to_string()));
+    }
+
+    /// Adds a synonym pointing to an existing headword.
+    pub fn add_synonym(&mut self, synonym: &str, word: &str) {
+        self.synonyms.push((synonym.to_string(), word.to_string()));
+    }
+
+    /// Grabs the amount of entries added so far.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether no entries were added.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Writes the dictionary files out using the given prefix
+    /// (or the path of the .ifo file to create).
+    pub fn write(mut self, path: &str) -> StardictResult<()> {
+        let dict_prefix = path.strip_suffix(".ifo").unwrap_or(path);
+
+        self.entries
+            .sort_by(|(left, _), (right, _)| stardict_cmp(left, right));
+
+        let mut idx = vec![];
+        let mut dict = vec![];
+        let mut indices = BTreeMap::new();
+        for (i, (word, definition)) in self.entries.iter().enumerate() {
+            indices.entry(word.as_str()).or_insert(i as u32);
+
+            idx.extend_from_slice(word.as_bytes());
+            idx.push(0);
+            idx.extend_from_slice(&(dict.len() as u32).to_be_bytes());
+            idx.extend_from_slice(&(definition.len() as u32).to_be_bytes());
+            dict.extend_from_slice(definition.as_bytes());
+        }
+
+        let mut synonyms: Vec<(&str, u32)> = self
+            .synonyms
+            .iter()
+            .filter_map(|(synonym, word)| Some((synonym.as_str(), *indices.get(word.as_str())?)))
+            .filter(|(synonym, _)| !indices.contains_key(synonym))
+            .collect();
+        synonyms.sort_by(|(left, _), (right, _)| stardict_cmp(left, right));
+        synonyms.dedup();
+
+        let mut ifo = format!(
+            "StarDict's dict ifo file\nversion=2.4.2\nwordcount={}\nidxfilesize={}\nbookname={}\n",
+            self.entries.len(),
+            idx.len(),
+            single_line(&self.bookname),
+        );
+        if !synonyms.is_empty() {
+            ifo.push_str(&format!("synwordcount={}\n", synonyms.len()));
+        }
+        for (key, value) in [
+            ("author", &self.author),
+            ("website", &self.website),
+            ("description", &self.desc),
+            ("date", &self.date),
+        ] {
+            if let Some(value) = value.as_ref().filter(|value| !value.is_empty()) {
+                ifo.push_str(&format!("{key}={}\n", single_line(value)));
+            }
+        }
+        ifo.push_str(&format!("sametypesequence={}\n", self.sametypesequence));
+
+        fs::write(format!("{dict_prefix}.ifo"), ifo)?;
+        fs::write(format!("{dict_prefix}.idx"), idx)?;
+        fs::write(format!("{dict_prefix}.dict"), dict)?;
+        if !synonyms.is_empty() {
+            let mut syn = vec![];
+            for (synonym, index) in synonyms.iter() {
+                syn.extend_from_slice(synonym.as_bytes());
+                syn.push(0);
+                syn.extend_from_slice(&index.to_be_bytes());
+            }
+            fs::write(format!("{dict_prefix}.syn"), syn)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// .ifo values can't span multiple lines; StarDict uses `<br>` instead.
+fn single_line(value: &str) -> String {
+    value.replace("\r\n", "<br>").replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stardict::Stardict;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn words_should_sort_like_stardict() {
+        let mut words = vec!["b", "a", "B", "ab", "A"];
+        words.sort_by(|l, r| stardict_cmp(l, r));
+        assert_eq!(vec!["A", "a", "ab", "B", "b"], words);
+    }
+
+    #[test]
+    fn written_dictionary_should_be_readable() {
+        let dir = TempDir::new("builder");
+        let prefix = dir.join("test").to_string_lossy().to_string();
+
+        let mut builder = SDBuilder::new("Test", 'm');
+        builder.desc = Some("line 1\nline 2".to_string());
+        builder.add_entry("zebra", "A striped animal.");
+        builder.add_entry("apple", "A fruit.");
+        builder.add_synonym("pomme", "apple");
+        builder
+            .write(&prefix)
+            .expect("Dictionary should be written");
+
+        let mut dict = Stardict::new(&prefix).expect("Dictionary should parse properly");
+        assert_eq!("Test", dict.get_bookname());
+        assert_eq!(
+            vec!["apple", "zebra"],
+            dict.idx
+                .records
+                .iter()
+                .map(|(word, _)| word)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("A fruit.", dict.get("apple").expect("Word should exist"));
+        assert_eq!("A fruit.", dict.get("pomme").expect("Synonym should exist"));
+        assert_eq!(
+            "A striped animal.",
+            dict.get("zebra").expect("Word should exist")
+        );
+    }
+}
@@ -1,53 +1,120 @@
 //! A container of the .dict parser for StarDict.
 //!
 
-use super::{Stardict, StardictResult};
+use super::{idx::Cords32bit, ifo::SDifo, StardictError, StardictResult};
+use crate::{dictzip::Dictzip, render};
 use std::{
     fs::File,
     io::{Read, Seek, SeekFrom},
 };
 
-pub struct SDdict<'dict> {
-    pub file: File,
-    pub container: &'dict Stardict<'dict>,
-    // pub in_memory: bool,
+/// A struct reading entries off a StarDict .dict file.
+///
+/// Plain .dict files are read straight off the disk,
+/// while dictzipped .dict.dz files are decompressed
+/// one chunk at a time.
+pub struct SDdict {
+    pub file: Option<File>,
+    pub dictzip: Option<Dictzip>,
+    /// The type of every entry, unless each field is led by its own.
+    pub sametypesequence: Option<char>,
 }
 
-impl<'dict> SDdict<'dict> {
+impl SDdict {
     /// Creates a new instance of SDdict.
-    pub fn new(dict_prefix: &str, container: &'dict Stardict) -> StardictResult<Self> {
+    pub fn new(dict_prefix: &str, ifo: &SDifo) -> StardictResult<Self> {
         let filename = format!("{dict_prefix}.dict");
+        let sametypesequence = ifo.sametypesequence;
+        if let Ok(file) = File::open(&filename) {
+            return Ok(Self {
+                file: Some(file),
+                dictzip: None,
+                sametypesequence,
+            });
+        }
+
         Ok(Self {
-            file: File::open(filename)?,
-            container,
+            file: None,
+            dictzip: Some(Dictzip::open(&format!("{filename}.dz"))?),
+            sametypesequence,
         })
     }
 
-    /// Grabs the specified word from inside the dict
-    /// and returns a tuple containing a copy of Self
-    /// and the dictionary entry of the word.
-    pub fn get(mut self, word: &str) -> StardictResult<(Self, String)> {
-        let cords = &self.container.idx[word];
-
-        self.file.seek(SeekFrom::Start(cords.offset as u64))?;
-
-        let mut buf = vec![];
-        let mut handler = self.file.take(cords.data_size as u64);
-        let _ = handler.read(&mut buf)?;
-
-        Ok((
-            Self {
-                file: handler.into_inner(),
-                container: self.container,
-            },
-            String::from_utf8(buf)?,
-        ))
+    /// Grabs the dictionary entry found at the specified cords.
+    pub fn get(&mut self, cords: &Cords32bit) -> StardictResult<String> {
+        let buf = match (self.file.as_mut(), self.dictzip.as_mut()) {
+            (Some(file), _) => {
+                file.seek(SeekFrom::Start(cords.offset as u64))?;
+                let mut buf = vec![];
+                file.take(cords.data_size as u64).read_to_end(&mut buf)?;
+                buf
+            }
+            (None, Some(dictzip)) => dictzip.read(cords.offset as u64, cords.data_size as usize)?,
+            (None, None) => vec![],
+        };
+
+        match self.sametypesequence {
+            Some(_) => Ok(String::from_utf8(buf)?),
+            None => Self::typed_fields(&buf),
+        }
+    }
+
+    /// Grabs the text of an entry whose fields are each led by their type,
+    /// rendering the marked up ones into plain text and skipping binary ones.
+    fn typed_fields(mut buf: &[u8]) -> StardictResult<String> {
+        let mut fields = vec![];
+        while let Some((&field_type, rest)) = buf.split_first() {
+            if field_type.is_ascii_uppercase() {
+                // Binary fields (i.e.: sounds and pictures) are led by their size
+                let size = rest
+                    .get(..4)
+                    .ok_or(StardictError::InvalidByte("Truncated .dict field size."))?;
+                let size = u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize;
+                buf = rest[4..]
+                    .get(size..)
+                    .ok_or(StardictError::InvalidByte("Truncated .dict field."))?;
+            } else {
+                // Text fields end at a null byte, unless they end the entry
+                let end = rest
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .unwrap_or(rest.len());
+                let text = String::from_utf8(rest[..end].to_vec())?;
+                fields.push(render::render(field_type as char, &text, false));
+                buf = rest.get(end + 1..).unwrap_or_default();
+            }
+        }
+        Ok(fields.join("\n"))
     }
 }
 
-impl Default for SDdict<'_> {
-    fn default() -> Self {
-        // TODO
-        todo!()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const FILEDIR: &str = "src/testdata/stardict-EnglishEtymology-2.4.2/EnglishEtymology";
+
+    #[test]
+    fn dict_parser_test() {
+        let ifo = SDifo::new(FILEDIR).expect("File should parse properly");
+        let mut dict = SDdict::new(FILEDIR, &ifo).expect("File should parse properly");
+        assert!(dict.file.is_none());
+
+        let entry = dict
+            .get(&Cords32bit {
+                offset: 0,
+                data_size: 136,
+            })
+            .expect("Entry should be readable");
+        assert!(!entry.is_empty());
+    }
+
+    #[test]
+    fn typed_fields_should_be_read() {
+        let entry = b"tha\0W\0\0\0\x02\xFF\xFEh<b>A</b> laugh.";
+        assert_eq!(
+            "ha\nA laugh.",
+            SDdict::typed_fields(entry).expect("Fields should parse")
+        );
+        assert!(SDdict::typed_fields(b"W\0\0\0\x09\xFF").is_err());
     }
 }
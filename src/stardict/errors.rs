@@ -14,8 +14,10 @@ pub enum StardictError {
     ParseInt(std::num::ParseIntError),
     InvalidByte(&'static str),
     WordCount(&'static str),
+    MissingField(&'static str),
     FromUtf8(std::string::FromUtf8Error),
     Unpacking(packed_struct::PackingError),
+    WordNotFound(String),
 }
 
 impl Display for StardictError {
@@ -25,8 +27,10 @@ impl Display for StardictError {
             StardictError::ParseInt(e) => write!(fmt, "ParseIntError: {e}"),
             StardictError::InvalidByte(e) => write!(fmt, "InvalidByteError: {e}"),
             StardictError::WordCount(e) => write!(fmt, "WordCountError: {e}"),
+            StardictError::MissingField(e) => write!(fmt, "MissingFieldError: {e}"),
             StardictError::FromUtf8(e) => write!(fmt, "FromUTF8Error: {e}"),
             StardictError::Unpacking(e) => write!(fmt, "UnpackingError: {e}"),
+            StardictError::WordNotFound(e) => write!(fmt, "WordNotFoundError: {e}"),
         }
     }
 }
@@ -0,0 +1,204 @@
+//! A module containing the exporters for StarDict dictionaries.
+//!
+
+use super::{Stardict, StardictResult};
+use crate::render;
+use serde::Serialize;
+use std::{collections::BTreeMap, io::Write};
+
+/// The formats a dictionary can be exported to.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ArgEnum)]
+pub enum ExportFormat {
+    /// One JSON object per line
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Tab-separated values with a header row
+    Tsv,
+    /// StarDict's tabfile, i.e.: `word|synonym\tdefinition`
+    Tab,
+}
+
+/// A single exported dictionary entry.
+#[derive(Debug, Serialize)]
+struct ExportedEntry<'a> {
+    word: &'a str,
+    definition: &'a str,
+    synonyms: &'a [String],
+}
+
+/// Writes every entry of the dictionary, in .idx order, along with its
+/// synonyms. Returns the amount of entries written.
+///
+/// If `plain` is set, the entries are rendered into plain text
+/// instead of being kept in their original markup.
+pub fn export(
+    dict: &mut Stardict,
+    format: ExportFormat,
+    plain: bool,
+    out: &mut dyn Write,
+) -> StardictResult<usize> {
+    let mut synonyms: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (synonym, word) in dict.synonyms() {
+        synonyms.entry(word).or_default().push(synonym);
+    }
+
+    match format {
+        ExportFormat::Csv => writeln!(out, "word,definition,synonyms")?,
+        ExportFormat::Tsv => writeln!(out, "word\tdefinition\tsynonyms")?,
+        _ => {}
+    }
+
+    let entry_type = dict.entry_type();
+    for (word, cords) in dict.idx.records.iter() {
+        let definition = dict.dict.get(cords)?;
+        let definition = if plain {
+            render::render(entry_type, &definition, false)
+        } else {
+            definition
+        };
+        let word_synonyms = synonyms.get(word).map(Vec::as_slice).unwrap_or(&[]);
+
+        match format {
+            ExportFormat::Jsonl => {
+                let entry = ExportedEntry {
+                    word,
+                    definition: &definition,
+                    synonyms: word_synonyms,
+                };
+                let line = serde_json::to_string(&entry).expect("Entries should serialize");
+                writeln!(out, "{line}")?;
+            }
+            ExportFormat::Csv => writeln!(
+                out,
+                "{},{},{}",
+                csv_field(word),
+                csv_field(&definition),
+                csv_field(&word_synonyms.join("|"))
+            )?,
+            ExportFormat::Tsv => writeln!(
+                out,
+                "{}\t{}\t{}",
+                escape(word),
+                escape(&definition),
+                escape(&word_synonyms.join("|"))
+            )?,
+            ExportFormat::Tab => {
+                let mut headwords = vec![escape_headword(word)];
+                headwords.extend(word_synonyms.iter().map(|synonym| escape_headword(synonym)));
+                writeln!(out, "{}\t{}", headwords.join("|"), escape(&definition))?;
+            }
+        }
+    }
+
+    Ok(dict.idx.records.len())
+}
+
+/// Writes the .ifo metadata of the dictionary as JSON.
+pub fn export_metadata(dict: &Stardict, out: &mut dyn Write) -> StardictResult<()> {
+    let metadata = serde_json::to_string_pretty(&dict.ifo).expect("Metadata should serialize");
+    writeln!(out, "{metadata}")?;
+    Ok(())
+}
+
+/// Quotes a CSV field whenever it holds a special character.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Escapes the characters that would break up a tab-separated line,
+/// the same way StarDict's tabfile does.
+fn escape(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Escapes a tabfile headword, where `|` separates the synonyms.
+fn escape_headword(word: &str) -> String {
+    escape(word).replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stardict::builder::SDBuilder;
+    use crate::testing::TempDir;
+
+    fn export_test_dict(format: ExportFormat) -> String {
+        let dir = TempDir::new(&format!("export-{format:?}"));
+        let prefix = dir.join("test").to_string_lossy().to_string();
+
+        let mut builder = SDBuilder::new("Test", 'h');
+        builder.add_entry("apple", "A <b>fruit</b>,\n\"red\".");
+        builder.add_entry("pear", "Another fruit.");
+        builder.add_synonym("pomme", "apple");
+        builder
+            .write(&prefix)
+            .expect("Dictionary should be written");
+
+        let mut dict = Stardict::new(&prefix).expect("Dictionary should parse properly");
+        let mut out = vec![];
+        let count = export(&mut dict, format, false, &mut out).expect("Export should succeed");
+        assert_eq!(2, count);
+        String::from_utf8(out).expect("Export should be UTF-8")
+    }
+
+    #[test]
+    fn jsonl_export_test() {
+        let exported = export_test_dict(ExportFormat::Jsonl);
+        let lines: Vec<serde_json::Value> = exported
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("Line should be JSON"))
+            .collect();
+        assert_eq!("apple", lines[0]["word"]);
+        assert_eq!("A <b>fruit</b>,\n\"red\".", lines[0]["definition"]);
+        assert_eq!("pomme", lines[0]["synonyms"][0]);
+        assert_eq!(0, lines[1]["synonyms"].as_array().map_or(1, Vec::len));
+    }
+
+    #[test]
+    fn csv_export_test() {
+        assert_eq!(
+            "word,definition,synonyms\napple,\"A <b>fruit</b>,\n\"\"red\"\".\",pomme\npear,Another fruit.,\n",
+            export_test_dict(ExportFormat::Csv)
+        );
+    }
+
+    #[test]
+    fn tab_export_test() {
+        assert_eq!(
+            "apple|pomme\tA <b>fruit</b>,\\n\"red\".\npear\tAnother fruit.\n",
+            export_test_dict(ExportFormat::Tab)
+        );
+    }
+
+    #[test]
+    fn repeated_headwords_should_keep_their_entries() {
+        let dir = TempDir::new("export-repeated");
+        let prefix = dir.join("test").to_string_lossy().to_string();
+
+        let mut builder = SDBuilder::new("Test", 'm');
+        builder.add_entry("bank", "first sense");
+        builder.add_entry("bank", "second sense");
+        builder
+            .write(&prefix)
+            .expect("Dictionary should be written");
+
+        let mut dict = Stardict::new(&prefix).expect("Dictionary should parse properly");
+        let mut out = vec![];
+        let count =
+            export(&mut dict, ExportFormat::Tab, false, &mut out).expect("Export should succeed");
+        assert_eq!(2, count);
+        assert_eq!(
+            "bank\tfirst sense\nbank\tsecond sense\n",
+            String::from_utf8(out).expect("Export should be UTF-8")
+        );
+    }
+}
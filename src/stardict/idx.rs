@@ -1,17 +1,17 @@
 //! A module containing the .idx parser for StarDict.
 //!
 
-use super::{Stardict, StardictResult};
+use super::{ifo::SDifo, StardictError, StardictResult};
 use packed_struct::prelude::*;
 use std::{collections::BTreeMap, fs::File, io::Read};
 
 /// A struct unpacking the `cords` group of bits
-#[derive(PackedStruct)]
+#[derive(PackedStruct, Clone, Copy)]
 #[packed_struct(endian = "msb", bit_numbering = "msb0")]
 pub struct Cords32bit {
-    #[packed_field(bytes = "0..4")]
+    #[packed_field(bytes = "0..=3")]
     pub offset: u32,
-    #[packed_field(bytes = "4..8")]
+    #[packed_field(bytes = "4..=7")]
     pub data_size: u32,
 }
 
@@ -26,85 +26,86 @@ pub struct Cords32bit {
 ///     - word_data_size ;; word data's total size in .dict
 #[derive(Default)]
 pub struct SDidx {
-    pub idx: Vec<u8>,
-    pub idx_content: BTreeMap<String, Cords32bit>,
+    /// The cords of every entry of a headword, since
+    /// a headword can show up several times.
+    pub idx_content: BTreeMap<String, Vec<Cords32bit>>,
+    /// The records in the order they appear inside the .idx,
+    /// which is what .syn files refer to.
+    pub records: Vec<(String, Cords32bit)>,
 }
 
 impl SDidx {
-    /// Finds all bytes until null + cords amount of bytes; returns
-    /// total bytes read for iteration.
-    fn find_until_cords(buf: &mut Vec<u8>, byte_arr: &[u8], cords: usize) -> usize {
-        let byte_arr = if byte_arr[0] == 0 {
-            &byte_arr[1..] // skips first byte if first byte is null
-        } else {
-            byte_arr
-        };
-
-        let mut byte_iter = byte_arr.iter();
-
-        let i = byte_iter
-            .position(|&byte| byte == 0)
-            .expect("Null byte should exist as separator.");
-        buf.extend_from_slice(&byte_arr[..=i + cords]);
-
-        // return total bytes read
-        i + cords + 1
-    }
-    /// Splits a byte arr at the first instance of a null byte.
-    fn split_at_null(byte_arr: &[u8]) -> (&[u8], &[u8]) {
-        let mut byte_split = byte_arr.split(|&byte| byte == 0);
-
-        let left = byte_split.next().expect("Left side should be present.");
-        let right = byte_split.next().expect("Right side should be present.");
-
-        (left, right)
+    /// Splits a byte arr at the first instance of a null byte,
+    /// leaving out the null byte itself.
+    fn split_at_null(byte_arr: &[u8]) -> Option<(&[u8], &[u8])> {
+        let i = byte_arr.iter().position(|&byte| byte == 0)?;
+        Some((&byte_arr[..i], &byte_arr[i + 1..]))
     }
     /// Creates a new .idx container.
-    pub fn new(dict_prefix: &str, container: &Stardict) -> StardictResult<Self> {
+    pub fn new(dict_prefix: &str, ifo: &SDifo) -> StardictResult<Self> {
         let idx_filename = format!("{dict_prefix}.idx");
-        // idx_filename_gz = format!("{dict_prefix}.gz");
-        // open_file(idx_filename, idx_filename_gz);
 
         let mut idx = Vec::new();
         let mut file = File::open(idx_filename)?;
         // Make sure that file size matches ifo
-        assert_eq!(container.ifo.idxfilesize, file.metadata()?.len() as usize);
+        if ifo.idxfilesize != file.metadata()?.len() as usize {
+            return Err(StardictError::InvalidByte(
+                "The .idx file size does not match the .ifo.",
+            ));
+        }
         file.read_to_end(&mut idx)?;
 
-        let mut idx_content = BTreeMap::new();
-        let idx_cords_bytes_size = (container.ifo.idxoffsetbits + 32) / 8;
-
-        // Parse data with byte functions
-        let mut words: Vec<Vec<u8>> = vec![];
-        let mut byte_counter = 0;
-        while byte_counter < idx.len() {
-            let mut byte_chunk = vec![];
-            byte_counter +=
-                Self::find_until_cords(&mut byte_chunk, &idx[byte_counter..], idx_cords_bytes_size);
-            words.push(byte_chunk);
+        if ifo.idxoffsetbits != 32 {
+            return Err(StardictError::InvalidByte(
+                "Only 32-bit .idx offsets are supported.",
+            ));
         }
-
-        // Make sure wordcount matches
-        assert_eq!(container.ifo.wordcount, words.len());
+        let idx_cords_bytes_size = (ifo.idxoffsetbits + 32) / 8;
 
         // Parse each record
-        for word in words.iter() {
-            let (word, cord_bytes) = Self::split_at_null(word);
-
+        let mut idx_content: BTreeMap<String, Vec<Cords32bit>> = BTreeMap::new();
+        let mut records = Vec::with_capacity(ifo.wordcount);
+        let mut rest = idx.as_slice();
+        while !rest.is_empty() {
+            let (word, tail) = Self::split_at_null(rest).ok_or(StardictError::InvalidByte(
+                "Null byte should exist as separator.",
+            ))?;
+            if tail.len() < idx_cords_bytes_size {
+                return Err(StardictError::InvalidByte("Truncated .idx record."));
+            }
+            let (cord_bytes, tail) = tail.split_at(idx_cords_bytes_size);
+
+            let word = String::from_utf8(word.to_vec())?;
             let cords = Cords32bit::unpack_from_slice(cord_bytes)?;
+            idx_content.entry(word.clone()).or_default().push(cords);
+            records.push((word, cords));
 
-            idx_content.insert(String::from_utf8(word.to_vec())?, cords);
+            rest = tail;
         }
 
-        Ok(Self { idx, idx_content })
+        // Make sure wordcount matches
+        if ifo.wordcount != records.len() {
+            return Err(StardictError::WordCount(
+                "The amount of words in the .idx does not match the .ifo.",
+            ));
+        }
+
+        Ok(Self {
+            idx_content,
+            records,
+        })
+    }
+    pub fn contains(&self, word: &str) -> bool {
+        self.idx_content.contains_key(word)
     }
-    pub fn keys(&self) -> Vec<String> {
-        self.idx_content.keys().cloned().collect()
+    /// Grabs the word at the given position of the .idx file.
+    pub fn word_at(&self, index: usize) -> Option<&str> {
+        self.records.get(index).map(|(word, _)| word.as_str())
     }
 }
 
-impl<'a> std::ops::Index<&'a str> for SDidx {
-    type Output = Cords32bit;
+impl std::ops::Index<&str> for SDidx {
+    type Output = [Cords32bit];
 
     fn index(&self, index: &str) -> &Self::Output {
         &self.idx_content[index]
@@ -117,17 +118,12 @@ mod tests {
     const FILEDIR: &str = "src/testdata/stardict-EnglishEtymology-2.4.2/EnglishEtymology";
 
     #[test]
-    #[ignore = "Expensive test -- reads file twice."]
     fn idx_parser_test() {
-        let dummy_dict = Stardict {
-            in_memory: false,
-            ifo: crate::stardict::ifo::SDifo::new(FILEDIR).expect("File should parse properly"),
-            idx: SDidx::default(),
-            dict: crate::stardict::dict::SDdict::default(),
-            syn: None,
-            cache: BTreeMap::new(),
-        };
-        let idx = SDidx::new(FILEDIR, &dummy_dict);
-        assert!(idx.is_ok());
+        let ifo = SDifo::new(FILEDIR).expect("File should parse properly");
+        let idx = SDidx::new(FILEDIR, &ifo).expect("File should parse properly");
+        assert_eq!(ifo.wordcount, idx.records.len());
+        assert_eq!(Some("'em"), idx.word_at(0));
+        assert_eq!(0, idx["'em"][0].offset);
+        assert_eq!(136, idx["'em"][0].data_size);
     }
 }
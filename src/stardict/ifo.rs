@@ -1,7 +1,8 @@
 //! A module containing the .ifo parser for StarDict
 //!
 
-use super::StardictResult;
+use super::{StardictError, StardictResult};
+use serde::Serialize;
 use std::{
     collections::BTreeMap,
     fs::File,
@@ -27,9 +28,11 @@ use std::{
 /// website=
 /// description=
 /// date=
-/// sametypesequence=!req(important)
+/// sametypesequence=
 ///
-#[derive(Debug, PartialEq, Default)]
+/// Without a sametypesequence, every field of an entry
+/// inside the .dict is led by its own type.
+#[derive(Debug, PartialEq, Default, Serialize)]
 pub struct SDifo {
     // Required fields
     pub version: String,
@@ -37,8 +40,8 @@ pub struct SDifo {
     pub wordcount: usize,
     pub idxfilesize: usize,
     pub idxoffsetbits: usize,
-    pub sametypesequence: char,
     // Optional fields
+    pub sametypesequence: Option<char>,
     pub synwordcount: Option<usize>,
     pub author: Option<String>,
     pub email: Option<String>,
@@ -63,41 +66,31 @@ impl SDifo {
                 continue; // Skips header
             }
             if let Ok(l) = line {
-                let pair: Vec<String> = l.split('=').map(|s| s.trim_end().to_string()).collect();
-                config.insert(pair[0].clone(), pair[1].clone());
+                if let Some((key, value)) = l.split_once('=') {
+                    config.insert(key.trim_end().to_string(), value.trim_end().to_string());
+                }
             }
         }
 
         // Required fields
-        _self.version = config.remove("version").expect("Version is required.");
-        _self.bookname = config.remove("bookname").expect("Bookname is required.");
-        _self.wordcount = config
-            .remove("wordcount")
-            .expect("Wordcount is required.")
-            .parse()?;
-        _self.idxfilesize = config
-            .remove("idxfilesize")
-            .expect("Idxfilesize is required.")
-            .parse()?;
-        _self.sametypesequence = config
-            .remove("sametypesequence")
-            .expect("Sametypesequence is highly required.")
-            .chars()
-            .next()
-            .expect("Sametypesequence should yield a character");
+        let mut required =
+            |key: &'static str| config.remove(key).ok_or(StardictError::MissingField(key));
+        _self.version = required("version")?;
+        _self.bookname = required("bookname")?;
+        _self.wordcount = required("wordcount")?.parse()?;
+        _self.idxfilesize = required("idxfilesize")?.parse()?;
         _self.idxoffsetbits = config
             .remove("idxoffsetbits")
             .unwrap_or_else(|| String::from("32"))
             .parse()?;
         // Optional fields
-        if _self.version == "3.0.0" {
-            _self.synwordcount = Some(
-                config
-                    .remove("synwordcount")
-                    .expect("Synwordcount is required at 3.0.0")
-                    .parse()?,
-            );
-        }
+        _self.sametypesequence = config
+            .remove("sametypesequence")
+            .and_then(|types| types.chars().next());
+        _self.synwordcount = config
+            .remove("synwordcount")
+            .map(|count| count.parse())
+            .transpose()?;
         _self.author = Some(config.remove("author").unwrap_or_default());
         _self.email = Some(config.remove("email").unwrap_or_default());
         _self.website = Some(config.remove("website").unwrap_or_default());
@@ -111,6 +104,8 @@ impl SDifo {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::TempDir;
+    use std::fs;
     const FILEDIR: &str = "src/testdata/stardict-EnglishEtymology-2.4.2/EnglishEtymology";
 
     #[test]
@@ -120,6 +115,30 @@ mod tests {
         assert_eq!(18380, ifo.wordcount);
         assert_eq!(303020, ifo.idxfilesize);
         assert_eq!("English Etymology", ifo.bookname);
-        assert_eq!('m', ifo.sametypesequence);
+        assert_eq!(Some('m'), ifo.sametypesequence);
+    }
+
+    #[test]
+    fn missing_fields_should_be_reported() {
+        let dir = TempDir::new("ifo-missing");
+        let prefix = dir.join("test").to_string_lossy().to_string();
+
+        fs::write(
+            format!("{prefix}.ifo"),
+            "StarDict's dict ifo file\nversion=2.4.2\nbookname=Test\nwordcount=1\nidxfilesize=10\n",
+        )
+        .expect("Temp dir should be writable");
+        let ifo = SDifo::new(&prefix).expect("File should parse properly");
+        assert_eq!(None, ifo.sametypesequence);
+
+        fs::write(
+            format!("{prefix}.ifo"),
+            "StarDict's dict ifo file\nversion=2.4.2\nwordcount=1\nidxfilesize=10\n",
+        )
+        .expect("Temp dir should be writable");
+        assert!(matches!(
+            SDifo::new(&prefix),
+            Err(StardictError::MissingField("bookname"))
+        ));
     }
 }
@@ -0,0 +1,438 @@
+//! A module containing the importers turning other
+//! dictionary formats into StarDict dictionaries.
+//!
+
+use super::builder::SDBuilder;
+use crate::render;
+use std::io;
+
+/// The formats a dictionary can be imported from.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ArgEnum)]
+pub enum ImportFormat {
+    /// StarDict's tabfile, i.e.: `word|synonym\tdefinition`
+    Tab,
+    /// ABBYY Lingvo's DSL
+    Dsl,
+    /// FreeDict's TEI P5 XML
+    Tei,
+    /// kaikki.org's Wiktionary extracts, one JSON entry per line
+    Kaikki,
+}
+
+impl ImportFormat {
+    /// Guesses the format of a file from its extension.
+    pub fn from_path(path: &str) -> Self {
+        let path = path.to_lowercase();
+        if path.ends_with(".dsl") {
+            Self::Dsl
+        } else if path.ends_with(".tei") {
+            Self::Tei
+        } else if path.ends_with(".jsonl") {
+            Self::Kaikki
+        } else {
+            Self::Tab
+        }
+    }
+}
+
+/// Imports a dictionary of the given format.
+pub fn import(format: ImportFormat, source: &[u8], bookname: &str) -> io::Result<SDBuilder> {
+    match format {
+        ImportFormat::Tab => Ok(import_tabfile(&String::from_utf8_lossy(source), bookname)),
+        ImportFormat::Dsl => Ok(import_dsl(&decode_text(source), bookname)),
+        ImportFormat::Tei => crate::freedict::to_stardict(source, bookname),
+        ImportFormat::Kaikki => crate::kaikki::to_stardict(source, bookname),
+    }
+}
+
+//* Tabfile *//
+
+/// Imports a tabfile, where every line holds the headword, its synonyms
+/// separated with `|`, then a tab and the plain text definition.
+pub fn import_tabfile(source: &str, bookname: &str) -> SDBuilder {
+    let mut builder = SDBuilder::new(bookname, 'm');
+
+    for line in source.lines() {
+        let fields = split_unescaped(line, '\t');
+        let [headwords, definition, ..] = fields.as_slice() else {
+            continue;
+        };
+        let mut headwords = split_unescaped(headwords, '|')
+            .into_iter()
+            .map(unescape)
+            .filter(|word| !word.is_empty());
+        let Some(word) = headwords.next() else {
+            continue;
+        };
+
+        builder.add_entry(&word, &unescape(definition));
+        for synonym in headwords {
+            builder.add_synonym(&synonym, &word);
+        }
+    }
+
+    builder
+}
+
+/// Splits a line on every separator not preceded by a backslash.
+fn split_unescaped(line: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, chr) in line.char_indices() {
+        match chr {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            _ if chr == separator => {
+                parts.push(&line[start..i]);
+                start = i + chr.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&line[start..]);
+    parts
+}
+
+/// Reverses the escapes of a tabfile field.
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(chr) = chars.next() {
+        if chr != '\\' {
+            unescaped.push(chr);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+//* ABBYY Lingvo DSL *//
+
+/// Decodes text which is either UTF-8 or UTF-16, the latter being
+/// what DSL files are usually distributed in.
+pub fn decode_text(bytes: &[u8]) -> String {
+    let utf16 = |bytes: &[u8], little_endian: bool| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| match little_endian {
+                true => u16::from_le_bytes([pair[0], pair[1]]),
+                false => u16::from_be_bytes([pair[0], pair[1]]),
+            })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, true),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, false),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).to_string(),
+        // Without a BOM, ASCII text in UTF-16 is full of null bytes
+        [_, 0, ..] => utf16(bytes, true),
+        [0, _, ..] => utf16(bytes, false),
+        _ => String::from_utf8_lossy(bytes).to_string(),
+    }
+}
+
+/// Imports a DSL dictionary, converting its cards into XDXF.
+///
+/// The `#NAME` header takes priority over the given name, while
+/// the language headers are kept in the description.
+pub fn import_dsl(source: &str, bookname: &str) -> SDBuilder {
+    let mut builder = SDBuilder::new(bookname, 'x');
+    let mut languages = vec![];
+    let mut headwords: Vec<String> = vec![];
+    let mut body: Vec<String> = vec![];
+
+    let mut flush = |headwords: &mut Vec<String>, body: &mut Vec<String>| {
+        if let Some(word) = headwords.first() {
+            let article = format!(
+                "<k>{}</k>\n{}",
+                render::escape_markup(word),
+                body.join("\n")
+            );
+            builder.add_entry(word, &article);
+            for synonym in headwords.iter().skip(1) {
+                builder.add_synonym(synonym, word);
+            }
+        }
+        headwords.clear();
+        body.clear();
+    };
+
+    let mut bookname_header = None;
+    for line in source.lines() {
+        let line = line.trim_end_matches('\r');
+        if let Some(header) = line.strip_prefix('#') {
+            let (key, value) = header
+                .split_once(char::is_whitespace)
+                .unwrap_or((header, ""));
+            let value = value.trim().trim_matches('"').to_string();
+            match key {
+                "NAME" => bookname_header = Some(value),
+                "INDEX_LANGUAGE" => languages.insert(0, value),
+                "CONTENTS_LANGUAGE" => languages.push(value),
+                _ => {}
+            }
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if line.starts_with(char::is_whitespace) {
+            body.push(dsl_line_to_xdxf(line.trim()));
+        } else {
+            // A headword after a body starts a new card
+            if !body.is_empty() {
+                flush(&mut headwords, &mut body);
+            }
+            let headword = dsl_headword(line);
+            if !headword.is_empty() {
+                headwords.push(headword);
+            }
+        }
+    }
+    flush(&mut headwords, &mut body);
+
+    if let Some(name) = bookname_header.filter(|name| !name.is_empty()) {
+        builder.bookname = name;
+    }
+    if !languages.is_empty() {
+        builder.desc = Some(languages.join(" → "));
+    }
+    builder
+}
+
+/// Cleans a DSL headword up, leaving out its unsorted `{...}`
+/// parts, comments and escapes.
+fn dsl_headword(line: &str) -> String {
+    let mut headword = String::new();
+    let mut chars = line.trim().chars();
+    let mut braces = 0usize;
+    while let Some(chr) = chars.next() {
+        match chr {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    if braces == 0 {
+                        headword.push(escaped);
+                    }
+                }
+            }
+            '{' => braces += 1,
+            '}' => braces = braces.saturating_sub(1),
+            _ if braces == 0 => headword.push(chr),
+            _ => {}
+        }
+    }
+    headword.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Converts a single line of a DSL card into XDXF.
+fn dsl_line_to_xdxf(line: &str) -> String {
+    // [mN] sets the margin of the whole line
+    let (margin, line) = match line.strip_prefix("[m") {
+        Some(rest) => match rest.split_once(']') {
+            Some((level, rest)) if level.chars().all(|chr| chr.is_ascii_digit()) => {
+                (level.parse().unwrap_or(1), rest)
+            }
+            _ => (0, line),
+        },
+        None => (0, line),
+    };
+    let line = line.trim_end().strip_suffix("[/m]").unwrap_or(line);
+
+    let mut xdxf = String::new();
+    let mut chars = line.chars().peekable();
+    let mut hidden_depth = 0usize;
+    while let Some(chr) = chars.next() {
+        match chr {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    push_text(&mut xdxf, escaped, hidden_depth);
+                }
+            }
+            '{' if chars.peek() == Some(&'{') => {
+                // {{comments}} are left out entirely
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '}' && next == '}' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            '<' if chars.peek() == Some(&'<') => {
+                chars.next();
+                xdxf.push_str("<kref>");
+            }
+            '>' if chars.peek() == Some(&'>') => {
+                chars.next();
+                xdxf.push_str("</kref>");
+            }
+            '[' => {
+                let mut tag = String::new();
+                for next in chars.by_ref() {
+                    if next == ']' {
+                        break;
+                    }
+                    tag.push(next);
+                }
+                match dsl_tag_to_xdxf(&tag) {
+                    DslTag::Xdxf(converted) => xdxf.push_str(&converted),
+                    DslTag::Hide => hidden_depth += 1,
+                    DslTag::Unhide => hidden_depth = hidden_depth.saturating_sub(1),
+                    DslTag::Drop => {}
+                }
+            }
+            _ => push_text(&mut xdxf, chr, hidden_depth),
+        }
+    }
+
+    let open = "<blockquote>".repeat(margin);
+    let close = "</blockquote>".repeat(margin);
+    format!("{open}{xdxf}{close}")
+}
+
+/// What a DSL tag turns into.
+enum DslTag {
+    Xdxf(String),
+    /// Opens a tag whose contents are left out (i.e.: media)
+    Hide,
+    Unhide,
+    Drop,
+}
+
+/// Converts a DSL tag (without its brackets) into XDXF.
+fn dsl_tag_to_xdxf(tag: &str) -> DslTag {
+    let (closing, tag) = match tag.strip_prefix('/') {
+        Some(tag) => (true, tag),
+        None => (false, tag),
+    };
+    let (name, argument) = tag.split_once(' ').unwrap_or((tag, ""));
+
+    let xdxf_name = match name {
+        "b" | "i" | "u" | "sup" | "sub" => name,
+        "c" => "c",
+        "trn" | "trn1" => "dtrn",
+        "ex" => "ex",
+        "p" => "abr",
+        "com" => "co",
+        "ref" => "kref",
+        "url" => "iref",
+        "t" => "tr",
+        "s" | "video" => {
+            return if closing {
+                DslTag::Unhide
+            } else {
+                DslTag::Hide
+            }
+        }
+        _ => return DslTag::Drop,
+    };
+
+    if closing {
+        DslTag::Xdxf(format!("</{xdxf_name}>"))
+    } else if name == "c" && !argument.is_empty() {
+        DslTag::Xdxf(format!(
+            "<c c=\"{}\">",
+            render::escape_markup(argument.trim())
+        ))
+    } else {
+        DslTag::Xdxf(format!("<{xdxf_name}>"))
+    }
+}
+
+fn push_text(xdxf: &mut String, chr: char, hidden_depth: usize) {
+    if hidden_depth > 0 {
+        return;
+    }
+    match chr {
+        '&' => xdxf.push_str("&amp;"),
+        '<' => xdxf.push_str("&lt;"),
+        '>' => xdxf.push_str("&gt;"),
+        _ => xdxf.push(chr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stardict::Stardict;
+    use crate::testing::TempDir;
+
+    fn read_back(builder: SDBuilder, name: &str) -> Stardict {
+        let dir = TempDir::new(&format!("import-{name}"));
+        let prefix = dir.join("test").to_string_lossy().to_string();
+        builder
+            .write(&prefix)
+            .expect("Dictionary should be written");
+        Stardict::new(&prefix).expect("Dictionary should parse properly")
+    }
+
+    #[test]
+    fn tabfile_import_test() {
+        let builder = import_tabfile(
+            "apple|pomme\tA fruit.\\nRed.\na\\|b\tPipe.\nbroken line\n",
+            "Tab",
+        );
+        let mut dict = read_back(builder, "tab");
+
+        assert_eq!(Some('m'), dict.ifo.sametypesequence);
+        assert_eq!(
+            "A fruit.\nRed.",
+            dict.get("apple").expect("Word should exist")
+        );
+        assert_eq!(
+            "A fruit.\nRed.",
+            dict.get("pomme").expect("Synonym should exist")
+        );
+        assert_eq!("Pipe.", dict.get("a|b").expect("Word should exist"));
+        assert_eq!(2, dict.idx.records.len());
+    }
+
+    #[test]
+    fn dsl_import_test() {
+        const DSL: &str = "#NAME \"Test DSL\"\r\n#INDEX_LANGUAGE \"English\"\r\n#CONTENTS_LANGUAGE \"Russian\"\r\n\r\ncat\r\nkitty{{alt}}\r\n\t[m1][p]n.[/p] [trn]кошка[/trn] {{note}}[/m]\r\n\t[m2][ex]a \\[black\\] cat[/ex][s]cat.wav[/s][/m]\r\n\tsee <<kitten>>\r\n";
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain(DSL.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+
+        let builder = import(ImportFormat::Dsl, &utf16, "Fallback").expect("DSL should import");
+        let mut dict = read_back(builder, "dsl");
+
+        assert_eq!("Test DSL", dict.get_bookname());
+        assert_eq!(Some('x'), dict.ifo.sametypesequence);
+        assert_eq!(Some("English → Russian".to_string()), dict.ifo.desc);
+        assert_eq!(
+            "<k>cat</k>\n<blockquote><abr>n.</abr> <dtrn>кошка</dtrn> </blockquote>\n<blockquote><blockquote><ex>a [black] cat</ex></blockquote></blockquote>\nsee <kref>kitten</kref>",
+            dict.get("kitty").expect("Synonym should exist")
+        );
+    }
+
+    #[test]
+    fn dsl_headword_test() {
+        assert_eq!("ab", dsl_headword("a{b}b"));
+        assert_eq!("a[b]", dsl_headword("a\\[b\\]"));
+    }
+
+    #[test]
+    fn text_decoding_test() {
+        assert_eq!("hi", decode_text(b"\xEF\xBB\xBFhi"));
+        assert_eq!("hi", decode_text(b"h\0i\0"));
+        assert_eq!("hi", decode_text(b"\xFE\xFF\0h\0i"));
+    }
+
+    #[test]
+    fn import_errors_should_be_returned() {
+        let imported = import(ImportFormat::Kaikki, b"{\"word\": \"\xFF\"}\n", "Broken");
+        assert!(imported.is_err());
+    }
+}
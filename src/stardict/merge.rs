@@ -0,0 +1,191 @@
+//! A module containing the merger for StarDict dictionaries.
+//!
+
+use super::{builder::SDBuilder, Stardict, StardictResult};
+use crate::render;
+use std::collections::BTreeMap;
+
+/// Merges several dictionaries into a single one.
+///
+/// Entries sharing a headword are concatenated, each labeled with
+/// the name of the dictionary it came from, and the synonyms of every
+/// dictionary are carried over. Dictionaries of differing types are
+/// merged as plain text.
+pub fn merge(dicts: &mut [Stardict], bookname: &str) -> StardictResult<SDBuilder> {
+    let entry_type = match dicts.first() {
+        Some(first) if dicts.iter().all(|d| d.entry_type() == first.entry_type()) => {
+            first.entry_type()
+        }
+        _ => 'm',
+    };
+
+    let mut entries: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    let mut builder = SDBuilder::new(bookname, entry_type);
+
+    collect(dicts, entry_type, &mut entries, &mut builder)?;
+
+    for (word, sources) in entries.iter() {
+        if let [(_, entry)] = sources.as_slice() {
+            builder.add_entry(word, entry);
+            continue;
+        }
+
+        let definition = sources
+            .iter()
+            .map(|(source, entry)| format!("{}{entry}", label(entry_type, source)))
+            .collect::<Vec<_>>()
+            .join(separator(entry_type));
+        builder.add_entry(word, &definition);
+    }
+
+    Ok(builder)
+}
+
+/// Collects the entries of every dictionary under their headwords,
+/// converting them to the given type, and adds their synonyms.
+fn collect(
+    dicts: &mut [Stardict],
+    entry_type: char,
+    entries: &mut BTreeMap<String, Vec<(String, String)>>,
+    builder: &mut SDBuilder,
+) -> StardictResult<()> {
+    for dict in dicts.iter_mut() {
+        let source_type = dict.entry_type();
+        let source = dict.get_bookname();
+
+        for (word, cords) in dict.idx.records.iter() {
+            let entry = dict.dict.get(cords)?;
+            let entry = if source_type == entry_type {
+                entry
+            } else {
+                render::render(source_type, &entry, false)
+            };
+            entries
+                .entry(word.clone())
+                .or_default()
+                .push((source.clone(), entry));
+        }
+
+        for (synonym, word) in dict.synonyms() {
+            builder.add_synonym(&synonym, &word);
+        }
+    }
+    Ok(())
+}
+
+/// Grabs the label marking which dictionary an entry came from.
+fn label(entry_type: char, source: &str) -> String {
+    let escaped = render::escape_markup(source);
+    match entry_type {
+        'h' => format!("<b>[{escaped}]</b><br>"),
+        'g' | 'x' => format!("<b>[{escaped}]</b>\n"),
+        _ => format!("[{source}]\n"),
+    }
+}
+
+/// Grabs the separator placed between the entries of a headword.
+pub(super) fn separator(entry_type: char) -> &'static str {
+    match entry_type {
+        'h' => "<br><br>",
+        _ => "\n\n",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn write_dict(
+        prefix: &str,
+        bookname: &str,
+        entries: &[(&str, &str)],
+        synonyms: &[(&str, &str)],
+    ) {
+        let mut builder = SDBuilder::new(bookname, 'm');
+        for (word, definition) in entries.iter() {
+            builder.add_entry(word, definition);
+        }
+        for (synonym, word) in synonyms.iter() {
+            builder.add_synonym(synonym, word);
+        }
+        builder.write(prefix).expect("Dictionary should be written");
+    }
+
+    #[test]
+    fn merged_dictionary_should_combine_entries() {
+        let dir = TempDir::new("merge");
+        let prefix = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        write_dict(
+            &prefix("a"),
+            "A",
+            &[("cat", "A feline."), ("dog", "A canine.")],
+            &[("kitty", "cat")],
+        );
+        write_dict(
+            &prefix("b"),
+            "B",
+            &[("Cat", "Meows."), ("cat", "Purrs.")],
+            &[("pup", "Cat")],
+        );
+
+        let mut dicts = vec![
+            Stardict::new(&prefix("a")).expect("Dictionary should parse properly"),
+            Stardict::new(&prefix("b")).expect("Dictionary should parse properly"),
+        ];
+        let builder = merge(&mut dicts, "A + B").expect("Dictionaries should merge");
+        builder
+            .write(&prefix("out"))
+            .expect("Dictionary should be written");
+
+        let mut merged = Stardict::new(&prefix("out")).expect("Dictionary should parse properly");
+        assert_eq!(
+            vec!["Cat", "cat", "dog"],
+            merged
+                .idx
+                .records
+                .iter()
+                .map(|(word, _)| word)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "[A]\nA feline.\n\n[B]\nPurrs.",
+            merged.get("cat").expect("Word should exist")
+        );
+        assert_eq!("Meows.", merged.get("pup").expect("Synonym should exist"));
+        assert_eq!(
+            "[A]\nA feline.\n\n[B]\nPurrs.",
+            merged.get("kitty").expect("Synonym should exist")
+        );
+    }
+
+    #[test]
+    fn repeated_headwords_should_keep_their_entries() {
+        let dir = TempDir::new("merge-repeated");
+        let prefix = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        write_dict(
+            &prefix("a"),
+            "A",
+            &[("bank", "A river side."), ("bank", "A money keeper.")],
+            &[],
+        );
+        write_dict(&prefix("b"), "B", &[("bank", "A slope.")], &[]);
+
+        let mut dicts = vec![
+            Stardict::new(&prefix("a")).expect("Dictionary should parse properly"),
+            Stardict::new(&prefix("b")).expect("Dictionary should parse properly"),
+        ];
+        let builder = merge(&mut dicts, "A + B").expect("Dictionaries should merge");
+        builder
+            .write(&prefix("out"))
+            .expect("Dictionary should be written");
+
+        let mut merged = Stardict::new(&prefix("out")).expect("Dictionary should parse properly");
+        assert_eq!(
+            "[A]\nA river side.\n\n[A]\nA money keeper.\n\n[B]\nA slope.",
+            merged.get("bank").expect("Word should exist")
+        );
+    }
+}
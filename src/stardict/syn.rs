@@ -1,26 +1,47 @@
 //! A module containing the .syn parser for StarDict
 //!
 
-use std::fs::File;
+use std::{collections::BTreeMap, fs::File, io::Read};
 
 /// A struct parsing a StarDict .syn file.
 ///
+/// A .syn is a sorted list of synonym entries,
+/// all of which contains two consecutive fields:
+///     - synonym_word ;; A string terminated by a null byte
+///     - original_word_index ;; the index of the original word in .idx
+///
 /// .syn files are optional, and thus the new()
 /// method has a chance to return the `None`
 /// variant.
 pub struct SDSyn {
-    syn: File,
+    pub syn_content: BTreeMap<String, usize>,
 }
 
 impl SDSyn {
     pub fn new(dict_prefix: &str) -> Option<Self> {
         let filename = format!("{dict_prefix}.syn");
-        if let Ok(f) = File::open(filename) {
-            Some(Self { syn: f })
-        } else {
-            // .syn files are optional
-            None
+        // .syn files are optional
+        let mut file = File::open(filename).ok()?;
+        let mut syn = vec![];
+        file.read_to_end(&mut syn).ok()?;
+
+        let mut syn_content = BTreeMap::new();
+        let mut rest = syn.as_slice();
+        while !rest.is_empty() {
+            let i = rest.iter().position(|&byte| byte == 0)?;
+            let index = rest.get(i + 1..i + 5)?;
+            let word = String::from_utf8(rest[..i].to_vec()).ok()?;
+            let index = u32::from_be_bytes([index[0], index[1], index[2], index[3]]);
+            syn_content.insert(word, index as usize);
+            rest = &rest[i + 5..];
         }
+
+        Some(Self { syn_content })
+    }
+
+    /// Grabs the .idx index of the word the synonym refers to.
+    pub fn get(&self, synonym: &str) -> Option<usize> {
+        self.syn_content.get(synonym).copied()
     }
 }
 
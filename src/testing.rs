@@ -0,0 +1,40 @@
+//! A module containing the helpers shared by the tests of every module.
+//!
+
+use std::{
+    env, fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// A temporary directory of a test, removed along with
+/// everything inside of it once dropped, even if the test fails.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates an empty directory, named after the test and the process
+    /// so that tests running at once don't step on each other.
+    pub fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("rdict-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).expect("Temp dir should be writable");
+        Self { path }
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
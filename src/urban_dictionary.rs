@@ -1,7 +1,10 @@
-use crate::colored_display::print_colored;
+use crate::{
+    cache::CacheKey,
+    http::{self, Client},
+    provider::{Entry, Sense},
+};
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::fmt::{Display, Formatter};
 
 //* Urban Dictionary Fields *//
 
@@ -24,13 +27,22 @@ pub struct UrbanDefinition {
     author: String,
 }
 
-const URL: &str = "https://api.urbandictionary.com/v0/define?term=";
-type Error = Box<dyn std::error::Error>;
+type Error = Box<dyn std::error::Error + Send + Sync>;
 /// A function which takes a word and returns a list of
 /// definitions obtained from Urban Dictionary.
-pub fn define(word: &str) -> Result<UrbanDictionary, Error> {
-    let request_url = format!("{URL}{word}");
-    let response = reqwest::blocking::get(request_url)?.text()?;
+pub async fn define(client: &Client, word: &str) -> Result<UrbanDictionary, Error> {
+    let request_url = format!(
+        "{}/define?term={}",
+        client.endpoints.urban_dictionary,
+        http::encode(word)
+    );
+    let response = client
+        .fetch(&CacheKey::new("urban-dictionary", "en", word), &request_url)
+        .await?;
+    if !response.is_success() {
+        return Err(format!("Urban Dictionary responded with HTTP {}", response.status).into());
+    }
+    let response = response.text();
 
     let mut parsed_resp: UrbanDictionary = serde_json::from_str(response.as_str())?;
     parsed_resp.word = word.to_string(); // Since at this point, the definition is guaranteed to exist.
@@ -71,16 +83,22 @@ impl UrbanDefinition {
     }
 }
 
-impl Display for UrbanDictionary {
-    fn fmt(&self, fmtr: &mut Formatter) -> std::fmt::Result {
-        writeln!(fmtr)?;
-
-        writeln!(fmtr, "{}", print_colored(self.get_word().as_str()))?;
-        for (author, definition) in self.get_definitions().iter() {
-            writeln!(fmtr, "    - {definition} (by: {author})")?;
+impl From<UrbanDictionary> for Entry {
+    /// Maps the definitions in the order Urban Dictionary ranks them.
+    fn from(found: UrbanDictionary) -> Self {
+        Entry {
+            senses: found
+                .definitions
+                .iter()
+                .map(|definition| Sense {
+                    definition: definition.get_definition(),
+                    author: Some(definition.get_author()),
+                    ..Sense::default()
+                })
+                .collect(),
+            word: found.get_word(),
+            ..Entry::default()
         }
-
-        Ok(())
     }
 }
 
@@ -132,4 +150,35 @@ mod tests {
         }
         assert_eq!(2, counter);
     }
+
+    #[tokio::test]
+    async fn definitions_should_be_requested() {
+        use crate::http::{
+            mock::{self, MockServer},
+            Endpoints,
+        };
+
+        let server = MockServer::start(&[
+            ("/define?term=faux", 200, FAKE_DATA),
+            ("/define?term=rock%20%26%20roll", 200, FAKE_DATA),
+        ]);
+        let client = Client::new(
+            mock::transport(),
+            Endpoints {
+                urban_dictionary: server.url.clone(),
+                ..Endpoints::default()
+            },
+        );
+
+        let data = define(&client, "faux")
+            .await
+            .expect("Word should be defined");
+        assert_eq!("faux", data.get_word());
+        assert_eq!(2, data.get_definitions().len());
+        assert!(define(&client, "fawx").await.is_err());
+        let phrase = define(&client, "rock & roll")
+            .await
+            .expect("Phrase should be defined");
+        assert_eq!("rock & roll", phrase.get_word());
+    }
 }
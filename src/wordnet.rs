@@ -0,0 +1,632 @@
+//! A module containing the reader for the Princeton WordNet database.
+//!
+
+use crate::dictionary::{Meaning, Semantic, WordDefinition};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Seek, SeekFrom},
+    path::PathBuf,
+};
+
+/// The parts of speech WordNet has, along with the suffix of their files.
+const PARTS_OF_SPEECH: &[(char, &str)] =
+    &[('n', "noun"), ('v', "verb"), ('a', "adj"), ('r', "adv")];
+
+/// A relation from a synset, or one of its words, to another.
+///
+/// Semantic pointers relate whole synsets and have no source or
+/// target word, while lexical ones relate a word to another word.
+#[derive(Debug, PartialEq)]
+struct Pointer {
+    symbol: String,
+    offset: u64,
+    pos: char,
+    source: usize,
+    target: usize,
+}
+
+/// A set of synonyms sharing the same meaning.
+#[derive(Debug, PartialEq)]
+pub struct Synset {
+    pub pos: char,
+    pub words: Vec<String>,
+    pub gloss: String,
+    pointers: Vec<Pointer>,
+}
+
+/// A struct for looking words up in a WordNet database directory,
+/// holding the `index.*`, `data.*` and `*.exc` files.
+///
+/// An index.* is a sorted list of lines, each of which holds:
+///     - lemma ;; lowercased, with underscores for spaces
+///     - pos, synset_cnt, p_cnt, [ptr_symbol...], sense_cnt, tagsense_cnt
+///     - synset_offset... ;; the byte offset of every synset in data.*
+///
+/// The index files are binary-searched straight from the disk, while
+/// the much smaller `*.exc` files are read once, when opening.
+pub struct WordNet {
+    dir: PathBuf,
+    /// The base forms of every irregular inflection, by part of speech.
+    exceptions: HashMap<&'static str, HashMap<String, Vec<String>>>,
+}
+
+impl WordNet {
+    /// Opens the WordNet database in the given directory.
+    pub fn new(dir: &str) -> io::Result<Self> {
+        let dir = PathBuf::from(dir);
+        if !dir.join("index.noun").exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No WordNet database was found in {}", dir.display()),
+            ));
+        }
+
+        let mut exceptions = HashMap::new();
+        for &(_, suffix) in PARTS_OF_SPEECH.iter() {
+            let path = dir.join(format!("{suffix}.exc"));
+            if !path.exists() {
+                continue;
+            }
+            let forms = fs::read_to_string(path)?
+                .lines()
+                .filter_map(|line| {
+                    let mut words = line.split(' ').map(String::from);
+                    Some((words.next()?, words.collect()))
+                })
+                .collect();
+            exceptions.insert(suffix, forms);
+        }
+        Ok(Self { dir, exceptions })
+    }
+
+    /// Grabs the base forms of an irregular inflection from the `*.exc` files.
+    fn exceptions(&self, lemma: &str, suffix: &str) -> Vec<String> {
+        self.exceptions
+            .get(suffix)
+            .and_then(|forms| forms.get(lemma))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Grabs the offsets of every synset the lemma belongs to,
+    /// binary-searching the index for the line starting with it.
+    fn index(&self, lemma: &str, suffix: &str) -> io::Result<Vec<u64>> {
+        let mut index = BufReader::new(File::open(self.dir.join(format!("index.{suffix}")))?);
+        let (mut low, mut high) = (0, index.get_ref().metadata()?.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match line_at(&mut index, mid)? {
+                Some(line) if line_lemma(&line) < lemma => low = mid + 1,
+                _ => high = mid,
+            }
+        }
+
+        let Some(line) = line_at(&mut index, low)?.filter(|line| line_lemma(line) == lemma) else {
+            return Ok(vec![]);
+        };
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let synset_count = fields
+            .get(2)
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(0);
+        Ok(fields[fields.len().saturating_sub(synset_count)..]
+            .iter()
+            .filter_map(|offset| offset.parse().ok())
+            .collect())
+    }
+
+    /// Reads the synset found at the given offset of a data.* file.
+    fn synset(&self, offset: u64, pos: char) -> io::Result<Synset> {
+        let mut data = BufReader::new(File::open(self.dir.join(format!("data.{}", suffix(pos))))?);
+        data.seek(SeekFrom::Start(offset))?;
+        let mut line = String::new();
+        data.read_line(&mut line)?;
+        parse_synset(&line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid synset at offset {offset}"),
+            )
+        })
+    }
+
+    /// Grabs every synset the word belongs to, along with the position
+    /// of the word in it, going through the irregular forms if needed.
+    pub fn synsets(&self, word: &str) -> io::Result<Vec<(Synset, usize)>> {
+        let lemma = word.trim().to_lowercase().replace(' ', "_");
+        let mut synsets = vec![];
+        for &(pos, suffix) in PARTS_OF_SPEECH.iter() {
+            let mut lemmas = vec![lemma.clone()];
+            lemmas.extend(self.exceptions(&lemma, suffix));
+
+            let found = synsets.len();
+            for lemma in lemmas.iter() {
+                for offset in self.index(lemma, suffix)? {
+                    let synset = self.synset(offset, pos)?;
+                    let position = synset
+                        .words
+                        .iter()
+                        .position(|word| word.to_lowercase().replace(' ', "_") == *lemma)
+                        .map_or(0, |i| i + 1);
+                    synsets.push((synset, position));
+                }
+                if synsets.len() > found {
+                    break;
+                }
+            }
+        }
+        Ok(synsets)
+    }
+
+    /// Grabs the synsets of the word as definitions, with
+    /// a meaning for every part of speech it has.
+    pub fn define(&self, word: &str) -> io::Result<Vec<WordDefinition>> {
+        let synsets = self.synsets(word)?;
+        if synsets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut meanings = vec![];
+        for &(pos, _) in PARTS_OF_SPEECH.iter() {
+            let synsets: Vec<&(Synset, usize)> = synsets
+                .iter()
+                .filter(|(synset, _)| normalize_pos(synset.pos) == pos)
+                .collect();
+            if synsets.is_empty() {
+                continue;
+            }
+            let glosses = synsets
+                .iter()
+                .map(|(synset, _)| synset.gloss.clone())
+                .collect();
+            let synonyms = dedup(
+                synsets
+                    .iter()
+                    .flat_map(|(synset, position)| other_words(synset, *position)),
+            );
+            let mut antonyms = vec![];
+            for (synset, position) in synsets.iter() {
+                antonyms.extend(self.related(synset, *position, &["!"])?);
+            }
+            meanings.push(Meaning::new(
+                part_of_speech_name(pos),
+                glosses,
+                synonyms,
+                dedup(antonyms.into_iter()),
+            ));
+        }
+
+        Ok(vec![WordDefinition::new(
+            &word.trim().to_lowercase(),
+            vec![],
+            meanings,
+            vec![],
+            None,
+        )])
+    }
+
+    /// Grabs the words related to the specified word in the given way.
+    pub fn relations(&self, word: &str, semantic: &Semantic) -> io::Result<Vec<String>> {
+        let symbols: &[&str] = match semantic {
+            Semantic::Synonym => {
+                return Ok(dedup(
+                    self.synsets(word)?
+                        .iter()
+                        .flat_map(|(synset, position)| other_words(synset, *position)),
+                ))
+            }
+            Semantic::Antonym => &["!"],
+            Semantic::Hypernym => &["@", "@i"],
+            Semantic::Hyponym => &["~", "~i"],
+            Semantic::Meronym => &["%m", "%s", "%p"],
+            Semantic::DerivedForm => &["+"],
+        };
+
+        let mut related = vec![];
+        for (synset, position) in self.synsets(word)?.iter() {
+            related.extend(self.related(synset, *position, symbols)?);
+        }
+        Ok(dedup(related.into_iter()))
+    }
+
+    /// Follows the pointers of the given kinds out of a synset. Lexical
+    /// pointers are only followed from the word at the given position.
+    fn related(
+        &self,
+        synset: &Synset,
+        position: usize,
+        symbols: &[&str],
+    ) -> io::Result<Vec<String>> {
+        let mut related = vec![];
+        for pointer in synset.pointers.iter() {
+            if !symbols.contains(&pointer.symbol.as_str()) {
+                continue;
+            }
+            if pointer.source != 0 && pointer.source != position {
+                continue;
+            }
+
+            let target = self.synset(pointer.offset, pointer.pos)?;
+            match pointer.target {
+                0 => related.extend(target.words),
+                target_word => related.extend(target.words.into_iter().nth(target_word - 1)),
+            }
+        }
+        Ok(related)
+    }
+}
+
+/// Parses a line of a data.* file, i.e.:
+/// `synset_offset lex_filenum ss_type w_cnt word lex_id [word lex_id...]
+/// p_cnt [ptr...] [frames...] | gloss`
+fn parse_synset(line: &str) -> Option<Synset> {
+    let (fields, gloss) = line.split_once(" | ").unwrap_or((line, ""));
+    let mut fields = fields.split_whitespace();
+    let pos = fields.nth(2)?.chars().next()?;
+
+    let word_count = usize::from_str_radix(fields.next()?, 16).ok()?;
+    let mut words = Vec::with_capacity(word_count);
+    for _ in 0..word_count {
+        words.push(display_word(fields.next()?));
+        // The lexical id
+        fields.next()?;
+    }
+
+    let pointer_count: usize = fields.next()?.parse().ok()?;
+    let mut pointers = Vec::with_capacity(pointer_count);
+    for _ in 0..pointer_count {
+        let symbol = fields.next()?.to_string();
+        let offset = fields.next()?.parse().ok()?;
+        let pos = fields.next()?.chars().next()?;
+        let source_target = fields.next()?;
+        pointers.push(Pointer {
+            symbol,
+            offset,
+            pos,
+            source: usize::from_str_radix(source_target.get(..2)?, 16).ok()?,
+            target: usize::from_str_radix(source_target.get(2..)?, 16).ok()?,
+        });
+    }
+
+    Some(Synset {
+        pos,
+        words,
+        gloss: gloss.trim().to_string(),
+        pointers,
+    })
+}
+
+/// Reads the first line starting at or after the given position.
+fn line_at(reader: &mut BufReader<File>, position: u64) -> io::Result<Option<String>> {
+    // The line the position is in is skipped, unless it starts there
+    reader.seek(SeekFrom::Start(position.saturating_sub(1)))?;
+    if position > 0 {
+        reader.read_until(b'\n', &mut vec![])?;
+    }
+    let mut line = String::new();
+    Ok((reader.read_line(&mut line)? > 0).then_some(line))
+}
+
+/// Grabs the lemma an index line is about, which the index is sorted by.
+/// The license header lines start with spaces, sorting them first.
+fn line_lemma(line: &str) -> &str {
+    line.split(' ').next().unwrap_or_default()
+}
+
+/// Turns a word of a data.* file back into a readable word, dropping
+/// the syntactic marker adjectives can have, i.e.: `galore(ip)`.
+fn display_word(word: &str) -> String {
+    let word = match word.find('(') {
+        Some(marker) if word.ends_with(')') => &word[..marker],
+        _ => word,
+    };
+    word.replace('_', " ")
+}
+
+/// Grabs the words of a synset other than the one at the given position.
+fn other_words(synset: &Synset, position: usize) -> impl Iterator<Item = String> + '_ {
+    synset
+        .words
+        .iter()
+        .enumerate()
+        .filter(move |(i, _)| i + 1 != position)
+        .map(|(_, word)| word.clone())
+}
+
+fn dedup(words: impl Iterator<Item = String>) -> Vec<String> {
+    let mut deduped: Vec<String> = vec![];
+    for word in words {
+        if !deduped.contains(&word) {
+            deduped.push(word);
+        }
+    }
+    deduped
+}
+
+/// Adjective satellites are stored along with the other adjectives.
+fn normalize_pos(pos: char) -> char {
+    match pos {
+        's' => 'a',
+        other => other,
+    }
+}
+
+fn suffix(pos: char) -> &'static str {
+    PARTS_OF_SPEECH
+        .iter()
+        .find(|(part, _)| *part == normalize_pos(pos))
+        .map_or("noun", |(_, suffix)| suffix)
+}
+
+fn part_of_speech_name(pos: char) -> &'static str {
+    match pos {
+        'n' => "noun",
+        'v' => "verb",
+        'a' => "adjective",
+        _ => "adverb",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use std::{collections::BTreeMap, path::Path};
+
+    /// A synset to write: its part of speech, words, pointers
+    /// (symbol, index of the target synset, source/target) and gloss.
+    type TestSynset<'a> = (
+        char,
+        &'a [&'a str],
+        &'a [(&'a str, usize, &'a str)],
+        &'a str,
+    );
+
+    /// Writes the data.* and index.* files of a tiny database.
+    fn write_wordnet(dir: &Path, synsets: &[TestSynset]) {
+        const HEADER: &str = "  1 This is a license header.\n";
+        let line = |i: usize, offsets: &[u64]| {
+            let (pos, words, pointers, gloss) = synsets[i];
+            let mut line = format!("{:08} 00 {pos} {:02x}", offsets[i], words.len());
+            for word in words.iter() {
+                line.push_str(&format!(" {word} 0"));
+            }
+            line.push_str(&format!(" {:03}", pointers.len()));
+            for &(symbol, target, source_target) in pointers.iter() {
+                let target_pos = synsets[target].0;
+                line.push_str(&format!(
+                    " {symbol} {:08} {target_pos} {source_target}",
+                    offsets[target]
+                ));
+            }
+            format!("{line} | {gloss}  \n")
+        };
+
+        // Offsets are fixed-width, so every line's length is known upfront
+        let mut offsets = vec![0; synsets.len()];
+        for &(pos, _) in PARTS_OF_SPEECH.iter() {
+            let mut offset = HEADER.len() as u64;
+            for (i, synset) in synsets.iter().enumerate() {
+                if normalize_pos(synset.0) == pos {
+                    offsets[i] = offset;
+                    offset += line(i, &vec![0; synsets.len()]).len() as u64;
+                }
+            }
+        }
+
+        for &(pos, suffix) in PARTS_OF_SPEECH.iter() {
+            let mut data = String::from(HEADER);
+            let mut index: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+            for (i, synset) in synsets.iter().enumerate() {
+                if normalize_pos(synset.0) == pos {
+                    data.push_str(&line(i, &offsets));
+                    for word in synset.1.iter() {
+                        index
+                            .entry(word.to_lowercase())
+                            .or_default()
+                            .push(offsets[i]);
+                    }
+                }
+            }
+
+            let mut index_file = String::from(HEADER);
+            for (lemma, offsets) in index.iter() {
+                let offsets: Vec<String> = offsets
+                    .iter()
+                    .map(|offset| format!("{offset:08}"))
+                    .collect();
+                index_file.push_str(&format!(
+                    "{lemma} {pos} {count} 0 {count} 0 {}  \n",
+                    offsets.join(" "),
+                    count = offsets.len(),
+                ));
+            }
+            fs::write(dir.join(format!("data.{suffix}")), data).expect("Data should be written");
+            fs::write(dir.join(format!("index.{suffix}")), index_file)
+                .expect("Index should be written");
+        }
+        fs::write(dir.join("noun.exc"), "geese goose\n").expect("Exceptions should be written");
+    }
+
+    /// Writes a tiny database, which lasts as long as its directory.
+    fn test_wordnet(name: &str) -> (TempDir, WordNet) {
+        let dir = TempDir::new(&format!("wordnet-{name}"));
+        write_wordnet(
+            &dir,
+            &[
+                (
+                    'n',
+                    &["dog", "domestic_dog"],
+                    &[
+                        ("@", 1, "0000"),
+                        ("~", 2, "0000"),
+                        ("%p", 3, "0000"),
+                        ("+", 5, "0101"),
+                    ],
+                    "a domesticated canid",
+                ),
+                (
+                    'n',
+                    &["canine", "canid"],
+                    &[("~", 0, "0000")],
+                    "a carnivore",
+                ),
+                ('n', &["puppy"], &[("@", 0, "0000")], "a young dog"),
+                ('n', &["flag"], &[("#p", 0, "0000")], "a dog's tail"),
+                ('n', &["goose"], &[], "a web-footed bird"),
+                (
+                    'v',
+                    &["dog", "tail"],
+                    &[("+", 0, "0101")],
+                    "go after with the intent to catch",
+                ),
+                ('a', &["hot"], &[("!", 7, "0101")], "used of physical heat"),
+                (
+                    'a',
+                    &["cold"],
+                    &[("!", 6, "0101")],
+                    "having a low temperature",
+                ),
+            ],
+        );
+        let wordnet = WordNet::new(&dir.to_string_lossy()).expect("Database should open");
+        (dir, wordnet)
+    }
+
+    #[test]
+    fn synsets_should_parse() {
+        let synset = parse_synset(
+            "00001740 03 n 02 entity 0 galore(ip) 0 001 ~ 00002137 n 0a01 | that which exists  \n",
+        )
+        .expect("Synset should parse");
+        assert_eq!(
+            vec!["entity".to_string(), "galore".to_string()],
+            synset.words
+        );
+        assert_eq!("that which exists", synset.gloss);
+        assert_eq!(
+            vec![Pointer {
+                symbol: "~".to_string(),
+                offset: 2137,
+                pos: 'n',
+                source: 10,
+                target: 1,
+            }],
+            synset.pointers
+        );
+    }
+
+    #[test]
+    fn index_should_be_searched() {
+        let (_dir, wordnet) = test_wordnet("index");
+        for lemma in [
+            "canid",
+            "canine",
+            "dog",
+            "domestic_dog",
+            "flag",
+            "goose",
+            "puppy",
+        ] {
+            assert_eq!(
+                1,
+                wordnet
+                    .index(lemma, "noun")
+                    .expect("Database should be readable")
+                    .len(),
+                "{lemma} should be indexed"
+            );
+        }
+        for lemma in ["a", "do", "dogs", "zebra"] {
+            assert!(wordnet
+                .index(lemma, "noun")
+                .expect("Database should be readable")
+                .is_empty());
+        }
+        assert_eq!(
+            vec!["goose".to_string()],
+            wordnet.exceptions("geese", "noun")
+        );
+    }
+
+    #[test]
+    fn wordnet_relations_test() {
+        let (_dir, wordnet) = test_wordnet("relations");
+        let relations = |word: &str, semantic: Semantic| {
+            wordnet
+                .relations(word, &semantic)
+                .expect("Database should be readable")
+        };
+
+        assert_eq!(
+            vec!["domestic dog".to_string(), "tail".to_string()],
+            relations("dog", Semantic::Synonym)
+        );
+        assert_eq!(
+            vec!["canine".to_string(), "canid".to_string()],
+            relations("dog", Semantic::Hypernym)
+        );
+        assert_eq!(
+            vec!["puppy".to_string()],
+            relations("dog", Semantic::Hyponym)
+        );
+        assert_eq!(
+            vec!["flag".to_string()],
+            relations("dog", Semantic::Meronym)
+        );
+        assert_eq!(
+            vec!["dog".to_string()],
+            relations("dog", Semantic::DerivedForm)
+        );
+        assert_eq!(
+            vec!["cold".to_string()],
+            relations("hot", Semantic::Antonym)
+        );
+        assert!(relations("domestic dog", Semantic::DerivedForm).is_empty());
+    }
+
+    #[test]
+    fn wordnet_definitions_test() {
+        let (_dir, wordnet) = test_wordnet("definitions");
+        let mut definitions = wordnet
+            .define("geese")
+            .expect("Database should be readable");
+        assert_eq!(1, definitions.len());
+        assert_eq!("geese", definitions[0].get_word());
+        let entry = crate::provider::Entry::from(definitions.remove(0));
+        let displayed = entry.display(Default::default()).to_string();
+        assert!(displayed.contains(": noun\n    1) a web-footed bird\n"));
+
+        let definitions = wordnet.define("hot").expect("Database should be readable");
+        assert_eq!(
+            vec!["cold".to_string()],
+            definitions[0].get_semantics(&Semantic::Antonym)
+        );
+        assert!(wordnet
+            .define("cat")
+            .expect("Database should be readable")
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn wordnet_entries_should_be_related() {
+        use crate::provider::{DictionaryProvider, WordNetProvider};
+
+        let (dir, _) = test_wordnet("provider");
+        let provider = WordNetProvider::open(&dir.to_string_lossy()).expect("Database should open");
+        let lookup = provider
+            .lookup("dogs")
+            .await
+            .expect("Database should be readable");
+        assert_eq!("dog", lookup.lemma);
+        assert_eq!(
+            Some(vec!["canine".to_string(), "canid".to_string()]),
+            lookup.entries[0].get_semantics(&Semantic::Hypernym)
+        );
+        assert!(!provider
+            .lookup("cat")
+            .await
+            .expect("Database should be readable")
+            .is_found());
+    }
+}